@@ -0,0 +1,273 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+/// The Disciple of the Hand actions that can be used in a craft. Actions that
+/// need a Good or Excellent condition, e.g. Intensive Synthesis, aren't
+/// included, as the simulator assumes every step has a Normal condition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[allow(missing_docs)]
+pub enum Action {
+    BasicSynthesis,
+    BasicTouch,
+    MastersMend,
+    Observe,
+    WasteNot,
+    Veneration,
+    StandardTouch,
+    GreatStrides,
+    Innovation,
+    WasteNotII,
+    ByregotsBlessing,
+    MuscleMemory,
+    CarefulSynthesis,
+    Manipulation,
+    PrudentTouch,
+    Reflect,
+    PreparatoryTouch,
+    Groundwork,
+    DelicateSynthesis,
+    AdvancedTouch,
+    PrudentSynthesis,
+    TrainedFinesse,
+    RefinedTouch,
+    ImmaculateMend,
+    TrainedPerfection,
+}
+
+/// The error returned when parsing an unknown action name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseActionError(pub String);
+
+impl Action {
+    /// Every action, in the order they're learned.
+    pub const ALL: [Self; 25] = [
+        Self::BasicSynthesis,
+        Self::BasicTouch,
+        Self::MastersMend,
+        Self::Observe,
+        Self::WasteNot,
+        Self::Veneration,
+        Self::StandardTouch,
+        Self::GreatStrides,
+        Self::Innovation,
+        Self::WasteNotII,
+        Self::ByregotsBlessing,
+        Self::MuscleMemory,
+        Self::CarefulSynthesis,
+        Self::Manipulation,
+        Self::PrudentTouch,
+        Self::Reflect,
+        Self::PreparatoryTouch,
+        Self::Groundwork,
+        Self::DelicateSynthesis,
+        Self::AdvancedTouch,
+        Self::PrudentSynthesis,
+        Self::TrainedFinesse,
+        Self::RefinedTouch,
+        Self::ImmaculateMend,
+        Self::TrainedPerfection,
+    ];
+
+    /// The name of the action in game.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::BasicSynthesis => "Basic Synthesis",
+            Self::BasicTouch => "Basic Touch",
+            Self::MastersMend => "Master's Mend",
+            Self::Observe => "Observe",
+            Self::WasteNot => "Waste Not",
+            Self::Veneration => "Veneration",
+            Self::StandardTouch => "Standard Touch",
+            Self::GreatStrides => "Great Strides",
+            Self::Innovation => "Innovation",
+            Self::WasteNotII => "Waste Not II",
+            Self::ByregotsBlessing => "Byregot's Blessing",
+            Self::MuscleMemory => "Muscle Memory",
+            Self::CarefulSynthesis => "Careful Synthesis",
+            Self::Manipulation => "Manipulation",
+            Self::PrudentTouch => "Prudent Touch",
+            Self::Reflect => "Reflect",
+            Self::PreparatoryTouch => "Preparatory Touch",
+            Self::Groundwork => "Groundwork",
+            Self::DelicateSynthesis => "Delicate Synthesis",
+            Self::AdvancedTouch => "Advanced Touch",
+            Self::PrudentSynthesis => "Prudent Synthesis",
+            Self::TrainedFinesse => "Trained Finesse",
+            Self::RefinedTouch => "Refined Touch",
+            Self::ImmaculateMend => "Immaculate Mend",
+            Self::TrainedPerfection => "Trained Perfection",
+        }
+    }
+
+    /// The crafter level the action is learned at.
+    pub fn level(self) -> u32 {
+        match self {
+            Self::BasicSynthesis => 1,
+            Self::BasicTouch => 5,
+            Self::MastersMend => 7,
+            Self::Observe => 13,
+            Self::WasteNot | Self::Veneration => 15,
+            Self::StandardTouch => 18,
+            Self::GreatStrides => 21,
+            Self::Innovation => 26,
+            Self::WasteNotII => 47,
+            Self::ByregotsBlessing => 50,
+            Self::MuscleMemory => 54,
+            Self::CarefulSynthesis => 62,
+            Self::Manipulation => 65,
+            Self::PrudentTouch => 66,
+            Self::Reflect => 69,
+            Self::PreparatoryTouch => 71,
+            Self::Groundwork => 72,
+            Self::DelicateSynthesis => 76,
+            Self::AdvancedTouch => 84,
+            Self::PrudentSynthesis => 88,
+            Self::TrainedFinesse => 90,
+            Self::RefinedTouch => 92,
+            Self::ImmaculateMend => 98,
+            Self::TrainedPerfection => 100,
+        }
+    }
+
+    /// The CP cost of the action, without a combo.
+    pub fn cp_cost(self) -> u32 {
+        match self {
+            Self::BasicSynthesis | Self::TrainedPerfection => 0,
+            Self::MuscleMemory | Self::Reflect => 6,
+            Self::Observe | Self::CarefulSynthesis => 7,
+            Self::BasicTouch
+            | Self::Veneration
+            | Self::Innovation
+            | Self::Groundwork
+            | Self::PrudentSynthesis => 18,
+            Self::ByregotsBlessing | Self::RefinedTouch => 24,
+            Self::PrudentTouch => 25,
+            Self::StandardTouch
+            | Self::GreatStrides
+            | Self::DelicateSynthesis
+            | Self::TrainedFinesse => 32,
+            Self::PreparatoryTouch => 40,
+            Self::AdvancedTouch => 46,
+            Self::WasteNot => 56,
+            Self::MastersMend => 88,
+            Self::Manipulation => 96,
+            Self::WasteNotII => 98,
+            Self::ImmaculateMend => 112,
+        }
+    }
+
+    /// The durability used by the action, before Waste Not.
+    pub fn durability_cost(self) -> u32 {
+        match self {
+            Self::PrudentTouch | Self::PrudentSynthesis => 5,
+            Self::BasicSynthesis
+            | Self::BasicTouch
+            | Self::StandardTouch
+            | Self::ByregotsBlessing
+            | Self::MuscleMemory
+            | Self::CarefulSynthesis
+            | Self::Reflect
+            | Self::DelicateSynthesis
+            | Self::AdvancedTouch
+            | Self::RefinedTouch => 10,
+            Self::PreparatoryTouch | Self::Groundwork => 20,
+            Self::MastersMend
+            | Self::Observe
+            | Self::WasteNot
+            | Self::Veneration
+            | Self::GreatStrides
+            | Self::Innovation
+            | Self::WasteNotII
+            | Self::Manipulation
+            | Self::TrainedFinesse
+            | Self::ImmaculateMend
+            | Self::TrainedPerfection => 0,
+        }
+    }
+
+    /// The percentage of a crafter's base progress the action adds, at a
+    /// crafter `level`. Some actions are improved by traits at higher levels.
+    pub fn progress_efficiency(self, level: u32) -> u32 {
+        match self {
+            Self::BasicSynthesis if level >= 31 => 120,
+            Self::DelicateSynthesis if level >= 94 => 150,
+            Self::BasicSynthesis | Self::DelicateSynthesis => 100,
+            Self::CarefulSynthesis if level >= 82 => 180,
+            Self::CarefulSynthesis => 150,
+            Self::PrudentSynthesis => 180,
+            Self::MuscleMemory => 300,
+            Self::Groundwork if level >= 86 => 360,
+            Self::Groundwork => 300,
+            _ => 0,
+        }
+    }
+
+    /// The percentage of a crafter's base quality the action adds, before
+    /// Byregot's Blessing is scaled by Inner Quiet.
+    pub fn quality_efficiency(self) -> u32 {
+        match self {
+            Self::BasicTouch
+            | Self::PrudentTouch
+            | Self::DelicateSynthesis
+            | Self::TrainedFinesse
+            | Self::RefinedTouch
+            | Self::ByregotsBlessing => 100,
+            Self::StandardTouch => 125,
+            Self::AdvancedTouch => 150,
+            Self::PreparatoryTouch => 200,
+            Self::Reflect => 300,
+            _ => 0,
+        }
+    }
+
+    /// The seconds to wait after the action in a macro, for its animation to
+    /// finish.
+    pub fn wait(self) -> u32 {
+        if self.progress_efficiency(0) > 0 || self.quality_efficiency() > 0 {
+            3
+        } else {
+            2
+        }
+    }
+
+    /// Whether the action can only be used on the first step of a craft.
+    pub fn is_first_step_only(self) -> bool {
+        matches!(self, Self::MuscleMemory | Self::Reflect)
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parses the in-game name of an action. Case, spaces, apostrophes,
+/// underscores & hyphens are ignored, so `byregots_blessing` is accepted.
+impl FromStr for Action {
+    type Err = ParseActionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalize = |name: &str| {
+            name.chars()
+                .filter(|c| !matches!(c, ' ' | '\'' | '_' | '-'))
+                .collect::<String>()
+                .to_lowercase()
+        };
+        let name = normalize(s);
+        Self::ALL
+            .into_iter()
+            .find(|action| normalize(action.name()) == name)
+            .ok_or_else(|| ParseActionError(s.trim().to_string()))
+    }
+}
+
+impl Display for ParseActionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown crafting action '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseActionError {}
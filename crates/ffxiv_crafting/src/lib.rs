@@ -0,0 +1,50 @@
+#![warn(unused_crate_dependencies)]
+#![warn(missing_docs)]
+
+//! This crate simulates crafting a recipe with a rotation of Disciple of the
+//! Hand actions. The simulation is deterministic: every step is assumed to
+//! have a Normal condition, so it reports the progress, quality, CP &
+//! durability a rotation reaches without any luck. A [Solver] can also search
+//! for the rotation completing a craft with the most quality, & write it as
+//! in-game macros.
+//!
+//! # Example
+//!
+//! ```rust
+//! use ffxiv_crafting::{Action, CraftRecipe, CrafterStats, Simulator};
+//!
+//! let recipe = CraftRecipe {
+//!     level: 90,
+//!     progress: 3_500,
+//!     quality: 7_200,
+//!     durability: 80,
+//!     progress_divider: 130,
+//!     quality_divider: 115,
+//!     progress_modifier: 80,
+//!     quality_modifier: 70,
+//! };
+//! let stats = CrafterStats {
+//!     level: 90,
+//!     craftsmanship: 3_000,
+//!     control: 3_000,
+//!     cp: 500,
+//! };
+//!
+//! let simulator = Simulator::new(recipe, stats).unwrap();
+//! let states = simulator
+//!     .simulate(&[Action::MuscleMemory, Action::Veneration, Action::Groundwork])
+//!     .unwrap();
+//! assert_eq!(states.len(), 3);
+//! assert_eq!(states[2].cp, 500 - 6 - 18 - 18);
+//! ```
+
+mod action;
+mod simulator;
+mod solver;
+
+pub use action::{Action, ParseActionError};
+pub use simulator::{
+    ActionError, Buffs, CraftError, CraftRecipe, CraftState, CrafterStats, Simulator,
+    TrainedPerfection, MAX_INNER_QUIET,
+};
+pub use solver::{macros, Solution, Solver, MACRO_LINES, MAX_STEPS};
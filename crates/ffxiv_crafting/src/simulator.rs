@@ -0,0 +1,667 @@
+use std::fmt::{self, Display, Formatter};
+
+use crate::Action;
+
+/// The most Inner Quiet stacks a crafter can have.
+pub const MAX_INNER_QUIET: u32 = 10;
+
+/// The stats of a crafter, including any food, medicine & gear.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CrafterStats {
+    /// The crafter's job level.
+    pub level: u32,
+    /// Increases the progress added by synthesis actions.
+    pub craftsmanship: u32,
+    /// Increases the quality added by touch actions.
+    pub control: u32,
+    /// The crafting points available at the start of a craft.
+    pub cp: u32,
+}
+
+/// The values of a recipe needed to simulate crafting it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CraftRecipe {
+    /// The job level of the recipe.
+    pub level: u32,
+    /// The progress needed to complete the craft.
+    pub progress: u32,
+    /// The maximum quality of the craft.
+    pub quality: u32,
+    /// The durability the craft starts with.
+    pub durability: u32,
+    /// Divides the crafter's craftsmanship when calculating progress.
+    pub progress_divider: u32,
+    /// Divides the crafter's control when calculating quality.
+    pub quality_divider: u32,
+    /// The percentage of progress gained when the crafter's level is no
+    /// higher than the recipe's.
+    pub progress_modifier: u32,
+    /// The percentage of quality gained when the crafter's level is no
+    /// higher than the recipe's.
+    pub quality_modifier: u32,
+}
+
+/// The state of a craft after some number of steps.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CraftState {
+    /// The number of actions used so far.
+    pub step: u32,
+    /// The progress made, up to the recipe's progress.
+    pub progress: u32,
+    /// The quality added, up to the recipe's quality.
+    pub quality: u32,
+    /// The remaining durability. The craft fails if this reaches 0 before
+    /// it's complete.
+    pub durability: u32,
+    /// The remaining crafting points.
+    pub cp: u32,
+    /// The active buffs.
+    pub buffs: Buffs,
+    combo: Combo,
+}
+
+/// The buffs active during a craft. Timed buffs count the steps they have
+/// left, and are inactive at 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Buffs {
+    /// Stacks increasing quality by 10% each, up to [`MAX_INNER_QUIET`].
+    pub inner_quiet: u32,
+    /// Halves the durability used by actions.
+    pub waste_not: u32,
+    /// Increases progress by 50%.
+    pub veneration: u32,
+    /// Doubles the quality of the next touch action.
+    pub great_strides: u32,
+    /// Increases quality by 50%.
+    pub innovation: u32,
+    /// Restores 5 durability after each step.
+    pub manipulation: u32,
+    /// Doubles the progress of the next synthesis action.
+    pub muscle_memory: u32,
+    /// Whether the next action that uses durability won't.
+    pub trained_perfection: TrainedPerfection,
+}
+
+/// Trained Perfection can be used once per craft, and lasts until an action
+/// that uses durability.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TrainedPerfection {
+    /// Trained Perfection hasn't been used.
+    #[default]
+    Unused,
+    /// The next action that uses durability won't.
+    Active,
+    /// Trained Perfection has been used up.
+    Used,
+}
+
+/// The previous action, for actions that cost less when combo'd.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+enum Combo {
+    #[default]
+    None,
+    BasicTouch,
+    StandardTouch,
+    Observe,
+}
+
+/// The reasons an action can't be used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActionError {
+    /// The craft is already complete or has failed.
+    CraftFinished,
+    /// The crafter's level is below the level the action is learned at.
+    LevelTooLow,
+    /// The crafter doesn't have enough CP left.
+    NotEnoughCp,
+    /// The action can only be used on the first step.
+    NotFirstStep,
+    /// Byregot's Blessing needs at least one Inner Quiet stack.
+    NoInnerQuiet,
+    /// Trained Finesse needs the maximum Inner Quiet stacks.
+    InnerQuietNotFull,
+    /// Prudent actions can't be used with Waste Not.
+    WasteNotActive,
+    /// Trained Perfection has already been used this craft.
+    AlreadyUsed,
+}
+
+/// The errors from simulating a craft.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CraftError {
+    /// The recipe is missing the values needed to simulate it, e.g. a
+    /// company workshop project.
+    InvalidRecipe,
+    /// The crafter's stats are too high for the progress or quality they add
+    /// to be calculated.
+    StatsTooHigh,
+    /// An action in the rotation couldn't be used.
+    Action {
+        /// The 1-based step of the action.
+        step: usize,
+        /// The action that couldn't be used.
+        action: Action,
+        /// Why the action couldn't be used.
+        error: ActionError,
+    },
+}
+
+/// Deterministically simulates crafting a recipe, assuming every step has a
+/// Normal condition.
+#[derive(Clone, Debug)]
+pub struct Simulator {
+    recipe: CraftRecipe,
+    stats: CrafterStats,
+    base_progress: u32,
+    base_quality: u32,
+}
+
+////////////////////////////////////////////////////////////
+
+impl Simulator {
+    /// Prepares to simulate a crafter with `stats` crafting `recipe`.
+    pub fn new(recipe: CraftRecipe, stats: CrafterStats) -> Result<Self, CraftError> {
+        if recipe.progress == 0 || recipe.progress_divider == 0 || recipe.quality_divider == 0 {
+            return Err(CraftError::InvalidRecipe);
+        }
+
+        let modifiers = match stats.level <= recipe.level {
+            true => (recipe.progress_modifier, recipe.quality_modifier),
+            false => (100, 100),
+        };
+        let base_progress =
+            base_value(stats.craftsmanship, recipe.progress_divider, 2, modifiers.0);
+        let base_quality = base_value(stats.control, recipe.quality_divider, 35, modifiers.1);
+
+        match (base_progress, base_quality) {
+            (Some(base_progress), Some(base_quality)) => Ok(Self {
+                recipe,
+                stats,
+                base_progress,
+                base_quality,
+            }),
+            _ => Err(CraftError::StatsTooHigh),
+        }
+    }
+
+    /// The recipe being crafted.
+    pub fn recipe(&self) -> &CraftRecipe {
+        &self.recipe
+    }
+
+    /// The stats of the crafter.
+    pub fn stats(&self) -> &CrafterStats {
+        &self.stats
+    }
+
+    /// The state of the craft before any actions are used.
+    pub fn initial_state(&self) -> CraftState {
+        CraftState {
+            durability: self.recipe.durability,
+            cp: self.stats.cp,
+            ..CraftState::default()
+        }
+    }
+
+    /// Uses each of `actions` in turn, returning the state after every step.
+    pub fn simulate(&self, actions: &[Action]) -> Result<Vec<CraftState>, CraftError> {
+        let mut state = self.initial_state();
+        let mut states = Vec::with_capacity(actions.len());
+        for (step, &action) in (1..).zip(actions) {
+            state = self
+                .apply(&state, action)
+                .map_err(|error| CraftError::Action {
+                    step,
+                    action,
+                    error,
+                })?;
+            states.push(state);
+        }
+        Ok(states)
+    }
+
+    /// Whether `action` can be used in `state`.
+    pub fn check(&self, state: &CraftState, action: Action) -> Result<(), ActionError> {
+        let buffs = &state.buffs;
+        if self.is_finished(state) {
+            Err(ActionError::CraftFinished)
+        } else if self.stats.level < action.level() {
+            Err(ActionError::LevelTooLow)
+        } else if state.cp < self.cp_cost(state, action) {
+            Err(ActionError::NotEnoughCp)
+        } else if action.is_first_step_only() && state.step > 0 {
+            Err(ActionError::NotFirstStep)
+        } else if action == Action::ByregotsBlessing && buffs.inner_quiet == 0 {
+            Err(ActionError::NoInnerQuiet)
+        } else if action == Action::TrainedFinesse && buffs.inner_quiet < MAX_INNER_QUIET {
+            Err(ActionError::InnerQuietNotFull)
+        } else if matches!(action, Action::PrudentTouch | Action::PrudentSynthesis)
+            && buffs.waste_not > 0
+        {
+            Err(ActionError::WasteNotActive)
+        } else if action == Action::TrainedPerfection
+            && buffs.trained_perfection != TrainedPerfection::Unused
+        {
+            Err(ActionError::AlreadyUsed)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Uses `action` in `state`, returning the state after the step.
+    pub fn apply(&self, state: &CraftState, action: Action) -> Result<CraftState, ActionError> {
+        self.check(state, action)?;
+
+        let mut next = *state;
+        let buffs = &mut next.buffs;
+        next.step += 1;
+        next.cp -= self.cp_cost(state, action);
+
+        let mut durability_cost = action.durability_cost();
+        if durability_cost > 0 && buffs.trained_perfection == TrainedPerfection::Active {
+            durability_cost = 0;
+            buffs.trained_perfection = TrainedPerfection::Used;
+        } else if buffs.waste_not > 0 {
+            durability_cost = durability_cost.div_ceil(2);
+        }
+
+        let mut progress_efficiency = action.progress_efficiency(self.stats.level);
+        if action == Action::Groundwork && state.durability < durability_cost {
+            progress_efficiency /= 2;
+        }
+        if progress_efficiency > 0 {
+            let multiplier = 100
+                + if buffs.veneration > 0 { 50 } else { 0 }
+                + if buffs.muscle_memory > 0 { 100 } else { 0 };
+            next.progress = next.progress.saturating_add(scale(
+                self.base_progress,
+                &[progress_efficiency, multiplier],
+            ));
+            buffs.muscle_memory = 0;
+        }
+
+        let mut quality_efficiency = action.quality_efficiency();
+        if action == Action::ByregotsBlessing {
+            quality_efficiency += 20 * buffs.inner_quiet;
+        }
+        if quality_efficiency > 0 {
+            let inner_quiet = 100 + 10 * buffs.inner_quiet;
+            let multiplier = 100
+                + if buffs.great_strides > 0 { 100 } else { 0 }
+                + if buffs.innovation > 0 { 50 } else { 0 };
+            next.quality = next.quality.saturating_add(scale(
+                self.base_quality,
+                &[quality_efficiency, inner_quiet, multiplier],
+            ));
+            buffs.great_strides = 0;
+
+            buffs.inner_quiet = match action {
+                Action::ByregotsBlessing => 0,
+                // Inner Quiet is a trait learned at level 11
+                _ if self.stats.level < 11 => 0,
+                Action::PreparatoryTouch | Action::Reflect => buffs.inner_quiet + 2,
+                Action::RefinedTouch if state.combo == Combo::BasicTouch => buffs.inner_quiet + 2,
+                _ => buffs.inner_quiet + 1,
+            }
+            .min(MAX_INNER_QUIET);
+        }
+        next.progress = next.progress.min(self.recipe.progress);
+        next.quality = next.quality.min(self.recipe.quality);
+
+        next.durability = next.durability.saturating_sub(durability_cost);
+        match action {
+            Action::MastersMend => {
+                next.durability = next
+                    .durability
+                    .saturating_add(30)
+                    .min(self.recipe.durability);
+            }
+            Action::ImmaculateMend => next.durability = self.recipe.durability,
+            _ => {}
+        }
+        // Manipulation doesn't restore durability on the step it's used
+        let is_finished = next.progress >= self.recipe.progress || next.durability == 0;
+        if buffs.manipulation > 0 && action != Action::Manipulation && !is_finished {
+            next.durability = (next.durability + 5).min(self.recipe.durability);
+        }
+
+        // Buffs from this action last from the next step
+        for turns in [
+            &mut buffs.waste_not,
+            &mut buffs.veneration,
+            &mut buffs.great_strides,
+            &mut buffs.innovation,
+            &mut buffs.manipulation,
+            &mut buffs.muscle_memory,
+        ] {
+            *turns = turns.saturating_sub(1);
+        }
+        match action {
+            Action::WasteNot => buffs.waste_not = 4,
+            Action::WasteNotII => buffs.waste_not = 8,
+            Action::Veneration => buffs.veneration = 4,
+            Action::GreatStrides => buffs.great_strides = 3,
+            Action::Innovation => buffs.innovation = 4,
+            Action::Manipulation => buffs.manipulation = 8,
+            Action::MuscleMemory => buffs.muscle_memory = 5,
+            Action::TrainedPerfection => buffs.trained_perfection = TrainedPerfection::Active,
+            _ => {}
+        }
+
+        next.combo = match action {
+            Action::BasicTouch => Combo::BasicTouch,
+            Action::StandardTouch if state.combo == Combo::BasicTouch => Combo::StandardTouch,
+            Action::Observe => Combo::Observe,
+            _ => Combo::None,
+        };
+
+        Ok(next)
+    }
+
+    /// The CP `action` costs in `state`, including any combo discount.
+    pub fn cp_cost(&self, state: &CraftState, action: Action) -> u32 {
+        match (action, state.combo) {
+            (Action::StandardTouch, Combo::BasicTouch)
+            | (Action::AdvancedTouch, Combo::StandardTouch | Combo::Observe) => 18,
+            _ => action.cp_cost(),
+        }
+    }
+
+    /// Whether the craft has reached its progress.
+    pub fn is_complete(&self, state: &CraftState) -> bool {
+        state.progress >= self.recipe.progress
+    }
+
+    /// Whether the craft is complete, or has failed by running out of
+    /// durability.
+    pub fn is_finished(&self, state: &CraftState) -> bool {
+        self.is_complete(state) || state.durability == 0
+    }
+}
+
+/// Scales `base` by each of `percentages`, rounding down at the end &
+/// saturating at `u32::MAX`.
+fn scale(base: u32, percentages: &[u32]) -> u32 {
+    let (value, divisor) = percentages
+        .iter()
+        .fold((u64::from(base), 1_u64), |(value, divisor), &percentage| {
+            (value * u64::from(percentage), divisor * 100)
+        });
+    u32::try_from(value / divisor).unwrap_or(u32::MAX)
+}
+
+/// The progress or quality added by an action with 100% efficiency, or `None`
+/// if it doesn't fit in a `u32`.
+fn base_value(stat: u32, divider: u32, bonus: u32, modifier: u32) -> Option<u32> {
+    let value = u64::from(stat) * 10 / u64::from(divider) + u64::from(bonus);
+    u32::try_from(value * u64::from(modifier) / 100).ok()
+}
+
+impl Display for ActionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::CraftFinished => "the craft has already finished",
+            Self::LevelTooLow => "the crafter's level is too low",
+            Self::NotEnoughCp => "there isn't enough CP left",
+            Self::NotFirstStep => "it can only be used on the first step",
+            Self::NoInnerQuiet => "it needs Inner Quiet",
+            Self::InnerQuietNotFull => "it needs 10 stacks of Inner Quiet",
+            Self::WasteNotActive => "it can't be used with Waste Not",
+            Self::AlreadyUsed => "it can only be used once per craft",
+        })
+    }
+}
+
+impl Display for CraftError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidRecipe => write!(f, "The recipe can't be crafted by a crafter"),
+            Self::StatsTooHigh => write!(f, "The crafter's stats are too high to simulate"),
+            Self::Action {
+                step,
+                action,
+                error,
+            } => write!(f, "Can't use {action} at step {step}, as {error}"),
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
+
+impl std::error::Error for CraftError {}
+
+////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECIPE: CraftRecipe = CraftRecipe {
+        level: 100,
+        progress: 3_400,
+        quality: 7_200,
+        durability: 80,
+        progress_divider: 130,
+        quality_divider: 115,
+        progress_modifier: 80,
+        quality_modifier: 70,
+    };
+
+    const STATS: CrafterStats = CrafterStats {
+        level: 100,
+        craftsmanship: 3_000,
+        control: 3_000,
+        cp: 500,
+    };
+
+    fn simulate(actions: &[Action]) -> Result<CraftState, CraftError> {
+        let simulator = Simulator::new(RECIPE, STATS)?;
+        Ok(simulator
+            .simulate(actions)?
+            .pop()
+            .unwrap_or_else(|| simulator.initial_state()))
+    }
+
+    #[test]
+    fn test_base_values() {
+        // 3000 * 10 / 130 + 2 = 232, then 80%
+        let state = simulate(&[Action::BasicSynthesis]).unwrap();
+        assert_eq!(state.progress, 185 * 120 / 100);
+        assert_eq!(state.durability, 70);
+        assert_eq!(state.cp, 500);
+
+        // 3000 * 10 / 115 + 35 = 295, then 70%
+        let state = simulate(&[Action::BasicTouch]).unwrap();
+        assert_eq!(state.quality, 206);
+        assert_eq!(state.buffs.inner_quiet, 1);
+
+        // Crafters above the recipe's level aren't penalized
+        let stats = CrafterStats {
+            level: 101,
+            ..STATS
+        };
+        let simulator = Simulator::new(RECIPE, stats).unwrap();
+        let state = simulator.simulate(&[Action::BasicSynthesis]).unwrap()[0];
+        assert_eq!(state.progress, 232 * 120 / 100);
+    }
+
+    #[test]
+    fn test_buffs() {
+        let state = simulate(&[Action::Veneration, Action::BasicSynthesis]).unwrap();
+        assert_eq!(state.progress, 185 * 120 * 150 / 10_000);
+
+        let state = simulate(&[Action::MuscleMemory, Action::BasicSynthesis]).unwrap();
+        assert_eq!(state.progress, 185 * 3 + 185 * 120 * 200 / 10_000);
+        assert_eq!(state.buffs.muscle_memory, 0);
+
+        // The second touch has 1 stack of Inner Quiet
+        let state = simulate(&[Action::BasicTouch, Action::BasicTouch]).unwrap();
+        assert_eq!(state.quality, 206 + 206 * 110 / 100);
+
+        let state = simulate(&[
+            Action::BasicTouch,
+            Action::BasicTouch,
+            Action::GreatStrides,
+            Action::Innovation,
+            Action::ByregotsBlessing,
+        ])
+        .unwrap();
+        assert_eq!(
+            state.quality,
+            206 + 206 * 110 / 100 + 206 * 140 * 120 * 250 / 1_000_000
+        );
+        assert_eq!(state.buffs.inner_quiet, 0);
+        assert_eq!(state.buffs.great_strides, 0);
+        assert_eq!(state.buffs.innovation, 3);
+
+        // Veneration lasts 4 steps, including the one after it's used
+        let actions = [Action::Veneration, Action::Observe, Action::Observe];
+        let state = simulate(&actions).unwrap();
+        assert_eq!(state.buffs.veneration, 2);
+    }
+
+    #[test]
+    fn test_durability() {
+        let state = simulate(&[Action::WasteNot, Action::Groundwork]).unwrap();
+        assert_eq!(state.durability, 70);
+
+        let state = simulate(&[Action::Manipulation, Action::BasicSynthesis]).unwrap();
+        assert_eq!(state.durability, 75);
+
+        let actions = [Action::Groundwork, Action::Groundwork, Action::MastersMend];
+        assert_eq!(simulate(&actions).unwrap().durability, 70);
+
+        let actions = [
+            Action::TrainedPerfection,
+            Action::Observe,
+            Action::Groundwork,
+        ];
+        let state = simulate(&actions).unwrap();
+        assert_eq!(state.durability, 80);
+        assert_eq!(state.buffs.trained_perfection, TrainedPerfection::Used);
+    }
+
+    #[test]
+    fn test_combos() {
+        let actions = [
+            Action::BasicTouch,
+            Action::StandardTouch,
+            Action::AdvancedTouch,
+        ];
+        assert_eq!(simulate(&actions).unwrap().cp, 500 - 18 * 3);
+
+        let actions = [Action::Observe, Action::AdvancedTouch];
+        assert_eq!(simulate(&actions).unwrap().cp, 500 - 7 - 18);
+
+        let actions = [Action::StandardTouch, Action::AdvancedTouch];
+        assert_eq!(simulate(&actions).unwrap().cp, 500 - 32 - 46);
+
+        let actions = [Action::BasicTouch, Action::RefinedTouch];
+        assert_eq!(simulate(&actions).unwrap().buffs.inner_quiet, 3);
+    }
+
+    #[test]
+    fn test_completion() {
+        let mut actions = vec![Action::MuscleMemory, Action::Veneration];
+        actions.extend([Action::CarefulSynthesis; 5]);
+        assert_eq!(simulate(&actions).unwrap().progress, 3_217);
+
+        // Progress is capped at the recipe's
+        actions.push(Action::BasicSynthesis);
+        let state = simulate(&actions).unwrap();
+        assert_eq!(state.progress, RECIPE.progress);
+        assert_eq!(state.durability, 10);
+
+        actions.push(Action::BasicSynthesis);
+        let err = simulate(&actions).unwrap_err();
+        assert_eq!(
+            err,
+            CraftError::Action {
+                step: 9,
+                action: Action::BasicSynthesis,
+                error: ActionError::CraftFinished,
+            }
+        );
+
+        // 4 Groundworks use all 80 durability, failing the craft
+        let err = simulate(&[Action::Groundwork; 5]).unwrap_err();
+        assert!(matches!(
+            err,
+            CraftError::Action {
+                step: 5,
+                error: ActionError::CraftFinished,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_unusable_actions() {
+        let error = |actions: &[Action]| match simulate(actions) {
+            Err(CraftError::Action { error, .. }) => Some(error),
+            _ => None,
+        };
+
+        assert_eq!(
+            error(&[Action::Observe, Action::Reflect]),
+            Some(ActionError::NotFirstStep)
+        );
+        assert_eq!(
+            error(&[Action::ByregotsBlessing]),
+            Some(ActionError::NoInnerQuiet)
+        );
+        assert_eq!(
+            error(&[Action::TrainedFinesse]),
+            Some(ActionError::InnerQuietNotFull)
+        );
+        assert_eq!(
+            error(&[Action::WasteNot, Action::PrudentTouch]),
+            Some(ActionError::WasteNotActive)
+        );
+        assert_eq!(
+            error(&[Action::TrainedPerfection, Action::TrainedPerfection]),
+            Some(ActionError::AlreadyUsed)
+        );
+        assert_eq!(
+            error(&[Action::Manipulation; 6]),
+            Some(ActionError::NotEnoughCp)
+        );
+
+        let stats = CrafterStats { level: 97, ..STATS };
+        let simulator = Simulator::new(RECIPE, stats).unwrap();
+        assert_eq!(
+            simulator.check(&simulator.initial_state(), Action::ImmaculateMend),
+            Err(ActionError::LevelTooLow)
+        );
+        assert_eq!(
+            Simulator::new(CraftRecipe::default(), STATS).unwrap_err(),
+            CraftError::InvalidRecipe
+        );
+        let recipe = CraftRecipe {
+            progress_divider: 1,
+            ..RECIPE
+        };
+        let stats = CrafterStats {
+            craftsmanship: u32::MAX,
+            ..STATS
+        };
+        assert_eq!(
+            Simulator::new(recipe, stats).unwrap_err(),
+            CraftError::StatsTooHigh
+        );
+        let simulator = Simulator::new(RECIPE, stats).unwrap();
+        let state = simulator.simulate(&[Action::BasicSynthesis]).unwrap();
+        assert_eq!(state.last().unwrap().progress, RECIPE.progress);
+    }
+
+    #[test]
+    fn test_parse_action() {
+        assert_eq!("Byregot's Blessing".parse(), Ok(Action::ByregotsBlessing));
+        assert_eq!("byregots_blessing".parse(), Ok(Action::ByregotsBlessing));
+        assert_eq!("waste-not-ii".parse(), Ok(Action::WasteNotII));
+        assert!("Hasty Touch ".parse::<Action>().is_err());
+        for action in Action::ALL {
+            assert_eq!(action.name().parse(), Ok(action));
+        }
+    }
+}
@@ -0,0 +1,332 @@
+use std::{
+    collections::HashSet,
+    time::{Duration, Instant},
+};
+
+use crate::{Action, CraftState, Simulator};
+
+/// The most actions a rotation may use.
+pub const MAX_STEPS: usize = 60;
+
+/// The number of lines in an in-game macro.
+pub const MACRO_LINES: usize = 15;
+
+const INITIAL_BEAM_WIDTH: usize = 32;
+const MAX_BEAM_WIDTH: usize = 8_192;
+
+/// A rotation found by the [`Solver`], along with the state of the craft
+/// after using it.
+#[derive(Clone, Debug)]
+pub struct Solution {
+    /// The actions to use, in order.
+    pub actions: Vec<Action>,
+    /// The state of the craft after every action has been used.
+    pub state: CraftState,
+}
+
+/// Searches for a rotation that completes a craft with as much quality as
+/// possible.
+///
+/// The search is a beam search over every usable action, ranking states by
+/// their quality & progress. Only states that a greedy run of synthesis
+/// actions can still complete are kept, & each of those completions is a
+/// candidate rotation. The beam starts narrow & doubles in width for as long
+/// as the time budget allows, keeping the best rotation found.
+#[derive(Clone, Debug)]
+pub struct Solver {
+    simulator: Simulator,
+    time_budget: Duration,
+}
+
+struct Node {
+    state: CraftState,
+    actions: Vec<Action>,
+}
+
+////////////////////////////////////////////////////////////
+
+impl Solver {
+    /// Prepares to solve the craft of `simulator`, spending up to
+    /// `time_budget` on the search.
+    pub fn new(simulator: Simulator, time_budget: Duration) -> Self {
+        Self {
+            simulator,
+            time_budget,
+        }
+    }
+
+    /// Finds the rotation reaching the recipe's progress with the most
+    /// quality, or `None` if no rotation completing the craft was found.
+    pub fn solve(&self) -> Option<Solution> {
+        let deadline = Instant::now() + self.time_budget;
+        let mut best: Option<Solution> = None;
+        let mut beam_width = INITIAL_BEAM_WIDTH;
+        loop {
+            if let Some(solution) = self.search(beam_width, deadline) {
+                Self::keep_best(&mut best, solution);
+            }
+
+            let quality = best.as_ref().map(|best| best.state.quality);
+            if quality == Some(self.simulator.recipe().quality)
+                || beam_width >= MAX_BEAM_WIDTH
+                || Instant::now() >= deadline
+            {
+                return best;
+            }
+            beam_width *= 2;
+        }
+    }
+
+    fn search(&self, beam_width: usize, deadline: Instant) -> Option<Solution> {
+        let mut best: Option<Solution> = None;
+        let mut beam = vec![Node {
+            state: self.simulator.initial_state(),
+            actions: Vec::new(),
+        }];
+
+        for _ in 0..MAX_STEPS {
+            if beam.is_empty() || Instant::now() >= deadline {
+                break;
+            }
+
+            let mut seen = HashSet::new();
+            let mut candidates = Vec::new();
+            for node in &beam {
+                for action in Action::ALL {
+                    if !self.is_useful(&node.state, action) {
+                        continue;
+                    }
+                    let Ok(state) = self.simulator.apply(&node.state, action) else {
+                        continue;
+                    };
+                    if self.simulator.is_finished(&state) && !self.simulator.is_complete(&state)
+                        || !seen.insert(state)
+                    {
+                        continue;
+                    }
+
+                    let mut actions = node.actions.clone();
+                    actions.push(action);
+                    candidates.push(Node { state, actions });
+                }
+            }
+            candidates.sort_by(|a, b| self.score(&b.state).total_cmp(&self.score(&a.state)));
+
+            // Only states that can still be completed are kept, & completing
+            // each one is a candidate solution
+            beam = Vec::with_capacity(beam_width);
+            for node in candidates {
+                if beam.len() >= beam_width {
+                    break;
+                }
+                let Some(solution) = self.finish(&node) else {
+                    continue;
+                };
+                Self::keep_best(&mut best, solution);
+                if !self.simulator.is_complete(&node.state) {
+                    beam.push(node);
+                }
+            }
+        }
+
+        best
+    }
+
+    /// Greedily completes the craft from `node` with synthesis actions,
+    /// using Veneration & Master's Mend when they're needed.
+    fn finish(&self, node: &Node) -> Option<Solution> {
+        const SYNTHESES: [Action; 4] = [
+            Action::Groundwork,
+            Action::CarefulSynthesis,
+            Action::PrudentSynthesis,
+            Action::BasicSynthesis,
+        ];
+
+        let simulator = &self.simulator;
+        let mut state = node.state;
+        let mut actions = node.actions.clone();
+        while !simulator.is_complete(&state) {
+            if actions.len() >= MAX_STEPS {
+                return None;
+            }
+
+            let syntheses = SYNTHESES
+                .into_iter()
+                .filter_map(|action| Some((action, simulator.apply(&state, action).ok()?)))
+                .collect::<Vec<_>>();
+            let completing = syntheses
+                .iter()
+                .filter(|(_, next)| simulator.is_complete(next))
+                .max_by_key(|(_, next)| (next.durability, next.cp));
+            let progressing = syntheses
+                .iter()
+                .filter(|(_, next)| !simulator.is_finished(next))
+                .max_by_key(|(_, next)| (next.progress, next.cp));
+
+            let (action, next) = if let Some(&completing) = completing {
+                completing
+            } else if let Some(next) = (state.buffs.veneration == 0)
+                .then(|| simulator.apply(&state, Action::Veneration).ok())
+                .flatten()
+            {
+                (Action::Veneration, next)
+            } else if let Some(&progressing) = progressing {
+                progressing
+            } else {
+                (
+                    Action::MastersMend,
+                    simulator.apply(&state, Action::MastersMend).ok()?,
+                )
+            };
+            actions.push(action);
+            state = next;
+        }
+
+        Some(Solution { actions, state })
+    }
+
+    /// Replaces `best` with `solution` if it has more quality, or the same
+    /// quality in fewer steps.
+    fn keep_best(best: &mut Option<Solution>, solution: Solution) {
+        let is_better = match best {
+            None => true,
+            Some(best) => {
+                (solution.state.quality, best.state.step)
+                    > (best.state.quality, solution.state.step)
+            }
+        };
+        if is_better {
+            *best = Some(solution);
+        }
+    }
+
+    /// Skips actions that can't improve on the state, to narrow the search.
+    fn is_useful(&self, state: &CraftState, action: Action) -> bool {
+        let recipe = self.simulator.recipe();
+        let quality_maxed = state.quality >= recipe.quality;
+        match action {
+            // Observe only helps by comboing into Advanced Touch
+            Action::Observe => {
+                !quality_maxed && self.simulator.stats().level >= Action::AdvancedTouch.level()
+            }
+            Action::MastersMend => state.durability + 30 <= recipe.durability,
+            Action::ImmaculateMend => state.durability + 30 < recipe.durability,
+            Action::GreatStrides | Action::Innovation => !quality_maxed,
+            _ if action.quality_efficiency() > 0 && action.progress_efficiency(0) == 0 => {
+                !quality_maxed
+            }
+            _ => true,
+        }
+    }
+
+    /// Ranks states in the beam, favouring quality over progress.
+    #[allow(clippy::cast_precision_loss)]
+    fn score(&self, state: &CraftState) -> f32 {
+        let recipe = self.simulator.recipe();
+        let stats = self.simulator.stats();
+        let fraction = |value: u32, max: u32| value as f32 / max.max(1) as f32;
+        fraction(state.quality, recipe.quality)
+            + 0.3 * fraction(state.progress, recipe.progress)
+            + 0.05 * fraction(state.durability, recipe.durability)
+            + 0.05 * fraction(state.cp, stats.cp)
+            + 0.01 * state.buffs.inner_quiet as f32
+    }
+}
+
+impl Solution {
+    /// The rotation as in-game macros of up to [`MACRO_LINES`] lines.
+    pub fn macros(&self) -> Vec<String> {
+        macros(&self.actions)
+    }
+}
+
+/// Writes `actions` as in-game `/ac` macros of up to [`MACRO_LINES`] lines,
+/// waiting for each action's animation to finish.
+pub fn macros(actions: &[Action]) -> Vec<String> {
+    actions
+        .chunks(MACRO_LINES)
+        .map(|actions| {
+            actions
+                .iter()
+                .map(|action| format!("/ac \"{}\" <wait.{}>", action.name(), action.wait()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect()
+}
+
+////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CraftRecipe, CrafterStats};
+
+    const RECIPE: CraftRecipe = CraftRecipe {
+        level: 90,
+        progress: 3_500,
+        quality: 7_200,
+        durability: 80,
+        progress_divider: 130,
+        quality_divider: 115,
+        progress_modifier: 80,
+        quality_modifier: 70,
+    };
+
+    const STATS: CrafterStats = CrafterStats {
+        level: 90,
+        craftsmanship: 3_000,
+        control: 3_000,
+        cp: 500,
+    };
+
+    #[test]
+    fn test_solve() {
+        let simulator = Simulator::new(RECIPE, STATS).unwrap();
+        let solver = Solver::new(simulator.clone(), Duration::from_millis(500));
+        let solution = solver.solve().unwrap();
+
+        let states = simulator.simulate(&solution.actions).unwrap();
+        assert_eq!(states.last(), Some(&solution.state));
+        assert!(simulator.is_complete(&solution.state));
+
+        // Better than a rotation that only uses Basic Touch for quality
+        let actions = [
+            Action::MuscleMemory,
+            Action::Veneration,
+            Action::Groundwork,
+            Action::Groundwork,
+            Action::BasicTouch,
+            Action::CarefulSynthesis,
+        ];
+        let naive = simulator.simulate(&actions).unwrap();
+        assert!(simulator.is_complete(naive.last().unwrap()));
+        assert!(solution.state.quality > naive.last().unwrap().quality);
+    }
+
+    #[test]
+    fn test_solve_impossible() {
+        let recipe = CraftRecipe {
+            progress: 100_000,
+            ..RECIPE
+        };
+        let simulator = Simulator::new(recipe, STATS).unwrap();
+        let solver = Solver::new(simulator, Duration::from_millis(100));
+        assert!(solver.solve().is_none());
+    }
+
+    #[test]
+    fn test_macros() {
+        let mut actions = vec![Action::ByregotsBlessing, Action::Veneration];
+        actions.extend([Action::BasicSynthesis; 14]);
+        let macros = macros(&actions);
+
+        assert_eq!(macros.len(), 2);
+        assert_eq!(macros[0].lines().count(), MACRO_LINES);
+        assert_eq!(macros[1], "/ac \"Basic Synthesis\" <wait.3>");
+
+        let mut lines = macros[0].lines();
+        assert_eq!(lines.next(), Some("/ac \"Byregot's Blessing\" <wait.3>"));
+        assert_eq!(lines.next(), Some("/ac \"Veneration\" <wait.2>"));
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+
+use crate::{tables::CollectableRewardTable, ItemDB, ItemDBError, ItemId};
+
+/// The rewards for turning in a collectable item.
+#[derive(Clone, Debug)]
+pub struct CollectableReward {
+    /// Where the item is turned in.
+    pub source: RewardSource,
+    /// The item_id of the collectable item.
+    pub item_id: u32,
+    /// The rewards for each collectability tier, from lowest to highest.
+    pub tiers: Vec<RewardTier>,
+}
+
+/// The rewards for a collectable with at least a certain collectability.
+#[derive(Clone, Debug)]
+pub struct RewardTier {
+    /// The minimum collectability for the tier.
+    pub collectability: u32,
+    /// The scrip rewarded, e.g. `Purple Crafters' Scrip`.
+    pub currency: String,
+    /// The number of scrips rewarded.
+    pub scrips: u32,
+    /// The experience rewarded. Custom deliveries & the collectables
+    /// appraiser scale their experience with the character's level, so this is
+    /// always 0 for them.
+    pub experience: u32,
+}
+
+/// The places that collectable items may be turned in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RewardSource {
+    /// Custom deliveries to a client NPC, from `SatisfactionSupply`.
+    CustomDelivery,
+    /// The Ishgard restoration, from `HWDCrafterSupply`.
+    IshgardRestoration,
+    /// The collectables appraiser's scrip exchange, from `CollectablesShopItem`.
+    CollectablesShop,
+}
+
+impl RewardSource {
+    pub(crate) fn id(self) -> u32 {
+        match self {
+            Self::CustomDelivery => 0,
+            Self::IshgardRestoration => 1,
+            Self::CollectablesShop => 2,
+        }
+    }
+
+    pub(crate) fn from_id(id: u32) -> Option<Self> {
+        match id {
+            0 => Some(Self::CustomDelivery),
+            1 => Some(Self::IshgardRestoration),
+            2 => Some(Self::CollectablesShop),
+            _ => None,
+        }
+    }
+}
+
+impl ItemDB {
+    /// Returns the rewards for every collectable turned in to `source`,
+    /// ordered by item id.
+    pub async fn collectable_rewards(
+        &self,
+        source: RewardSource,
+    ) -> Result<Vec<CollectableReward>, ItemDBError> {
+        Ok(CollectableRewardTable::new(self).by_source(source).await?)
+    }
+
+    /// Returns the rewards for turning in each of the `ids` that are
+    /// collectables, from every source, ordered by item id.
+    pub async fn collectable_rewards_by_item_ids<I: ItemId>(
+        &self,
+        ids: &[I],
+    ) -> Result<Vec<CollectableReward>, ItemDBError> {
+        Ok(CollectableRewardTable::new(self).by_item_ids(ids).await?)
+    }
+}
@@ -1,44 +1,184 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::ItemDBError;
+
 macro_rules! parse {
-    (@ $info:tt $id:ident = U[$start:literal .. $end:literal]; $($tail:tt)*) => {
+    (@ $pos:tt $info:tt $id:ident = U[$start:literal .. $end:literal]; $($tail:tt)*) => {
         let $id = $info[$start..$end].into_iter().map(|v| v.parse::<u32>().unwrap_or_default()).collect::<Vec<_>>();
-        crate::csv_parse::parse!(@ $info $($tail)*)
+        crate::csv_parse::parse!(@ $pos $info $($tail)*)
     };
 
-    (@ $info:tt $id:ident = U[$index:expr]; $($tail:tt)*) => {
-        let $id = $info[$index].parse::<u32>()?;
-        crate::csv_parse::parse!(@ $info $($tail)*)
+    (@ ($file:ident, $record:ident) $info:tt $id:ident = U[$index:expr]; $($tail:tt)*) => {
+        let $id = crate::csv_parse::parse_u32($file, $record.position(), &$info, $index)?;
+        crate::csv_parse::parse!(@ ($file, $record) $info $($tail)*)
     };
 
-    (@ $info:tt $id:ident = S[$index:expr]; $($tail:tt)*) => {
+    (@ $pos:tt $info:tt $id:ident = S[$index:expr]; $($tail:tt)*) => {
         let $id = $info[$index].to_string();
-        crate::csv_parse::parse!(@ $info $($tail)*)
+        crate::csv_parse::parse!(@ $pos $info $($tail)*)
     };
 
-    (@ $info:tt $id:ident = B[$index:expr]; $($tail:tt)*) => {
+    (@ $pos:tt $info:tt $id:ident = B[$index:expr]; $($tail:tt)*) => {
         let $id = $info[$index] == "True";
-        crate::csv_parse::parse!(@ $info $($tail)*)
+        crate::csv_parse::parse!(@ $pos $info $($tail)*)
     };
 
-    (@ $info:tt $($tail:tt)+) => { $($tail)+ };
+    (@ $pos:tt $info:tt $($tail:tt)+) => { $($tail)+ };
 
-    (@ $info:tt ) => {}
+    (@ $pos:tt $info:tt ) => {}
 }
 
+/// Parses each record of a datamining csv file, after the field names & types.
+/// Records that can't be parsed fail with an [ItemDBError::Import] error.
 macro_rules! csv_parse {
-    ($reader:expr, $info:ident => { $($tail:tt)* }) => {
+    ($file:expr, $reader:expr, $info:ident => { $($tail:tt)* }) => {
+        let csv_file: &str = $file;
         let mut reader = csv::ReaderBuilder::new().from_reader($reader);
-        for (line, record) in reader.records().enumerate() {
-            if line < 2 {
+        for (index, record) in reader.records().enumerate() {
+            if index < 2 {
                 continue;
             }
-            let record = record?;
+            let record = record.map_err(|err| crate::csv_parse::record_error(csv_file, index, err))?;
             let $info = record.into_iter().collect::<Vec<_>>();
 
-            crate::csv_parse::parse!(@ $info $($tail)*);
+            crate::csv_parse::parse!(@ (csv_file, record) $info $($tail)*);
         }
     };
 
-    ($reader:expr => { $($tail:tt)* }) => { csv_parse!($reader, info => { $($tail)* }); }
+    ($file:expr, $reader:expr => { $($tail:tt)* }) => { csv_parse!($file, $reader, info => { $($tail)* }); }
 }
 
 pub(crate) use {csv_parse, parse};
+
+/// Parses the number in a column of a csv record.
+pub(crate) fn parse_u32(
+    file: &str,
+    position: Option<&csv::Position>,
+    info: &[&str],
+    column: usize,
+) -> Result<u32, ItemDBError> {
+    // Records read from a file always have a position
+    let line = position.map_or(0, csv::Position::line);
+    let value = info.get(column).ok_or_else(|| {
+        ItemDBError::import(
+            file,
+            line,
+            Some(column),
+            "the record is missing this column",
+        )
+    })?;
+    value
+        .parse::<u32>()
+        .map_err(|err| ItemDBError::import(file, line, Some(column), format!("'{value}': {err}")))
+}
+
+/// Converts an error from reading the record at `index` of a csv file.
+pub(crate) fn record_error(file: &str, index: usize, err: csv::Error) -> ItemDBError {
+    let line = err.position().map_or(index as u64 + 2, csv::Position::line);
+    let column = match err.kind() {
+        csv::ErrorKind::Utf8 { err, .. } => Some(err.field()),
+        _ => None,
+    };
+    ItemDBError::import(file, line, column, err)
+}
+
+/// Parses the values of `columns` from a csv record, treating blanks as 0.
+pub(crate) fn column_values(info: &[&str], columns: &[usize]) -> Vec<u32> {
+    columns
+        .iter()
+        .map(|&column| info[column].parse::<u32>().unwrap_or_default())
+        .collect()
+}
+
+/// Looks up columns by their field names, from the record after the index
+/// header of a datamining csv file. Used for files whose column order is less
+/// certain than that of e.g. Item.csv.
+pub(crate) struct CsvColumns {
+    file_name: &'static str,
+    names: HashMap<String, usize>,
+}
+
+impl CsvColumns {
+    pub fn new(file_name: &'static str, contents: &str) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new().from_reader(contents.as_bytes());
+        let names = match reader.records().next() {
+            Some(record) => record
+                .map_err(|err| record_error(file_name, 0, err))?
+                .into_iter()
+                .enumerate()
+                .map(|(index, name)| (name.to_string(), index))
+                .collect(),
+            None => HashMap::new(),
+        };
+        Ok(Self { file_name, names })
+    }
+
+    pub fn get(&self, name: &str) -> Result<usize> {
+        self.names.get(name).copied().ok_or_else(|| {
+            let reason = format!("Couldn't find column '{name}'");
+            ItemDBError::import(self.file_name, 2, None, reason).into()
+        })
+    }
+
+    /// The columns of an array field, e.g. `Part[0]`, `Part[1]`, ...
+    pub fn array(&self, name: &str, count: usize) -> Result<Vec<usize>> {
+        (0..count)
+            .map(|index| self.get(&format!("{name}[{index}]")))
+            .collect()
+    }
+
+    /// The number of columns in an array field, for arrays whose length
+    /// varies between game versions.
+    pub fn array_len(&self, name: &str) -> usize {
+        (0..)
+            .take_while(|index| self.names.contains_key(&format!("{name}[{index}]")))
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_ids(contents: &str) -> Result<Vec<(u32, u32)>> {
+        let mut ids = Vec::new();
+        csv_parse!("Test.csv", contents.as_bytes() => {
+            id = U[0];
+            other_id = U[2];
+            ids.push((id, other_id));
+        });
+        Ok(ids)
+    }
+
+    #[test]
+    fn test_import_errors() {
+        let contents = "key,0,1\n#,Name,Other\nint32,str,int32\n1,a,10\n2,b,x\n";
+        let err = parse_ids(contents).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ItemDBError>(),
+            Some(&ItemDBError::import(
+                "Test.csv",
+                5,
+                Some(2),
+                "'x': invalid digit found in string"
+            ))
+        );
+
+        let contents = "key,0,1\n#,Name,Other\nint32,str,int32\n1,a,10\n2,b\n";
+        let err = parse_ids(contents).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ItemDBError>(),
+            Some(ItemDBError::Import {
+                line: 5,
+                column: None,
+                ..
+            })
+        ));
+        assert_eq!(
+            parse_ids(&contents[..contents.len() - 5]).unwrap(),
+            vec![(1, 10)]
+        );
+    }
+}
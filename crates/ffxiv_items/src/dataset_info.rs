@@ -0,0 +1,59 @@
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset};
+
+use crate::{
+    tables::{DatasetFilesTable, UpdateTable},
+    ItemDB, ItemDBError,
+};
+
+/// Describes which game data the item database was built from.
+#[derive(Clone, Debug)]
+pub struct DatasetInfo {
+    /// The date of the most recent datamining commit seen on github, if the
+    /// database has ever been checked against github.
+    pub last_updated: Option<DateTime<FixedOffset>>,
+    /// Provenance for each of the csv files that the database is built from.
+    pub files: Vec<DatasetFile>,
+}
+
+/// Provenance information for a single imported csv file.
+#[derive(Clone, Debug)]
+pub struct DatasetFile {
+    /// The name of the file in the datamining repo, e.g. `Item.csv`.
+    pub file_name: String,
+    /// The datamining commit the file was downloaded from. This is unknown if
+    /// github couldn't be reached before the file was downloaded.
+    pub commit_sha: Option<String>,
+    /// The author date of `commit_sha`.
+    pub commit_date: Option<DateTime<FixedOffset>>,
+    /// When the file was downloaded.
+    pub downloaded_at: Option<DateTime<FixedOffset>>,
+    /// The number of data rows in the file.
+    pub row_count: Option<u32>,
+    /// The hex-encoded SHA-256 hash of the file contents.
+    pub content_hash: Option<String>,
+    /// Whether every table built from the file finished importing. If this is
+    /// false, the import failed partway through.
+    pub imported: bool,
+}
+
+impl ItemDB {
+    /// Returns information about which datamining commit & files the item
+    /// database was built from.
+    pub async fn dataset_info(&self) -> Result<DatasetInfo, ItemDBError> {
+        let update_table = UpdateTable::new(self);
+        let dataset_files = DatasetFilesTable::new(self);
+        update_table.create().await?;
+        dataset_files.create().await?;
+
+        let last_updated = match update_table.is_empty().await? {
+            true => None,
+            false => Some(update_table.last_updated().await?),
+        };
+
+        Ok(DatasetInfo {
+            last_updated,
+            files: dataset_files.all().await?,
+        })
+    }
+}
@@ -0,0 +1,237 @@
+use std::fmt::{self, Display, Formatter};
+
+use chrono::{DateTime, Utc};
+
+use crate::{QueryTooExpensive, SavedQueryError};
+
+/// The errors returned from [ItemDB](crate::ItemDB), split by the kinds of
+/// failures that callers may want to handle differently, e.g. to tell a user
+/// whether their query was at fault.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ItemDBError {
+    /// The database couldn't be reached, or the connection to it was lost.
+    Connection {
+        /// A description of the failure.
+        reason: String,
+    },
+    /// A query string couldn't be run, e.g. because of an invalid pattern.
+    Query {
+        /// The byte offset in the query string of the filter at fault.
+        offset: usize,
+        /// A description of the failure.
+        reason: String,
+    },
+    /// A query string was rejected for asking too much of the database.
+    QueryTooExpensive(QueryTooExpensive),
+    /// A saved query was rejected, or a query string uses an `@name` alias
+    /// that isn't saved.
+    SavedQuery(SavedQueryError),
+    /// A datamining csv file couldn't be imported.
+    Import {
+        /// The name of the csv file.
+        file: String,
+        /// The line of the file at fault, starting from 1.
+        line: u64,
+        /// The column at fault, starting from 0, if known.
+        column: Option<usize>,
+        /// A description of the failure.
+        reason: String,
+    },
+    /// Github refused a request because its rate limit was exceeded.
+    RateLimited {
+        /// When the rate limit resets, if github reported it.
+        reset: Option<DateTime<Utc>>,
+    },
+    /// Any other failure, e.g. a database error or a dataset that failed
+    /// validation.
+    Other {
+        /// A description of the failure, along with its causes.
+        reason: String,
+    },
+}
+
+// Connections that were refused or lost, which the server reports as
+// database errors rather than IO errors
+const ER_CON_COUNT_ERROR: u16 = 1040;
+const ER_ACCESS_DENIED_ERROR: u16 = 1045;
+const ER_SERVER_SHUTDOWN: u16 = 1053;
+
+impl ItemDBError {
+    /// Finds the kind of an error that passed through an [anyhow::Error], if
+    /// it's known. Database errors that weren't given a kind where they
+    /// happened, e.g. a connection lost partway through a query, are
+    /// classified here.
+    pub fn find(err: &anyhow::Error) -> Option<Self> {
+        err.chain().find_map(|err| {
+            if let Some(err) = err.downcast_ref::<Self>() {
+                return Some(err.clone());
+            }
+            if let Some(err) = err.downcast_ref::<QueryTooExpensive>() {
+                return Some(Self::QueryTooExpensive(err.clone()));
+            }
+            if let Some(err) = err.downcast_ref::<SavedQueryError>() {
+                return Some(Self::SavedQuery(err.clone()));
+            }
+            err.downcast_ref::<sqlx::Error>()
+                .filter(|err| is_connection_error(err))
+                .map(|err| Self::Connection {
+                    reason: err.to_string(),
+                })
+        })
+    }
+
+    /// Whether the query string was at fault, rather than the database.
+    pub fn is_query(&self) -> bool {
+        matches!(
+            self,
+            Self::Query { .. } | Self::QueryTooExpensive(_) | Self::SavedQuery(_)
+        )
+    }
+
+    /// An [Other](Self::Other) error.
+    pub(crate) fn other(reason: impl Display) -> Self {
+        Self::Other {
+            reason: reason.to_string(),
+        }
+    }
+
+    /// An [Import](Self::Import) error for a line of a csv file.
+    pub(crate) fn import(
+        file: &str,
+        line: u64,
+        column: Option<usize>,
+        reason: impl Display,
+    ) -> Self {
+        Self::Import {
+            file: file.to_string(),
+            line,
+            column,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+fn is_connection_error(err: &sqlx::Error) -> bool {
+    use sqlx::mysql::MySqlDatabaseError;
+
+    match err {
+        sqlx::Error::Io(_)
+        | sqlx::Error::Tls(_)
+        | sqlx::Error::PoolTimedOut
+        | sqlx::Error::PoolClosed
+        | sqlx::Error::WorkerCrashed => true,
+        sqlx::Error::Database(err) => {
+            err.try_downcast_ref::<MySqlDatabaseError>()
+                .is_some_and(|err| {
+                    matches!(
+                        err.number(),
+                        ER_CON_COUNT_ERROR | ER_ACCESS_DENIED_ERROR | ER_SERVER_SHUTDOWN
+                    )
+                })
+        }
+        _ => false,
+    }
+}
+
+impl Display for ItemDBError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Connection { reason } => write!(f, "Couldn't connect to the database: {reason}"),
+            Self::Query { offset, reason } => {
+                write!(f, "Invalid query at offset {offset}: {reason}")
+            }
+            Self::QueryTooExpensive(err) => err.fmt(f),
+            Self::SavedQuery(err) => err.fmt(f),
+            Self::Import {
+                file,
+                line,
+                column,
+                reason,
+            } => match column {
+                Some(column) => write!(
+                    f,
+                    "Couldn't import {file}, line {line}, column {column}: {reason}"
+                ),
+                None => write!(f, "Couldn't import {file}, line {line}: {reason}"),
+            },
+            Self::RateLimited { reset } => match reset {
+                Some(reset) => write!(f, "Github rate limit exceeded, resets at {reset}"),
+                None => write!(f, "Github rate limit exceeded"),
+            },
+            Self::Other { reason } => f.write_str(reason),
+        }
+    }
+}
+
+impl std::error::Error for ItemDBError {}
+
+impl From<anyhow::Error> for ItemDBError {
+    fn from(err: anyhow::Error) -> Self {
+        Self::find(&err).unwrap_or_else(|| Self::Other {
+            reason: format!("{err:#}"),
+        })
+    }
+}
+
+impl From<sqlx::Error> for ItemDBError {
+    fn from(err: sqlx::Error) -> Self {
+        anyhow::Error::from(err).into()
+    }
+}
+
+impl From<QueryTooExpensive> for ItemDBError {
+    fn from(err: QueryTooExpensive) -> Self {
+        Self::QueryTooExpensive(err)
+    }
+}
+
+impl From<SavedQueryError> for ItemDBError {
+    fn from(err: SavedQueryError) -> Self {
+        Self::SavedQuery(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn test_find() {
+        let err = anyhow::Error::from(ItemDBError::import("Item.csv", 3, Some(1), "bad"))
+            .context("Couldn't build the item table");
+        assert_eq!(
+            ItemDBError::find(&err),
+            Some(ItemDBError::import("Item.csv", 3, Some(1), "bad"))
+        );
+
+        let err = Err::<(), _>(sqlx::Error::PoolTimedOut)
+            .context("Couldn't fetch items")
+            .unwrap_err();
+        assert!(matches!(
+            ItemDBError::find(&err),
+            Some(ItemDBError::Connection { .. })
+        ));
+
+        let err = anyhow::Error::from(SavedQueryError::EmptyQuery).context("Couldn't save");
+        assert_eq!(
+            ItemDBError::find(&err),
+            Some(ItemDBError::SavedQuery(SavedQueryError::EmptyQuery))
+        );
+
+        let err = anyhow::Error::from(sqlx::Error::RowNotFound);
+        assert_eq!(ItemDBError::find(&err), None);
+    }
+
+    #[test]
+    fn test_from_anyhow() {
+        let err = anyhow::Error::from(sqlx::Error::RowNotFound).context("Couldn't fetch items");
+        let err = ItemDBError::from(err);
+        assert!(
+            matches!(&err, ItemDBError::Other { reason } if reason.starts_with("Couldn't fetch items: "))
+        );
+        assert!(!err.is_query());
+        assert!(ItemDBError::from(SavedQueryError::EmptyQuery).is_query());
+    }
+}
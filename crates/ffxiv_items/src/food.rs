@@ -0,0 +1,68 @@
+/// The effects of eating a meal or drinking a medicine.
+#[derive(Clone, Debug)]
+pub struct Food {
+    /// Whether the item is a meal or a medicine.
+    pub kind: FoodKind,
+    /// The stats the item raises.
+    pub effects: Vec<FoodEffect>,
+}
+
+/// The kinds of consumables with stat effects, which stack with each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FoodKind {
+    /// A meal, which grants the `Well Fed` status.
+    Meal,
+    /// A medicine, e.g. a tincture or a draught, which grants the
+    /// `Medicated` status.
+    Medicine,
+}
+
+/// The raise of a single stat from a meal or medicine.
+#[derive(Clone, Debug)]
+pub struct FoodEffect {
+    /// The stat raised, e.g. `CP`.
+    pub stat: String,
+    /// Whether `value` is a percent of the stat, capped at `max`, rather than
+    /// a flat raise.
+    pub is_relative: bool,
+    /// The raise from a normal quality item.
+    pub value: i32,
+    /// The largest raise from a normal quality item, if `is_relative`.
+    pub max: i32,
+    /// The raise from a high quality item.
+    pub value_hq: i32,
+    /// The largest raise from a high quality item, if `is_relative`.
+    pub max_hq: i32,
+}
+
+impl FoodKind {
+    pub(crate) fn id(self) -> u32 {
+        match self {
+            Self::Meal => 0,
+            Self::Medicine => 1,
+        }
+    }
+
+    pub(crate) fn from_id(id: u32) -> Option<Self> {
+        match id {
+            0 => Some(Self::Meal),
+            1 => Some(Self::Medicine),
+            _ => None,
+        }
+    }
+}
+
+impl FoodEffect {
+    /// The raise to a stat of `base` from eating the item, in normal or high
+    /// quality.
+    pub fn raise(&self, base: i32, is_hq: bool) -> i32 {
+        let (value, max) = match is_hq {
+            true => (self.value_hq, self.max_hq),
+            false => (self.value, self.max),
+        };
+        match self.is_relative {
+            true => (base * value / 100).min(max),
+            false => value,
+        }
+    }
+}
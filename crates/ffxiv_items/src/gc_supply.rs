@@ -0,0 +1,58 @@
+use anyhow::Result;
+
+use crate::{tables::GcSupplyDutyTable, ItemDB, ItemDBError};
+
+/// An item requested by a Grand Company supply or provisioning mission.
+#[derive(Clone, Debug)]
+pub struct GcSupplyDuty {
+    /// The character level of the mission.
+    pub level: u32,
+    /// The abbreviation of the job the item is requested from, e.g. `CUL`.
+    pub job: String,
+    /// Whether the item is requested by a supply or provisioning mission.
+    pub kind: GcSupplyKind,
+    /// The item_id of the requested item.
+    pub item_id: u32,
+    /// The number of items that must be turned in.
+    pub count: u32,
+    /// The experience rewarded for turning in the items. Doubled for high
+    /// quality items.
+    pub experience: u32,
+    /// The Grand Company seals rewarded for turning in the items. Doubled for
+    /// high quality items.
+    pub seals: u32,
+}
+
+/// The kinds of Grand Company missions that request items.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GcSupplyKind {
+    /// Supply missions, which request crafted items.
+    Supply,
+    /// Provisioning missions, which request gathered items.
+    Provisioning,
+}
+
+impl GcSupplyKind {
+    pub(crate) fn from_job(job: &str) -> Self {
+        match job {
+            "MIN" | "BTN" | "FSH" => Self::Provisioning,
+            _ => Self::Supply,
+        }
+    }
+}
+
+impl ItemDB {
+    /// Returns the items requested by Grand Company supply & provisioning
+    /// missions for a job, e.g. `CUL`, or for every job if `job` is `None`.
+    /// Rewards are based on the item level of each requested item.
+    pub async fn gc_supply_duties(
+        &self,
+        job: Option<&str>,
+    ) -> Result<Vec<GcSupplyDuty>, ItemDBError> {
+        let jobs = match job {
+            Some(job) => vec![job],
+            None => Vec::new(),
+        };
+        Ok(GcSupplyDutyTable::new(self).by_jobs(&jobs).await?)
+    }
+}
@@ -1,13 +1,16 @@
 use anyhow::{anyhow, bail, Result};
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, FixedOffset, TimeZone, Utc};
 
-use mock_traits::FileDownloader;
+use mock_traits::{DownloadResponse, FileDownloader};
+
+use crate::{tables::GithubCacheTable, ItemDB, ItemDBError};
 
 #[derive(serde::Deserialize)]
 struct CommitList(Vec<CommitParent>);
 
 #[derive(serde::Deserialize)]
 struct CommitParent {
+    sha: String,
     commit: Commit,
 }
 
@@ -21,10 +24,19 @@ struct CommitAuthor {
     date: String,
 }
 
-pub async fn last_updated_from_github<F: FileDownloader>(
-    file_name: &str,
-) -> Result<DateTime<FixedOffset>> {
-    let commits_str = download_commits::<F>(file_name).await?;
+/// The most recent datamining commit that touched a particular csv file.
+#[derive(Clone, Debug)]
+pub struct CommitInfo {
+    pub file_name: &'static str,
+    pub sha: String,
+    pub date: DateTime<FixedOffset>,
+}
+
+pub async fn latest_commit_from_github<F: FileDownloader>(
+    db: &ItemDB,
+    file_name: &'static str,
+) -> Result<CommitInfo> {
+    let commits_str = download_commits::<F>(db, file_name).await?;
     let commits: CommitList = match serde_json::from_str(&commits_str) {
         Ok(commits) => commits,
         Err(err) => {
@@ -33,12 +45,132 @@ pub async fn last_updated_from_github<F: FileDownloader>(
         }
     };
     let err = anyhow!("No commits found for '{file_name}'");
-    let date_str = &commits.0.first().ok_or(err)?.commit.author.date;
-    Ok(DateTime::parse_from_rfc3339(date_str)?)
+    let CommitParent { sha, commit } = commits.0.into_iter().next().ok_or(err)?;
+    Ok(CommitInfo {
+        file_name,
+        sha,
+        date: DateTime::parse_from_rfc3339(&commit.author.date)?,
+    })
+}
+
+/// Fetches the commit list for a file, reusing the cached response if github
+/// reports that it hasn't changed. Conditional requests that return a
+/// `304 Not Modified` don't count against the github rate limit.
+///
+/// If `GITHUB_TOKEN` is set, requests are authenticated, which raises the
+/// rate limit from 60 to 5000 requests per hour.
+async fn download_commits<F: FileDownloader>(db: &ItemDB, file_name: &str) -> Result<String> {
+    let url = format!(
+        "https://api.github.com/repos/xivapi/ffxiv-datamining/commits?path=csv/{file_name}&page=1&per_page=1"
+    );
+    let cache = GithubCacheTable::new(db);
+    let cached = cache.get(&url).await?;
+
+    let authorization = std::env::var("GITHUB_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty())
+        .map(|token| format!("Bearer {token}"));
+    let mut headers = vec![("Accept", "application/vnd.github+json")];
+    if let Some(authorization) = &authorization {
+        headers.push(("Authorization", authorization));
+    }
+    if let Some((etag, _)) = &cached {
+        headers.push(("If-None-Match", etag));
+    }
+
+    let response = F::download_with_headers(&url, &headers).await?;
+    match response.status {
+        304 => match cached {
+            Some((_, body)) => Ok(body),
+            None => bail!("Github returned 304 for '{file_name}' without a cached response"),
+        },
+        200..=299 => {
+            if let Some(etag) = response.header("etag") {
+                cache.set(&url, etag, &response.body).await?;
+            }
+            Ok(response.body)
+        }
+        403 | 429 if is_rate_limited(&response) => {
+            let reset = rate_limit_reset(&response, Utc::now());
+            Err(anyhow::Error::new(ItemDBError::RateLimited { reset })
+                .context(format!("Couldn't check '{file_name}' on github")))
+        }
+        status => bail!(
+            "Github returned {status} for '{file_name}': {}",
+            response.body
+        ),
+    }
 }
 
-pub async fn download_commits<F: FileDownloader>(file_name: &str) -> Result<String> {
-    F::download(&format!(
-        "https://api.github.com/repos/xivapi/ffxiv-datamining/commits?path=csv/{file_name}&page=1&page_per=1"
-    )).await
+/// Github's primary rate limit reports no remaining requests, while its
+/// secondary rate limits ask for a `retry-after` delay instead.
+fn is_rate_limited(response: &DownloadResponse) -> bool {
+    response.header("x-ratelimit-remaining") == Some("0")
+        || response.header("retry-after").is_some()
+}
+
+/// When a rate limited request can be retried. A `retry-after` delay, in
+/// seconds or as an HTTP date, takes precedence over the `x-ratelimit-reset`
+/// timestamp.
+fn rate_limit_reset(response: &DownloadResponse, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if let Some(retry_after) = response.header("retry-after") {
+        let retry_after = retry_after.trim();
+        return match retry_after.parse::<i64>() {
+            Ok(secs) => Some(now + chrono::Duration::seconds(secs)),
+            Err(_) => DateTime::parse_from_rfc2822(retry_after)
+                .ok()
+                .map(|date| date.with_timezone(&Utc)),
+        };
+    }
+    response
+        .header("x-ratelimit-reset")
+        .and_then(|reset| reset.parse::<i64>().ok())
+        .and_then(|reset| Utc.timestamp_opt(reset, 0).single())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16, headers: &[(&str, &str)]) -> DownloadResponse {
+        DownloadResponse {
+            status,
+            headers: headers
+                .iter()
+                .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+                .collect(),
+            body: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_reset() {
+        let now = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+
+        let primary = response(
+            403,
+            &[
+                ("x-ratelimit-remaining", "0"),
+                ("x-ratelimit-reset", "1700000600"),
+            ],
+        );
+        assert!(is_rate_limited(&primary));
+        assert_eq!(
+            rate_limit_reset(&primary, now),
+            Utc.timestamp_opt(1_700_000_600, 0).single()
+        );
+
+        let secondary = response(
+            429,
+            &[("x-ratelimit-remaining", "42"), ("retry-after", "60")],
+        );
+        assert!(is_rate_limited(&secondary));
+        assert_eq!(
+            rate_limit_reset(&secondary, now),
+            Some(now + chrono::Duration::seconds(60))
+        );
+
+        let forbidden = response(403, &[("x-ratelimit-remaining", "42")]);
+        assert!(!is_rate_limited(&forbidden));
+    }
 }
@@ -0,0 +1,251 @@
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, MutexGuard,
+    },
+};
+
+use chrono::{DateTime, FixedOffset};
+use lru::LruCache;
+
+use crate::{ItemInfo, Query};
+
+/// Hit & miss counts for the in-memory caches held by [ItemDB](crate::ItemDB).
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CacheStats {
+    /// Query strings answered from memory.
+    pub query_hits: u64,
+    /// Query strings that had to be run against the database.
+    pub query_misses: u64,
+    /// Items found in memory.
+    pub item_hits: u64,
+    /// Items that had to be fetched from the database.
+    pub item_misses: u64,
+    /// The number of query strings currently cached.
+    pub cached_queries: usize,
+    /// The number of items currently cached.
+    pub cached_items: usize,
+}
+
+/// Caches query results & item info in memory. The data only changes when
+/// the item tables are rebuilt, at which point the cache is cleared. Rebuilds
+/// by other processes are noticed through the persisted last updated date.
+pub(crate) struct ItemCache {
+    inner: Mutex<CacheInner>,
+    query_hits: AtomicU64,
+    query_misses: AtomicU64,
+    item_hits: AtomicU64,
+    item_misses: AtomicU64,
+}
+
+/// Values computed before a [clear](ItemCache::clear) may be stale, so each
+/// lookup records the generation it saw, and only inserts into that generation.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Generation(u64);
+
+#[derive(Clone, Default)]
+pub(crate) struct CachedQuery {
+    pub ids: Vec<u32>,
+    pub associated_ids: Option<Vec<u32>>,
+}
+
+struct CacheInner {
+    generation: u64,
+    /// The last updated date of the dataset that was cached.
+    last_updated: Option<DateTime<FixedOffset>>,
+    queries: LruCache<String, CachedQuery>,
+    items: LruCache<u32, ItemInfo>,
+}
+
+////////////////////////////////////////////////////////////
+
+// Enough for every example query & the items they return, many times over
+const MAX_CACHED_QUERIES: usize = 512;
+const MAX_CACHED_ITEMS: usize = 16_384;
+
+impl ItemCache {
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(CacheInner {
+                generation: 0,
+                last_updated: None,
+                queries: LruCache::new(NonZeroUsize::new(MAX_CACHED_QUERIES).unwrap()),
+                items: LruCache::new(NonZeroUsize::new(MAX_CACHED_ITEMS).unwrap()),
+            }),
+            query_hits: AtomicU64::new(0),
+            query_misses: AtomicU64::new(0),
+            item_hits: AtomicU64::new(0),
+            item_misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Queries that parse the same way share a cache entry, regardless of
+    /// spacing around tags & separators.
+    pub fn query_key(query_str: &str) -> String {
+        format!("{:?}", Query::parse_all_clauses(query_str.trim()))
+    }
+
+    pub fn query(&self, key: &str) -> (Option<CachedQuery>, Generation) {
+        let mut inner = self.lock();
+        let query = inner.queries.get(key).cloned();
+        let counter = match query {
+            Some(_) => &self.query_hits,
+            None => &self.query_misses,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        (query, Generation(inner.generation))
+    }
+
+    pub fn insert_query(&self, generation: Generation, key: String, query: CachedQuery) {
+        let mut inner = self.lock();
+        if Generation(inner.generation) == generation {
+            inner.queries.put(key, query);
+        }
+    }
+
+    /// Returns the cached items, and the ids that weren't found.
+    pub fn items(&self, ids: &[u32]) -> (Vec<ItemInfo>, Vec<u32>, Generation) {
+        let mut inner = self.lock();
+        let (mut items, mut missing) = (Vec::new(), Vec::new());
+        for &id in ids {
+            match inner.items.get(&id) {
+                Some(item) => items.push(item.clone()),
+                None => missing.push(id),
+            }
+        }
+        self.item_hits
+            .fetch_add(items.len() as u64, Ordering::Relaxed);
+        self.item_misses
+            .fetch_add(missing.len() as u64, Ordering::Relaxed);
+        (items, missing, Generation(inner.generation))
+    }
+
+    pub fn insert_items(&self, generation: Generation, items: &[ItemInfo]) {
+        let mut inner = self.lock();
+        if Generation(inner.generation) == generation {
+            for item in items {
+                inner.items.put(item.id, item.clone());
+            }
+        }
+    }
+
+    /// Empties the cache, e.g. when the item tables are rebuilt.
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.generation += 1;
+        inner.queries.clear();
+        inner.items.clear();
+    }
+
+    /// Empties the cache if the dataset's last updated date has changed since
+    /// it was last seen. Returns whether the cache was cleared.
+    pub fn clear_if_updated(&self, last_updated: Option<DateTime<FixedOffset>>) -> bool {
+        let mut inner = self.lock();
+        if inner.last_updated == last_updated {
+            return false;
+        }
+        inner.last_updated = last_updated;
+        inner.generation += 1;
+        inner.queries.clear();
+        inner.items.clear();
+        true
+    }
+
+    /// Empties only the cached queries, e.g. when a saved query changes.
+    pub fn clear_queries(&self) {
+        let mut inner = self.lock();
+        inner.generation += 1;
+        inner.queries.clear();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.lock();
+        CacheStats {
+            query_hits: self.query_hits.load(Ordering::Relaxed),
+            query_misses: self.query_misses.load(Ordering::Relaxed),
+            item_hits: self.item_hits.load(Ordering::Relaxed),
+            item_misses: self.item_misses.load(Ordering::Relaxed),
+            cached_queries: inner.queries.len(),
+            cached_items: inner.items.len(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CacheInner> {
+        // Nothing can be left half-updated by a panic, so poisoning is ignored
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl std::fmt::Debug for ItemCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ItemCache").field(&self.stats()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: u32) -> ItemInfo {
+        ItemInfo {
+            id,
+            name: format!("Item {id}"),
+            recipe: None,
+            stats: Vec::new(),
+            food: None,
+            is_marketable: true,
+        }
+    }
+
+    #[test]
+    fn test_cache() {
+        let cache = ItemCache::new();
+        let (_, _, generation) = cache.items(&[1, 2]);
+        cache.insert_items(generation, &[item(1), item(2)]);
+
+        let (items, missing, generation) = cache.items(&[1, 2, 3]);
+        assert_eq!(items.len(), 2);
+        assert_eq!(missing, vec![3]);
+
+        // Anything fetched before a clear is dropped, as it may be stale
+        cache.clear();
+        cache.insert_items(generation, &[item(3)]);
+        let (items, missing, _) = cache.items(&[1, 2, 3]);
+        assert!(items.is_empty());
+        assert_eq!(missing, vec![1, 2, 3]);
+
+        let stats = cache.stats();
+        assert_eq!((stats.item_hits, stats.item_misses), (2, 6));
+        assert_eq!(stats.cached_items, 0);
+    }
+
+    #[test]
+    fn test_clear_if_updated() {
+        let cache = ItemCache::new();
+        let date = DateTime::parse_from_rfc3339("2023-04-01T12:00:00+00:00").unwrap();
+        assert!(!cache.clear_if_updated(None));
+
+        let (_, _, generation) = cache.items(&[1]);
+        cache.insert_items(generation, &[item(1)]);
+        assert!(cache.clear_if_updated(Some(date)));
+        assert_eq!(cache.stats().cached_items, 0);
+
+        let (_, _, generation) = cache.items(&[1]);
+        cache.insert_items(generation, &[item(1)]);
+        assert!(!cache.clear_if_updated(Some(date)));
+        assert_eq!(cache.stats().cached_items, 1);
+    }
+
+    #[test]
+    fn test_query_key() {
+        assert_eq!(
+            ItemCache::query_key(":name Iron ,  :rlevel 1 | 50"),
+            ItemCache::query_key(":name Iron, :rlevel 1|50 ")
+        );
+        assert_ne!(
+            ItemCache::query_key(":name Iron"),
+            ItemCache::query_key(":name Iron Ore")
+        );
+    }
+}
@@ -1,14 +1,24 @@
-use std::marker::PhantomData;
+use std::{collections::HashSet, marker::PhantomData};
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, FixedOffset};
 use futures::try_join;
+use itertools::Itertools;
 use mock_traits::FileDownloader;
 use sqlx::MySqlPool;
 use tuple_conv::RepeatedTuple;
 
-use crate::tables::{
-    IngredientTable, InputIdsTable, ItemInfoTable, ItemInfoTableBuilder, RecipeTable,
-    RecipeTableBuilder, UiCategoryTable, UiCategoryTableBuilder, UpdateTable,
+use crate::{
+    tables::{
+        CollectableRewardTable, CollectableRewardTableBuilder, CompanyCraftSupplyTable,
+        CompanyCraftTable, CompanyCraftTableBuilder, DatasetFilesTable, FoodEffectTable,
+        GcSupplyDutyTable, GcSupplyDutyTableBuilder, GcSupplyRewardTable, GithubCacheTable,
+        IngredientTable, InputIdsTable, ItemInfoTable, ItemInfoTableBuilder, ItemStatTable,
+        MateriaJoinRateTable, MateriaTable, MateriaTableBuilder, RecipeTable, RecipeTableBuilder,
+        SavedQueryTable, SearchCategoryTable, SearchCategoryTableBuilder, UiCategoryTable,
+        UiCategoryTableBuilder, UpdateTable,
+    },
+    CacheStats, CommitInfo, ItemCache, ItemDBError,
 };
 
 /// The database used to look up information about items.
@@ -38,21 +48,80 @@ use crate::tables::{
 #[derive(Debug)]
 pub struct ItemDB {
     pub(super) pool: MySqlPool,
+    pub(crate) cache: ItemCache,
 }
 
 struct Tables<'a, F: FileDownloader> {
+    db: &'a ItemDB,
     items: ItemInfoTable<'a>,
     items_builder: ItemInfoTableBuilder<'a, F>,
+    item_stats: ItemStatTable<'a>,
+    food_effects: FoodEffectTable<'a>,
     recipes: RecipeTable<'a>,
     recipes_builder: RecipeTableBuilder<'a, F>,
     ingredients: IngredientTable<'a>,
     input_ids: InputIdsTable<'a>,
+    company_crafts: CompanyCraftTable<'a>,
+    company_crafts_builder: CompanyCraftTableBuilder<'a, F>,
+    company_craft_supplies: CompanyCraftSupplyTable<'a>,
+    gc_supply_duties: GcSupplyDutyTable<'a>,
+    gc_supply_duties_builder: GcSupplyDutyTableBuilder<'a, F>,
+    gc_supply_rewards: GcSupplyRewardTable<'a>,
+    collectable_rewards: CollectableRewardTable<'a>,
+    collectable_rewards_builder: CollectableRewardTableBuilder<'a, F>,
+    materia: MateriaTable<'a>,
+    materia_builder: MateriaTableBuilder<'a, F>,
+    materia_join_rates: MateriaJoinRateTable<'a>,
     ui_categories: UiCategoryTable<'a>,
     ui_categories_builder: UiCategoryTableBuilder<'a, F>,
+    search_categories: SearchCategoryTable<'a>,
+    search_categories_builder: SearchCategoryTableBuilder<'a, F>,
+    saved_queries: SavedQueryTable<'a>,
     update_table: UpdateTable<'a>,
+    dataset_files: DatasetFilesTable<'a>,
+    github_cache: GithubCacheTable<'a>,
     marker_f: PhantomData<fn() -> F>,
 }
 
+/// Newer data files found on github.
+struct DatasetUpdate {
+    /// The date of the newest commit.
+    date: DateTime<FixedOffset>,
+    commits: Vec<CommitInfo>,
+}
+
+/// Appended to the name of the database to name the one that new datasets are
+/// built in.
+const STAGING_SUFFIX: &str = "_staging";
+
+/// A database alongside the live one, that tables are built in before they're
+/// swapped in by [finish_staging](ItemDB::finish_staging).
+pub(crate) struct Staging {
+    pub(crate) db: ItemDB,
+    live_schema: String,
+    schema: String,
+}
+
+/// The tables that are built from the dataset, & replaced when it's updated.
+const DATASET_TABLES: [&str; 16] = [
+    DatasetFilesTable::SQL_TABLE_NAME,
+    ItemInfoTable::SQL_TABLE_NAME,
+    ItemStatTable::SQL_TABLE_NAME,
+    FoodEffectTable::SQL_TABLE_NAME,
+    RecipeTable::SQL_TABLE_NAME,
+    UiCategoryTable::SQL_TABLE_NAME,
+    SearchCategoryTable::SQL_TABLE_NAME,
+    IngredientTable::SQL_TABLE_NAME,
+    InputIdsTable::SQL_TABLE_NAME,
+    CompanyCraftTable::SQL_TABLE_NAME,
+    CompanyCraftSupplyTable::SQL_TABLE_NAME,
+    GcSupplyDutyTable::SQL_TABLE_NAME,
+    GcSupplyRewardTable::SQL_TABLE_NAME,
+    CollectableRewardTable::SQL_TABLE_NAME,
+    MateriaTable::SQL_TABLE_NAME,
+    MateriaJoinRateTable::SQL_TABLE_NAME,
+];
+
 ////////////////////////////////////////////////////////////
 
 impl ItemDB {
@@ -60,39 +129,260 @@ impl ItemDB {
     /// The connection string should likely be in the format of:
     ///
     /// `mysql://<user>:<password>@<server>:<port>/<database>`
-    pub async fn connect<S: AsRef<str>>(conn_string: S) -> Result<Self> {
-        let pool = MySqlPool::connect(conn_string.as_ref()).await?;
-        Ok(Self { pool })
+    ///
+    /// Fails with an [ItemDBError::Connection] error if the database can't be
+    /// reached.
+    pub async fn connect<S: AsRef<str>>(conn_string: S) -> Result<Self, ItemDBError> {
+        let pool = MySqlPool::connect(conn_string.as_ref())
+            .await
+            .map_err(|err| ItemDBError::Connection {
+                reason: err.to_string(),
+            })?;
+        Ok(Self {
+            pool,
+            cache: ItemCache::new(),
+        })
     }
 
     /// Ensures the item database is either created or updated.
     ///
     /// The files are pulled from <https://www.github.com/xivapi/ffxiv-datamining>.
     /// Additionally, github commit metadata is checked, but is heavily rate
-    /// limited to 60 attempts per IP per some time period. If this fails due to
-    /// excessive rate limiting, it's assumed that the data is up-to-date.
+    /// limited to 60 attempts per IP per hour, unless a `GITHUB_TOKEN` is set.
+    /// Responses are cached in the database, and only re-fetched when github
+    /// reports that they've changed, which doesn't count against the limit.
+    /// If the check still fails, it's assumed that the data is up-to-date.
     /// If you'd like to ensure the database is updated, you can simply delete
     /// the database or drop the tables.
-    pub async fn initialize<F: FileDownloader>(&self) -> Result<bool> {
+    ///
+    /// The tables are built as described in [update](Self::update). If some
+    /// are empty, e.g. on the first run, errors are returned, but otherwise
+    /// the current data is kept & served.
+    ///
+    /// Returns `true` if the database was rebuilt.
+    pub async fn initialize<F: FileDownloader>(&self) -> Result<bool, ItemDBError> {
         let tables = self.tables::<F>();
-        if cfg!(not(test)) {
-            // We're going to swallow errors with github, wrt: rate limiting
-            let _ = tables.check_updated_github().await;
+        let is_empty = tables.is_any_empty().await?;
+        let update = if cfg!(test) {
+            None
+        } else {
+            // Errors with github (e.g. rate limiting) shouldn't prevent serving the current data
+            tables.check_updated_github().await.unwrap_or_else(|err| {
+                log::warn!(target: "ffxiv_items", "Couldn't check github for updates: {err}");
+                None
+            })
+        };
+        if !is_empty && update.is_none() {
+            return Ok(false);
+        }
+
+        match self.rebuild_staged::<F>(update).await {
+            Ok(()) => Ok(true),
+            Err(err) if !is_empty => {
+                log::warn!(target: "ffxiv_items", "Couldn't update the item database: {err:#}");
+                Ok(false)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Checks github for newer data files and rebuilds the item database if
+    /// any are found, or if any of the tables are empty. Unlike
+    /// [initialize](Self::initialize), errors from github (including rate
+    /// limiting) are returned to the caller.
+    ///
+    /// Each check costs several github API requests, so this shouldn't be
+    /// called more often than every few minutes. The new tables are built &
+    /// validated in a separate staging database, named after this one with a
+    /// `_staging` suffix, so the database user needs privileges on it too.
+    /// They replace the current tables in a single `RENAME TABLE`, so queries
+    /// keep being served throughout. If the build fails, the current tables
+    /// are kept, and the update is attempted again on the next check.
+    ///
+    /// Returns `true` if the database was rebuilt.
+    pub async fn update<F: FileDownloader>(&self) -> Result<bool, ItemDBError> {
+        let tables = self.tables::<F>();
+        let update = tables.check_updated_github().await?;
+        if update.is_none() && !tables.is_any_empty().await? {
+            return Ok(false);
+        }
+        self.rebuild_staged::<F>(update).await?;
+        Ok(true)
+    }
+
+    /// Rebuilds all of the item tables, regardless of whether github reports
+    /// any newer data files. As with [update](Self::update), the current
+    /// tables are only replaced once the new ones are built & validated.
+    pub async fn rebuild<F: FileDownloader>(&self) -> Result<(), ItemDBError> {
+        Ok(self.rebuild_staged::<F>(None).await?)
+    }
+
+    /// Builds the dataset tables in the staging database & swaps them in. The
+    /// update date is only recorded once the new tables are in place, so that
+    /// a failed build is retried.
+    async fn rebuild_staged<F: FileDownloader>(&self, update: Option<DatasetUpdate>) -> Result<()> {
+        let staging = self.create_staging().await?;
+        let built = async {
+            let staging_tables = staging.db.tables::<F>();
+            staging_tables
+                .copy_commits(&staging.live_schema, update.as_ref())
+                .await?;
+            staging_tables.create().await?;
+            Ok(())
+        }
+        .await;
+        self.finish_staging(staging, built, &DATASET_TABLES).await?;
+
+        if let Some(update) = update {
+            self.tables::<F>().set_last_updated(&update.date).await?;
+        }
+        self.cache.clear();
+        Ok(())
+    }
+
+    /// Creates an empty staging database, named after this one with a
+    /// `_staging` suffix, & connects to it.
+    pub(crate) async fn create_staging(&self) -> Result<Staging> {
+        let live_schema = self.schema().await?;
+        let schema = format!("{live_schema}{STAGING_SUFFIX}");
+        sqlx::query(&format!("DROP DATABASE IF EXISTS `{schema}`"))
+            .execute(self)
+            .await?;
+        sqlx::query(&format!("CREATE DATABASE `{schema}`"))
+            .execute(self)
+            .await
+            .with_context(|| format!("Couldn't create the staging database '{schema}'"))?;
+
+        let options = (*self.pool.connect_options()).clone().database(&schema);
+        let db = match MySqlPool::connect_with(options).await {
+            Ok(pool) => Self {
+                pool,
+                cache: ItemCache::new(),
+            },
+            Err(err) => {
+                self.drop_staging(&schema).await;
+                return Err(err.into());
+            }
+        };
+        Ok(Staging {
+            db,
+            live_schema,
+            schema,
+        })
+    }
+
+    /// Swaps `tables` in from the staging database if they were `built`, then
+    /// drops the staging database. If they weren't, the live tables are left
+    /// untouched.
+    pub(crate) async fn finish_staging(
+        &self,
+        staging: Staging,
+        built: Result<()>,
+        tables: &[&str],
+    ) -> Result<()> {
+        staging.db.pool.close().await;
+        let result = match built {
+            Ok(()) => self.swap_in(&staging, tables).await,
+            Err(err) => Err(err),
+        };
+        // Once swapped, the staging database only holds the previous tables
+        self.drop_staging(&staging.schema).await;
+        result
+    }
+
+    /// Replaces the live `tables` with those built in the staging database, in
+    /// a single atomic statement. The previous tables are moved to the staging
+    /// database.
+    async fn swap_in(&self, staging: &Staging, tables: &[&str]) -> Result<()> {
+        let Staging {
+            live_schema,
+            schema: staging_schema,
+            ..
+        } = staging;
+        // Every table must exist to be renamed
+        self.create_snapshot_tables().await?;
+        let renames = tables
+            .iter()
+            .map(|table| {
+                format!(
+                    "`{live_schema}`.{table} TO `{staging_schema}`.{table}_previous, \
+                    `{staging_schema}`.{table} TO `{live_schema}`.{table}"
+                )
+            })
+            .join(", ");
+        sqlx::query(&format!("RENAME TABLE {renames}"))
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+
+    async fn drop_staging(&self, schema: &str) {
+        if let Err(err) = sqlx::query(&format!("DROP DATABASE IF EXISTS `{schema}`"))
+            .execute(self)
+            .await
+        {
+            log::warn!(target: "ffxiv_items", "Couldn't drop the staging database: {err}");
         }
-        tables.create().await
+    }
+
+    /// The name of the database that the pool is connected to.
+    async fn schema(&self) -> Result<String> {
+        let schema = sqlx::query_scalar::<_, Option<String>>("SELECT DATABASE()")
+            .fetch_one(self)
+            .await?;
+        schema.ok_or_else(|| anyhow!("The connection string doesn't name a database"))
+    }
+
+    /// Hit & miss counts for the in-memory query & item caches. The caches
+    /// are cleared whenever the item tables are rebuilt.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    /// Clears the in-memory caches if the item tables were rebuilt by another
+    /// process, e.g. the CLI or a second server instance, which is noticed by a
+    /// change to the persisted last updated date. Returns whether they were
+    /// cleared.
+    pub async fn sync_cache(&self) -> Result<bool, ItemDBError> {
+        let update_table = UpdateTable::new(self);
+        update_table.create().await?;
+        let last_updated = match update_table.is_empty().await? {
+            true => None,
+            false => Some(update_table.last_updated().await?),
+        };
+        Ok(self.cache.clear_if_updated(last_updated))
     }
 
     fn tables<F: FileDownloader>(&self) -> Tables<'_, F> {
         Tables {
+            db: self,
             items: ItemInfoTable::new(self),
             items_builder: ItemInfoTableBuilder::new(self),
+            item_stats: ItemStatTable::new(self),
+            food_effects: FoodEffectTable::new(self),
             recipes: RecipeTable::new(self),
             recipes_builder: RecipeTableBuilder::new(self),
             ingredients: IngredientTable::new(self),
             input_ids: InputIdsTable::new(self),
+            company_crafts: CompanyCraftTable::new(self),
+            company_crafts_builder: CompanyCraftTableBuilder::new(self),
+            company_craft_supplies: CompanyCraftSupplyTable::new(self),
+            gc_supply_duties: GcSupplyDutyTable::new(self),
+            gc_supply_duties_builder: GcSupplyDutyTableBuilder::new(self),
+            gc_supply_rewards: GcSupplyRewardTable::new(self),
+            collectable_rewards: CollectableRewardTable::new(self),
+            collectable_rewards_builder: CollectableRewardTableBuilder::new(self),
+            materia: MateriaTable::new(self),
+            materia_builder: MateriaTableBuilder::new(self),
+            materia_join_rates: MateriaJoinRateTable::new(self),
             ui_categories: UiCategoryTable::new(self),
             ui_categories_builder: UiCategoryTableBuilder::new(self),
+            search_categories: SearchCategoryTable::new(self),
+            search_categories_builder: SearchCategoryTableBuilder::new(self),
+            saved_queries: SavedQueryTable::new(self),
             update_table: UpdateTable::new(self),
+            dataset_files: DatasetFilesTable::new(self),
+            github_cache: GithubCacheTable::new(self),
             marker_f: PhantomData,
         }
     }
@@ -101,59 +391,158 @@ impl ItemDB {
 ////////////////////////////////////////////////////////////
 
 impl<F: FileDownloader> Tables<'_, F> {
-    async fn check_updated_github(&self) -> Result<()> {
-        let last_updated_github = try_join!(
-            self.items_builder.last_updated_github(),
-            self.recipes_builder.last_updated_github(),
-            self.ui_categories_builder.last_updated_github(),
+    /// Returns the newest commit date & the commits of each file, if github
+    /// has data files newer than those imported.
+    async fn check_updated_github(&self) -> Result<Option<DatasetUpdate>> {
+        self.github_cache.create().await?;
+        let commits = try_join!(
+            self.items_builder.latest_commits(),
+            self.recipes_builder.latest_commits(),
+            self.company_crafts_builder.latest_commits(),
+            self.gc_supply_duties_builder.latest_commits(),
+            self.collectable_rewards_builder.latest_commits(),
+            self.materia_builder.latest_commits(),
+            self.ui_categories_builder.latest_commits(),
+            self.search_categories_builder.latest_commits(),
         )?
         .to_vec()
         .into_iter()
-        .max()
-        .unwrap();
+        .flatten()
+        .collect::<Vec<_>>();
+        let date = commits.iter().map(|commit| commit.date).max().unwrap();
+
+        self.update_table.create().await?;
+        let is_new_update =
+            self.update_table.is_empty().await? || date > self.update_table.last_updated().await?;
+        Ok(is_new_update.then_some(DatasetUpdate { date, commits }))
+    }
 
-        let mut is_new_update = false;
+    async fn set_last_updated(&self, date: &DateTime<FixedOffset>) -> Result<()> {
         self.update_table.create().await?;
         if self.update_table.is_empty().await? {
-            self.update_table.insert(&last_updated_github).await?;
-            is_new_update = true;
+            self.update_table.insert(date).await
+        } else {
+            self.update_table.update(date).await
         }
+    }
 
-        let last_updated_db = self.update_table.last_updated().await?;
-        is_new_update |= last_updated_github > last_updated_db;
-        if is_new_update {
-            try_join!(
-                self.update_table.update(&last_updated_github),
-                self.items.drop(),
-                self.recipes.drop(),
-                self.ui_categories.drop(),
-                self.ingredients.drop(),
-                self.input_ids.drop(),
-            )?;
-        }
+    /// Whether any of the dataset tables are empty, creating any that are
+    /// missing.
+    async fn is_any_empty(&self) -> Result<bool> {
+        self.db.create_snapshot_tables().await?;
+        Ok(try_join!(
+            self.items.is_empty(),
+            self.item_stats.is_empty(),
+            self.food_effects.is_empty(),
+            self.recipes.is_empty(),
+            self.ui_categories.is_empty(),
+            self.search_categories.is_empty(),
+            self.ingredients.is_empty(),
+            self.input_ids.is_empty(),
+            self.company_crafts.is_empty(),
+            self.company_craft_supplies.is_empty(),
+            self.gc_supply_duties.is_empty(),
+            self.gc_supply_rewards.is_empty(),
+            self.collectable_rewards.is_empty(),
+            self.materia.is_empty(),
+            self.materia_join_rates.is_empty(),
+        )?
+        .to_vec()
+        .into_iter()
+        .any(|v| v))
+    }
 
+    /// Records the commits to download each file from, in a staging
+    /// database. Files that github didn't report newer commits for keep the
+    /// commits recorded in the live database.
+    async fn copy_commits(&self, live_schema: &str, update: Option<&DatasetUpdate>) -> Result<()> {
+        self.dataset_files.create().await?;
+        let table = DatasetFilesTable::SQL_TABLE_NAME;
+        sqlx::query(&format!(
+            "INSERT INTO {table} (file_name, commit_sha, commit_date)
+            SELECT file_name, commit_sha, commit_date FROM `{live_schema}`.{table}"
+        ))
+        .execute(self.db)
+        .await?;
+        for commit in update.iter().flat_map(|update| &update.commits) {
+            self.dataset_files.set_commit(commit).await?;
+        }
         Ok(())
     }
 
     async fn create(&self) -> Result<bool> {
-        let is_empty = try_join!(
-            self.create_items(),
-            self.create_ui_categories(),
-            self.create_recipes(),
-        )?
+        // Saved queries aren't built from the dataset, so are never dropped
+        try_join!(self.dataset_files.create(), self.saved_queries.create())?;
+        // Boxed, as holding every csv download at once makes for a large future
+        let is_empty = Box::pin(async {
+            try_join!(
+                self.create_items(),
+                self.create_ui_categories(),
+                self.create_search_categories(),
+                self.create_recipes(),
+                self.create_gc_supply(),
+                self.create_collectable_rewards(),
+                self.create_materia(),
+            )
+        })
+        .await?
         .to_vec()
         .into_iter()
         .any(|v| v);
+
+        // Rather than serve a half-broken dataset, fail before it's swapped in,
+        // so that the previous tables are kept & the import is attempted again
+        let report = self.db.validate().await?;
+        if !report.is_valid() {
+            bail!("Item database failed validation, keeping the previous tables: {report}");
+        }
+        if is_empty {
+            self.db.cache.clear();
+        }
+
         Ok(is_empty)
     }
 
     async fn create_items(&self) -> Result<bool> {
-        self.items.create().await?;
-        let is_empty = self.items.is_empty().await?;
-        if is_empty {
-            self.items_builder.initialize().await?;
+        let is_empty = try_join!(
+            {
+                self.items.create().await?;
+                self.items.is_empty()
+            },
+            {
+                self.item_stats.create().await?;
+                self.item_stats.is_empty()
+            },
+            {
+                self.food_effects.create().await?;
+                self.food_effects.is_empty()
+            },
+        )?
+        .to_vec()
+        .into_iter()
+        .any(|v| v);
+
+        if !is_empty {
+            return Ok(false);
         }
-        Ok(is_empty)
+
+        try_join!(
+            {
+                self.items.drop().await?;
+                self.items.create()
+            },
+            {
+                self.item_stats.drop().await?;
+                self.item_stats.create()
+            },
+            {
+                self.food_effects.drop().await?;
+                self.food_effects.create()
+            },
+        )?;
+        self.items_builder.initialize().await?;
+
+        Ok(true)
     }
 
     async fn create_ui_categories(&self) -> Result<bool> {
@@ -165,6 +554,92 @@ impl<F: FileDownloader> Tables<'_, F> {
         Ok(is_empty)
     }
 
+    async fn create_search_categories(&self) -> Result<bool> {
+        self.search_categories.create().await?;
+        let is_empty = self.search_categories.is_empty().await?;
+        if is_empty {
+            self.search_categories_builder.initialize().await?;
+        }
+        Ok(is_empty)
+    }
+
+    async fn create_collectable_rewards(&self) -> Result<bool> {
+        self.collectable_rewards.create().await?;
+        let is_empty = self.collectable_rewards.is_empty().await?;
+        if is_empty {
+            self.collectable_rewards_builder.initialize().await?;
+        }
+        Ok(is_empty)
+    }
+
+    async fn create_materia(&self) -> Result<bool> {
+        let is_empty = try_join!(
+            {
+                self.materia.create().await?;
+                self.materia.is_empty()
+            },
+            {
+                self.materia_join_rates.create().await?;
+                self.materia_join_rates.is_empty()
+            },
+        )?
+        .to_vec()
+        .into_iter()
+        .any(|v| v);
+
+        if !is_empty {
+            return Ok(false);
+        }
+
+        try_join!(
+            {
+                self.materia.drop().await?;
+                self.materia.create()
+            },
+            {
+                self.materia_join_rates.drop().await?;
+                self.materia_join_rates.create()
+            },
+        )?;
+        self.materia_builder.initialize().await?;
+
+        Ok(true)
+    }
+
+    async fn create_gc_supply(&self) -> Result<bool> {
+        let is_empty = try_join!(
+            {
+                self.gc_supply_duties.create().await?;
+                self.gc_supply_duties.is_empty()
+            },
+            {
+                self.gc_supply_rewards.create().await?;
+                self.gc_supply_rewards.is_empty()
+            },
+        )?
+        .to_vec()
+        .into_iter()
+        .any(|v| v);
+
+        if !is_empty {
+            return Ok(false);
+        }
+
+        try_join!(
+            {
+                self.gc_supply_duties.drop().await?;
+                self.gc_supply_duties.create()
+            },
+            {
+                self.gc_supply_rewards.drop().await?;
+                self.gc_supply_rewards.create()
+            },
+        )?;
+        self.gc_supply_duties_builder.initialize().await?;
+
+        Ok(true)
+    }
+
     async fn create_recipes(&self) -> Result<bool> {
         let is_empty = try_join!(
             {
@@ -179,6 +654,14 @@ impl<F: FileDownloader> Tables<'_, F> {
                 self.input_ids.create().await?;
                 self.input_ids.is_empty()
             },
+            {
+                self.company_crafts.create().await?;
+                self.company_crafts.is_empty()
+            },
+            {
+                self.company_craft_supplies.create().await?;
+                self.company_craft_supplies.is_empty()
+            },
         )?
         .to_vec()
         .into_iter()
@@ -188,8 +671,9 @@ impl<F: FileDownloader> Tables<'_, F> {
             return Ok(false);
         }
 
-        let (recipes, _, _, _) = try_join!(
-            RecipeTableBuilder::<'_, F>::download_recipe_info(),
+        let (mut recipes, company_crafts, _, _, _, _, _) = try_join!(
+            self.recipes_builder.download_recipe_info(),
+            self.company_crafts_builder.download(),
             {
                 self.recipes.drop().await?;
                 self.recipes.create()
@@ -201,13 +685,40 @@ impl<F: FileDownloader> Tables<'_, F> {
             {
                 self.input_ids.drop().await?;
                 self.input_ids.create()
+            },
+            {
+                self.company_crafts.drop().await?;
+                self.company_crafts.create()
+            },
+            {
+                self.company_craft_supplies.drop().await?;
+                self.company_craft_supplies.create()
             }
         )?;
 
+        // Workshop projects share the recipe tables, so that they're found by
+        // the recipe filters & trees. An item only has a single recipe, so
+        // any made by crafters take precedence.
+        let crafter_ids = recipes
+            .iter()
+            .map(|recipe| recipe.output.item_id)
+            .collect::<HashSet<_>>();
+        let company_crafts = company_crafts
+            .into_iter()
+            .filter(|craft| !crafter_ids.contains(&craft.recipe.output.item_id))
+            .collect::<Vec<_>>();
+        recipes.extend(company_crafts.iter().map(|craft| craft.recipe.clone()));
+
         try_join!(
             self.recipes_builder.initialize(&recipes),
             self.ingredients.initialize(&recipes),
             self.input_ids.initialize(&recipes),
+            self.company_crafts.initialize(&company_crafts),
+            self.company_craft_supplies.initialize(&company_crafts),
+        )?;
+        try_join!(
+            self.recipes_builder.mark_imported(),
+            self.company_crafts_builder.mark_imported(),
         )?;
 
         Ok(true)
@@ -228,7 +739,7 @@ mod executor {
     impl<'c> Executor<'c> for &ItemDB {
         type Database = MySql;
 
-        fn fetch_many<'e, 'q: 'e, E: 'q>(
+        fn fetch_many<'e, 'q: 'e, E>(
             self,
             query: E,
         ) -> BoxStream<
@@ -243,18 +754,18 @@ mod executor {
         >
         where
             'e: 'e,
-            E: Execute<'q, Self::Database>,
+            E: 'q + Execute<'q, Self::Database>,
         {
             self.pool.fetch_many(query)
         }
 
-        fn fetch_optional<'e, 'q: 'e, E: 'q>(
+        fn fetch_optional<'e, 'q: 'e, E>(
             self,
             query: E,
         ) -> BoxFuture<'e, Result<Option<<Self::Database as Database>::Row>, Error>>
         where
             'e: 'e,
-            E: Execute<'q, Self::Database>,
+            E: 'q + Execute<'q, Self::Database>,
         {
             self.pool.fetch_optional(query)
         }
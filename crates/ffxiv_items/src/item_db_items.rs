@@ -1,16 +1,29 @@
+use std::collections::BTreeMap;
+
 use anyhow::Result;
 use itertools::Itertools;
 
-use crate::{ItemId, ItemInfo};
+use crate::{CachedQuery, ItemCache, ItemDBError, ItemId, ItemInfo};
 
 use super::{
     tables::{InputIdsTable, ItemInfoTable},
     ItemDB,
 };
 
+/// What an item name resolved to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    /// The name matches a single item.
+    Unique(u32),
+    /// The name is shared by several items, ordered by id.
+    Ambiguous(Vec<u32>),
+    /// The name matches no item.
+    NotFound,
+}
+
 impl ItemDB {
     /// Returns all descendant ids of any of the `ids` that are recipes.
-    pub async fn associated_ids(&self, ids: &[u32]) -> Result<Vec<u32>> {
+    pub async fn associated_ids(&self, ids: &[u32]) -> Result<Vec<u32>, ItemDBError> {
         let mut all_ids = InputIdsTable::new(self).by_item_ids(ids).await?;
         all_ids.extend(ids);
 
@@ -19,9 +32,48 @@ impl ItemDB {
         Ok(all_ids)
     }
 
-    /// Returns [ItemInfo] for each of the `ids` passed in.
-    pub async fn items_from_ids<I: ItemId>(&self, ids: &[I]) -> Result<Vec<ItemInfo>> {
-        ItemInfoTable::new(self).by_item_ids(ids).await
+    /// Returns [ItemInfo] for each of the `ids` passed in, ordered by id.
+    /// Items are cached in memory.
+    pub async fn items_from_ids<I: ItemId>(&self, ids: &[I]) -> Result<Vec<ItemInfo>, ItemDBError> {
+        let ids = ids.iter().map(I::item_id).sorted().dedup().collect_vec();
+        let (mut items, missing, generation) = self.cache.items(&ids);
+        if !missing.is_empty() {
+            let fetched = ItemInfoTable::new(self).by_item_ids(&missing).await?;
+            self.cache.insert_items(generation, &fetched);
+            items.extend(fetched);
+            items.sort_by_key(|item| item.id);
+        }
+        Ok(items)
+    }
+
+    /// Looks up the ids of each of `names`, matching item names exactly but
+    /// ignoring case.
+    pub async fn resolve_names(
+        &self,
+        names: &[&str],
+    ) -> Result<Vec<(String, Resolution)>, ItemDBError> {
+        let names = names.iter().map(|name| name.trim()).collect_vec();
+        if names.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let unique_names = names.iter().copied().unique().collect_vec();
+        let mut ids = BTreeMap::<String, Vec<u32>>::new();
+        for (id, name) in ItemInfoTable::new(self).by_names(&unique_names).await? {
+            ids.entry(name.to_lowercase()).or_default().push(id);
+        }
+
+        Ok(names
+            .into_iter()
+            .map(|name| {
+                let resolution = match ids.get(&name.to_lowercase()) {
+                    Some(ids) if ids.len() == 1 => Resolution::Unique(ids[0]),
+                    Some(ids) => Resolution::Ambiguous(ids.iter().copied().sorted().collect()),
+                    None => Resolution::NotFound,
+                };
+                (name.to_string(), resolution)
+            })
+            .collect())
     }
 
     /// Returns top-level ids, descendant ids and [ItemInfo] data for an input
@@ -29,9 +81,28 @@ impl ItemDB {
     pub async fn all_info_from_query<S: AsRef<str>>(
         &self,
         query: S,
-    ) -> Result<(Vec<u32>, Vec<u32>, Vec<ItemInfo>)> {
-        let top_ids = self.ids_from_query(query).await?;
-        let all_ids = self.associated_ids(&top_ids).await?;
+    ) -> Result<(Vec<u32>, Vec<u32>, Vec<ItemInfo>), ItemDBError> {
+        let key = ItemCache::query_key(query.as_ref());
+        let (cached, generation) = self.cache.query(&key);
+        let (top_ids, all_ids) = match cached {
+            Some(CachedQuery {
+                ids,
+                associated_ids: Some(associated_ids),
+            }) => (ids, associated_ids),
+            cached => {
+                let top_ids = match cached {
+                    Some(cached) => cached.ids,
+                    None => self.fetch_ids_from_query(query.as_ref()).await?,
+                };
+                let all_ids = self.associated_ids(&top_ids).await?;
+                let cached = CachedQuery {
+                    ids: top_ids.clone(),
+                    associated_ids: Some(all_ids.clone()),
+                };
+                self.cache.insert_query(generation, key, cached);
+                (top_ids, all_ids)
+            }
+        };
         let items = self.items_from_ids(&all_ids).await?;
         Ok((top_ids, all_ids, items))
     }
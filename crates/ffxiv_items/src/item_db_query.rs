@@ -1,22 +1,54 @@
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use const_format::formatcp;
 use futures::TryStreamExt;
-use sqlx::Row;
+use sqlx::{mysql::MySqlDatabaseError, Row};
+use tokio::time::timeout;
 
 use crate::{
     tables::{strip_whitespace, ItemInfoTable},
-    ItemDB, Query, QueryBindingInfo,
+    CachedQuery, ItemCache, ItemDB, ItemDBError, Query, QueryBindingInfo, QueryTooExpensive,
 };
 
+/// How long the database may spend on a single query string.
+const QUERY_TIMEOUT_MS: u64 = 5000;
+/// Extra time given to the database to abort a query itself, before the
+/// client gives up on it.
+const QUERY_TIMEOUT_GRACE: Duration = Duration::from_secs(1);
+/// The MySQL error for statements interrupted by `MAX_EXECUTION_TIME`.
+const ER_QUERY_TIMEOUT: u16 = 3024;
+
 impl ItemDB {
     /// Returns items that match a particular query string.
-    pub async fn ids_from_query<S: AsRef<str>>(&self, query: S) -> Result<Vec<u32>> {
+    ///
+    /// Queries that are too complex, or that take too long to run, fail with
+    /// an [ItemDBError::QueryTooExpensive] error, & those with invalid
+    /// patterns fail with an [ItemDBError::Query] error. Results are cached in
+    /// memory.
+    pub async fn ids_from_query<S: AsRef<str>>(&self, query: S) -> Result<Vec<u32>, ItemDBError> {
+        let key = ItemCache::query_key(query.as_ref());
+        let (cached, generation) = self.cache.query(&key);
+        if let Some(cached) = cached {
+            return Ok(cached.ids);
+        }
+
+        let ids = self.fetch_ids_from_query(query.as_ref()).await?;
+        let cached = CachedQuery {
+            ids: ids.clone(),
+            associated_ids: None,
+        };
+        self.cache.insert_query(generation, key, cached);
+        Ok(ids)
+    }
+
+    pub(crate) async fn fetch_ids_from_query(&self, query: &str) -> Result<Vec<u32>> {
         let start = Instant::now();
-        let Some(QueryBindingInfo { clause, binds }) = Query::from_query(query.as_ref()) else {
+        let aliases = self.query_aliases(query).await?;
+        let Some(QueryBindingInfo { clause, binds }) = Query::from_query(query, &aliases)? else {
             return Ok(Vec::new());
         };
+        Query::check_syntax(query)?;
 
         let db_query_string = strip_whitespace(format!("{SQL_SELECT} WHERE {clause}"));
         let mut sql_query = sqlx::query(&db_query_string);
@@ -24,11 +56,23 @@ impl ItemDB {
             sql_query = sql_query.bind(bind);
         }
 
-        let mut ids = Vec::new();
-        let mut sql_query = sql_query.persistent(true).fetch(self);
-        while let Some(row) = sql_query.try_next().await? {
-            ids.push(row.get::<u32, _>(0));
-        }
+        let fetch_ids = async {
+            let mut ids = Vec::new();
+            let mut sql_query = sql_query.persistent(true).fetch(self);
+            while let Some(row) = sql_query.try_next().await? {
+                ids.push(row.get::<u32, _>(0));
+            }
+            Ok::<_, sqlx::Error>(ids)
+        };
+
+        let limit = Duration::from_millis(QUERY_TIMEOUT_MS);
+        let mut ids = match timeout(limit + QUERY_TIMEOUT_GRACE, fetch_ids).await {
+            Ok(Err(err)) if is_query_timeout(&err) => {
+                return Err(QueryTooExpensive::TimedOut { limit }.into())
+            }
+            Ok(result) => result?,
+            Err(_) => return Err(QueryTooExpensive::TimedOut { limit }.into()),
+        };
 
         log::debug!(target: "ffxiv_items", "DB Query for query string ({} ids returned): {:.3}s", ids.len(), start.elapsed().as_secs_f32());
         ids.sort();
@@ -36,7 +80,13 @@ impl ItemDB {
     }
 }
 
+fn is_query_timeout(err: &sqlx::Error) -> bool {
+    err.as_database_error()
+        .and_then(|err| err.try_downcast_ref::<MySqlDatabaseError>())
+        .is_some_and(|err| err.number() == ER_QUERY_TIMEOUT)
+}
+
 const SQL_SELECT: &str = formatcp!(
-    "SELECT i.id, i.name FROM {} AS i",
+    "SELECT /*+ MAX_EXECUTION_TIME({QUERY_TIMEOUT_MS}) */ i.id, i.name FROM {} AS i",
     ItemInfoTable::SQL_TABLE_NAME
 );
@@ -1,4 +1,4 @@
-use crate::Recipe;
+use crate::{Food, Recipe};
 
 /// The base information for an item.
 #[derive(Clone)]
@@ -9,4 +9,13 @@ pub struct ItemInfo {
     pub name: String,
     /// The recipe for the item, if it is craftable.
     pub recipe: Option<Recipe>,
+    /// The item's base parameters & their values, e.g. `("Control", 50)`.
+    /// Empty for items without any.
+    pub stats: Vec<(String, i32)>,
+    /// The stats raised by eating or drinking the item, if it's a meal or a
+    /// medicine.
+    pub food: Option<Food>,
+    /// Whether the item can be sold on the market board, i.e. it has a market
+    /// board category.
+    pub is_marketable: bool,
 }
@@ -0,0 +1,71 @@
+use futures::{future::BoxFuture, FutureExt};
+
+use crate::{ItemDB, ItemDBError, ItemInfo};
+
+/// The top-level ids, descendant ids and [ItemInfo] data for a query string.
+type QueryInfo = (Vec<u32>, Vec<u32>, Vec<ItemInfo>);
+
+/// Looks up items & runs query strings against them. This is implemented by
+/// [ItemDB], and by [MemoryItemRepository](crate::MemoryItemRepository) for
+/// use without a database.
+pub trait ItemRepository: Send + Sync {
+    /// Returns the ids of the items that match a query string, in order.
+    fn ids_from_query<'a>(&'a self, query: &'a str)
+        -> BoxFuture<'a, Result<Vec<u32>, ItemDBError>>;
+
+    /// Returns the `ids` along with all descendant ids of any that are
+    /// recipes, in order.
+    fn associated_ids<'a>(&'a self, ids: &'a [u32])
+        -> BoxFuture<'a, Result<Vec<u32>, ItemDBError>>;
+
+    /// Returns [ItemInfo] for each of the `ids` passed in, ordered by id.
+    fn items_from_ids<'a>(
+        &'a self,
+        ids: &'a [u32],
+    ) -> BoxFuture<'a, Result<Vec<ItemInfo>, ItemDBError>>;
+
+    /// Returns top-level ids, descendant ids and [ItemInfo] data for an input
+    /// query string.
+    fn all_info_from_query<'a>(
+        &'a self,
+        query: &'a str,
+    ) -> BoxFuture<'a, Result<QueryInfo, ItemDBError>> {
+        async move {
+            let top_ids = self.ids_from_query(query).await?;
+            let all_ids = self.associated_ids(&top_ids).await?;
+            let items = self.items_from_ids(&all_ids).await?;
+            Ok((top_ids, all_ids, items))
+        }
+        .boxed()
+    }
+}
+
+impl ItemRepository for ItemDB {
+    fn ids_from_query<'a>(
+        &'a self,
+        query: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<u32>, ItemDBError>> {
+        ItemDB::ids_from_query(self, query).boxed()
+    }
+
+    fn associated_ids<'a>(
+        &'a self,
+        ids: &'a [u32],
+    ) -> BoxFuture<'a, Result<Vec<u32>, ItemDBError>> {
+        ItemDB::associated_ids(self, ids).boxed()
+    }
+
+    fn items_from_ids<'a>(
+        &'a self,
+        ids: &'a [u32],
+    ) -> BoxFuture<'a, Result<Vec<ItemInfo>, ItemDBError>> {
+        ItemDB::items_from_ids(self, ids).boxed()
+    }
+
+    fn all_info_from_query<'a>(
+        &'a self,
+        query: &'a str,
+    ) -> BoxFuture<'a, Result<QueryInfo, ItemDBError>> {
+        ItemDB::all_info_from_query(self, query).boxed()
+    }
+}
@@ -29,30 +29,62 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! [ItemDB]'s methods return [ItemDBError]s, which tell apart the failures
+//! that callers may want to handle, e.g. a lost connection to the database
+//! from an invalid query string.
 
+mod collectables;
 mod csv_parse;
+mod dataset_info;
+mod error;
+mod food;
+mod gc_supply;
 mod github_metadata;
+mod item_cache;
 mod item_db;
 mod item_db_items;
 mod item_db_query;
 mod item_id;
 mod item_info;
+mod item_repository;
+mod materia;
+mod memory_item_repository;
 mod parsers;
 mod query;
 mod recipe;
+mod saved_query;
+mod snapshot;
 mod tables;
+mod validation;
 
-use csv_parse::csv_parse;
-use github_metadata::last_updated_from_github;
+use csv_parse::{column_values, csv_parse, CsvColumns};
+use github_metadata::{latest_commit_from_github, CommitInfo};
+use item_cache::{CachedQuery, ItemCache};
 use item_id::ItemId;
 use query::{Query, QueryBindingInfo};
 
+pub use collectables::{CollectableReward, RewardSource, RewardTier};
+pub use dataset_info::{DatasetFile, DatasetInfo};
+pub use error::ItemDBError;
+pub use food::{Food, FoodEffect, FoodKind};
+pub use gc_supply::{GcSupplyDuty, GcSupplyKind};
+pub use item_cache::CacheStats;
 pub use item_db::ItemDB;
+pub use item_db_items::Resolution;
 pub use item_info::ItemInfo;
-pub use recipe::{Ingredient, Recipe};
+pub use item_repository::ItemRepository;
+pub use materia::{Materia, Meld, MeldPlan};
+pub use memory_item_repository::MemoryItemRepository;
+pub use query::QueryTooExpensive;
+pub use recipe::{
+    CraftPhase, Ingredient, PhaseSupply, Recipe, RecipeBook, RecipeDifficulty, RecipeKind,
+};
+pub use saved_query::{SavedQuery, SavedQueryError};
+pub use validation::{ValidationIssue, ValidationReport};
 
 mod _temp {
     use chrono as _;
+    use clap as _;
     use env_logger as _;
-    use tokio as _;
 }
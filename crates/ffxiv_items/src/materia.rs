@@ -0,0 +1,137 @@
+use anyhow::Result;
+use futures::try_join;
+
+use crate::{
+    tables::{ItemInfoTable, MateriaTable},
+    ItemDB, ItemDBError, ItemId,
+};
+
+/// Gear has at most this many materia slots, including overmelds.
+const MAX_MELDS: usize = 5;
+
+/// A materia item, which raises a stat of the gear it's melded into.
+#[derive(Clone, Debug)]
+pub struct Materia {
+    /// The item_id of the materia.
+    pub item_id: u32,
+    /// The stat raised, e.g. `Control`.
+    pub stat: String,
+    /// The grade of the materia, from 1, e.g. 10 for `Quickarm Materia X`.
+    pub grade: u32,
+    /// The raise to the stat.
+    pub value: i32,
+}
+
+/// The materia expected to be consumed while melding a set of materia into a
+/// piece of gear.
+#[derive(Clone, Debug)]
+pub struct MeldPlan {
+    /// The item_id of the gear.
+    pub item_id: u32,
+    /// The number of slots that are guaranteed to succeed.
+    pub slots: u32,
+    /// Each meld, in order.
+    pub melds: Vec<Meld>,
+}
+
+/// A single materia melded into a piece of gear.
+#[derive(Clone, Debug)]
+pub struct Meld {
+    /// The materia melded.
+    pub materia: Materia,
+    /// Whether the meld is into a slot past the guaranteed ones.
+    pub is_overmeld: bool,
+    /// The chance of each attempt succeeding, from 0 to 1.
+    pub success_rate: f32,
+    /// The number of materia expected to be consumed before the meld
+    /// succeeds, as materia are lost on failed attempts.
+    pub expected_count: f32,
+}
+
+impl ItemDB {
+    /// Returns the materia among `ids`, ordered by item id.
+    pub async fn materia_by_item_ids<I: ItemId>(
+        &self,
+        ids: &[I],
+    ) -> Result<Vec<Materia>, ItemDBError> {
+        Ok(MateriaTable::new(self).by_item_ids(ids).await?)
+    }
+
+    /// Plans melding `materia_ids` into the gear `item_id`, in order. Melds
+    /// past the gear's guaranteed slots are overmelds, which may fail & need
+    /// several materia. High quality gear has better overmeld success rates.
+    pub async fn meld_plan(
+        &self,
+        item_id: u32,
+        materia_ids: &[u32],
+        is_hq: bool,
+    ) -> Result<MeldPlan, ItemDBError> {
+        let (items, materia_table) = (ItemInfoTable::new(self), MateriaTable::new(self));
+        let (slots, materia, join_rates) = try_join!(
+            items.materia_slots(item_id),
+            materia_table.by_item_ids(materia_ids),
+            materia_table.join_rates(),
+        )?;
+        let Some((slots, is_advanced_melding_permitted)) = slots else {
+            return Err(ItemDBError::other(format!("Item {item_id} doesn't exist")));
+        };
+        if slots == 0 {
+            return Err(ItemDBError::other(format!(
+                "Item {item_id} has no materia slots"
+            )));
+        }
+
+        let max_melds = match is_advanced_melding_permitted {
+            true => MAX_MELDS,
+            false => slots as usize,
+        };
+        if materia_ids.len() > max_melds {
+            return Err(ItemDBError::other(format!(
+                "Item {item_id} can only be melded with {max_melds} materia, not {}",
+                materia_ids.len()
+            )));
+        }
+
+        let mut melds = Vec::new();
+        for (index, materia_id) in (0..).zip(materia_ids) {
+            let materia = materia
+                .iter()
+                .find(|materia| materia.item_id == *materia_id)
+                .ok_or_else(|| ItemDBError::other(format!("Item {materia_id} isn't materia")))?;
+            let is_overmeld = index >= slots;
+            let success_rate = match is_overmeld {
+                false => 1.0,
+                true => {
+                    let overmeld = index - slots;
+                    let (nq_rate, hq_rate) = join_rates
+                        .get(&(materia.grade, overmeld))
+                        .copied()
+                        .unwrap_or_default();
+                    let rate = if is_hq { hq_rate } else { nq_rate };
+                    if rate == 0 {
+                        return Err(ItemDBError::other(format!(
+                            "Grade {} materia can't be melded into overmeld slot {}",
+                            materia.grade,
+                            overmeld + 1
+                        )));
+                    }
+                    #[allow(clippy::cast_precision_loss)]
+                    let rate = rate as f32 / 100.0;
+                    rate
+                }
+            };
+            melds.push(Meld {
+                materia: materia.clone(),
+                is_overmeld,
+                success_rate,
+                expected_count: 1.0 / success_rate,
+            });
+        }
+
+        Ok(MeldPlan {
+            item_id,
+            slots,
+            melds,
+        })
+    }
+}
@@ -0,0 +1,536 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use anyhow::{bail, Result};
+use futures::{future::BoxFuture, try_join, FutureExt};
+use itertools::Itertools;
+use mock_traits::FileDownloader;
+
+use crate::{
+    parsers::job_bits,
+    query::{name_options, parse_range, parse_stat, StringMatcher},
+    tables::{
+        download_company_crafts, download_items, download_latest_csv, download_recipe_info,
+        download_search_categories, download_ui_categories, recipe_tree_ids,
+    },
+    ItemDBError, ItemInfo, ItemRepository, Query, Recipe, SavedQueryError,
+};
+
+/// An [ItemRepository] that holds every item in memory, so that queries can be
+/// run without a database, e.g. in tests.
+///
+/// Only items & their recipes are held, so the `:gcsupply`, `:delivery` &
+/// `:ishgard` filters fail, as do `@name` aliases of saved queries.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use ffxiv_items::{ItemRepository, MemoryItemRepository};
+/// use mock_traits::ReqwestDownloader;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let items = MemoryItemRepository::load::<ReqwestDownloader>().await?;
+///     let info = items.all_info_from_query(":name ^Rarefied, :rlevel 61|69").await?;
+///     let (top_level_ids, associated_ids, item_info) = info;
+///
+///     Ok(())
+/// }
+/// ```
+pub struct MemoryItemRepository {
+    items: BTreeMap<u32, MemoryItem>,
+    ui_categories: HashMap<u32, String>,
+    search_categories: HashMap<u32, String>,
+    /// The workshop category of each company workshop project.
+    workshops: HashMap<u32, String>,
+    /// The ids in the recipe tree of each recipe, including its own.
+    input_ids: HashMap<u32, Vec<u32>>,
+}
+
+/// An item, along with the fields it can be filtered by.
+struct MemoryItem {
+    info: ItemInfo,
+    ui_category: u32,
+    search_category: u32,
+    item_level: u32,
+    equip_level: u32,
+    equip_slot: &'static str,
+    class_jobs: u64,
+}
+
+type Filter<'a> = Box<dyn Fn(&MemoryItem) -> bool + 'a>;
+
+impl MemoryItemRepository {
+    /// Downloads the latest csv files from
+    /// <https://www.github.com/xivapi/ffxiv-datamining>, and parses them the
+    /// same way as when building an [ItemDB](crate::ItemDB).
+    pub async fn load<F: FileDownloader>() -> Result<Self> {
+        let (items, ui_categories, search_categories, mut recipes, company_crafts) = try_join!(
+            download_items(download_latest_csv::<F>),
+            download_ui_categories(download_latest_csv::<F>),
+            download_search_categories(download_latest_csv::<F>),
+            download_recipe_info(download_latest_csv::<F>),
+            download_company_crafts(download_latest_csv::<F>),
+        )?;
+
+        // An item only has a single recipe, so any made by crafters take
+        // precedence over workshop projects
+        let crafter_ids = recipes
+            .iter()
+            .map(|recipe| recipe.output.item_id)
+            .collect::<HashSet<_>>();
+        let company_crafts = company_crafts
+            .into_iter()
+            .filter(|craft| !crafter_ids.contains(&craft.recipe.output.item_id))
+            .collect_vec();
+        let workshops = company_crafts
+            .iter()
+            .map(|craft| (craft.recipe.output.item_id, craft.craft_type.clone()))
+            .collect();
+        recipes.extend(company_crafts.into_iter().map(|craft| craft.recipe));
+
+        let items = items
+            .into_iter()
+            .filter(|item| !item.name.is_empty())
+            .map(|item| MemoryItem {
+                info: ItemInfo {
+                    id: item.id,
+                    name: item.name,
+                    recipe: None,
+                    stats: item.stats,
+                    food: item.food,
+                    is_marketable: item.search_category != 0,
+                },
+                ui_category: item.ui_category,
+                search_category: item.search_category,
+                item_level: item.ilevel,
+                equip_level: item.equip_level,
+                equip_slot: item.equip_slot,
+                class_jobs: item.class_jobs,
+            })
+            .collect();
+
+        Ok(Self {
+            ui_categories: ui_categories
+                .into_iter()
+                .map(|category| (category.id, category.name))
+                .collect(),
+            search_categories: search_categories
+                .into_iter()
+                .map(|category| (category.id, category.name))
+                .collect(),
+            workshops,
+            ..Self::new(items, recipes)
+        })
+    }
+
+    /// Holds the `items` passed in, along with their recipes. Filters on
+    /// fields that [ItemInfo] doesn't have, e.g. `:ilevel` or `:cat`, don't
+    /// match any of them.
+    pub fn from_items(items: Vec<ItemInfo>) -> Self {
+        let recipes = items
+            .iter()
+            .filter_map(|item| item.recipe.clone())
+            .collect_vec();
+        let items = items
+            .into_iter()
+            .map(|info| MemoryItem {
+                info,
+                ui_category: 0,
+                search_category: 0,
+                item_level: 0,
+                equip_level: 0,
+                equip_slot: "",
+                class_jobs: 0,
+            })
+            .collect();
+        Self::new(items, recipes)
+    }
+
+    fn new(items: Vec<MemoryItem>, recipes: Vec<Recipe>) -> Self {
+        let recipe_map = Recipe::to_map_ref(&recipes);
+        let input_ids = recipe_map
+            .keys()
+            .map(|&item_id| (item_id, recipe_tree_ids(item_id, &recipe_map)))
+            .collect();
+
+        let mut items = items
+            .into_iter()
+            .map(|item| (item.info.id, item))
+            .collect::<BTreeMap<_, _>>();
+        for recipe in recipes {
+            if let Some(item) = items.get_mut(&recipe.output.item_id) {
+                item.info.recipe = Some(recipe);
+            }
+        }
+
+        Self {
+            items,
+            ui_categories: HashMap::new(),
+            search_categories: HashMap::new(),
+            workshops: HashMap::new(),
+            input_ids,
+        }
+    }
+
+    fn query_ids(&self, query: &str) -> Result<Vec<u32>> {
+        if let Some(name) = Query::alias_names(query).into_iter().next() {
+            return Err(SavedQueryError::UnknownAlias(name).into());
+        }
+
+        let clauses = Query::clauses(query, &HashMap::new())?;
+        Query::check_syntax(query)?;
+        let clauses = clauses
+            .iter()
+            .zip(Query::filter_offsets(query))
+            .map(|(clause, offsets)| self.clause_filters(clause, &offsets))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(self
+            .items
+            .values()
+            .filter(|item| {
+                clauses
+                    .iter()
+                    .any(|filters| !filters.is_empty() && filters.iter().all(|filter| filter(item)))
+            })
+            .map(|item| item.info.id)
+            .collect())
+    }
+
+    /// Builds the filters of a clause, whose filters start at `offsets` in the
+    /// query string.
+    fn clause_filters(&self, clause: &[Query], offsets: &[usize]) -> Result<Vec<Filter<'_>>> {
+        let mut filters = Vec::new();
+        for (Query { tag, options }, &offset) in clause.iter().zip(offsets) {
+            let filter = self
+                .filter(tag, options)
+                .map_err(|err| ItemDBError::Query {
+                    offset,
+                    reason: err.to_string(),
+                })?;
+            filters.extend(filter);
+        }
+        Ok(filters)
+    }
+
+    /// Builds the filter for a tag, matching the same items as its SQL clause
+    /// would. Filters whose options can't be parsed are `None`, and ignored.
+    fn filter(&self, tag: &str, options: &[String]) -> Result<Option<Filter<'_>>> {
+        let filter: Filter = match tag {
+            ":name" => {
+                let Some(matcher) = StringMatcher::new(options)? else {
+                    return Ok(None);
+                };
+                Box::new(move |item| matcher.is_match(&item.info.name))
+            }
+            ":id" => {
+                let ids = options
+                    .iter()
+                    .filter_map(|option| option.trim().parse::<u32>().ok())
+                    .collect::<HashSet<_>>();
+                if ids.is_empty() {
+                    return Ok(None);
+                }
+                Box::new(move |item| ids.contains(&item.info.id))
+            }
+            ":rlevel" => {
+                let Some(range) = parse_range(options) else {
+                    return Ok(None);
+                };
+                Box::new(move |item| {
+                    let recipe = item.info.recipe.as_ref();
+                    recipe.is_some_and(|recipe| range.contains(&recipe.level))
+                })
+            }
+            ":elevel" => {
+                let Some(range) = parse_range(options) else {
+                    return Ok(None);
+                };
+                Box::new(move |item| range.contains(&item.equip_level))
+            }
+            ":ilevel" => {
+                let Some(range) = parse_range(options) else {
+                    return Ok(None);
+                };
+                Box::new(move |item| range.contains(&item.item_level))
+            }
+            ":cat" => {
+                let Some(matcher) = StringMatcher::new(options)? else {
+                    return Ok(None);
+                };
+                Box::new(move |item| {
+                    let category = self.ui_categories.get(&item.ui_category);
+                    category.is_some_and(|category| matcher.is_match(category))
+                })
+            }
+            ":mbcat" => {
+                let matcher = StringMatcher::new(options)?;
+                Box::new(move |item| {
+                    let category = self.search_categories.get(&item.search_category);
+                    category.is_some_and(|category| is_match_or_any(&matcher, category))
+                })
+            }
+            ":slot" => {
+                let Some(matcher) = StringMatcher::new(options)? else {
+                    return Ok(None);
+                };
+                Box::new(move |item| matcher.is_match(item.equip_slot))
+            }
+            ":class" => {
+                let bits = job_bits(options);
+                if bits == 0 {
+                    return Ok(None);
+                }
+                Box::new(move |item| item.class_jobs & bits != 0)
+            }
+            ":stat" => {
+                let comparisons = options
+                    .iter()
+                    .filter_map(|option| parse_stat(option))
+                    .map(|(stat, comparison)| (stat.to_lowercase(), comparison))
+                    .collect_vec();
+                if comparisons.is_empty() {
+                    return Ok(None);
+                }
+                Box::new(move |item| {
+                    item.info.stats.iter().any(|(stat, value)| {
+                        let stat = stat.to_lowercase();
+                        comparisons.iter().any(|(name, comparison)| {
+                            *name == stat
+                                && match comparison {
+                                    Some((op, other)) => compare(*value, op, *other),
+                                    None => true,
+                                }
+                        })
+                    })
+                })
+            }
+            ":food" => {
+                let matcher = StringMatcher::new(options)?;
+                Box::new(move |item| {
+                    let food = item.info.food.as_ref();
+                    food.is_some_and(|food| {
+                        let mut stats = food.effects.iter().map(|effect| &effect.stat);
+                        stats.any(|stat| is_match_or_any(&matcher, stat))
+                    })
+                })
+            }
+            ":book" => {
+                let (none, books): (Vec<_>, Vec<_>) = options
+                    .iter()
+                    .cloned()
+                    .partition(|option| option.eq_ignore_ascii_case("none"));
+                let without_book = !none.is_empty();
+                let matcher = StringMatcher::new(&books)?;
+                if !without_book && matcher.is_none() {
+                    return Ok(None);
+                }
+                Box::new(move |item| {
+                    let recipe = item.info.recipe.as_ref();
+                    recipe.is_some_and(|recipe| match &recipe.book {
+                        Some(book) => matcher.as_ref().is_some_and(|m| m.is_match(&book.name)),
+                        None => without_book,
+                    })
+                })
+            }
+            ":contains" => {
+                let Some(matcher) = StringMatcher::new(options)? else {
+                    return Ok(None);
+                };
+                Box::new(move |item| {
+                    let recipe = item.info.recipe.as_ref();
+                    recipe.is_some_and(|recipe| {
+                        let mut ids = recipe.inputs.iter().map(|input| input.item_id);
+                        ids.any(|id| self.is_name_match(id, &matcher))
+                    })
+                })
+            }
+            ":includes" => {
+                let Some(matcher) = StringMatcher::new(options)? else {
+                    return Ok(None);
+                };
+                Box::new(move |item| {
+                    let input_ids = self.input_ids.get(&item.info.id);
+                    input_ids
+                        .is_some_and(|ids| ids.iter().any(|&id| self.is_name_match(id, &matcher)))
+                })
+            }
+            ":workshop" => {
+                let matcher = StringMatcher::new(options)?;
+                Box::new(move |item| {
+                    let craft_type = self.workshops.get(&item.info.id);
+                    craft_type.is_some_and(|craft_type| is_match_or_any(&matcher, craft_type))
+                })
+            }
+            ":gcsupply" | ":delivery" | ":ishgard" => {
+                bail!("{tag} filters aren't supported without an item database")
+            }
+            ":count" | ":limit" | ":min_velocity" => return Ok(None),
+            tag if tag.starts_with(':') => {
+                log::info!(target: "ffxiv_items", "Invalid query tag: {tag}");
+                return Ok(None);
+            }
+            tag => return self.filter(":name", &name_options(tag, options)),
+        };
+        Ok(Some(filter))
+    }
+
+    fn is_name_match(&self, item_id: u32, matcher: &StringMatcher) -> bool {
+        let item = self.items.get(&item_id);
+        item.is_some_and(|item| matcher.is_match(&item.info.name))
+    }
+}
+
+/// Filters without any options match every item that has the field.
+fn is_match_or_any(matcher: &Option<StringMatcher>, value: &str) -> bool {
+    match matcher {
+        Some(matcher) => matcher.is_match(value),
+        None => true,
+    }
+}
+
+fn compare(value: i32, op: &str, other: i32) -> bool {
+    match op {
+        "<=" => value <= other,
+        ">=" => value >= other,
+        "<" => value < other,
+        ">" => value > other,
+        _ => value == other,
+    }
+}
+
+impl ItemRepository for MemoryItemRepository {
+    fn ids_from_query<'a>(
+        &'a self,
+        query: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<u32>, ItemDBError>> {
+        async move { Ok(self.query_ids(query)?) }.boxed()
+    }
+
+    fn associated_ids<'a>(
+        &'a self,
+        ids: &'a [u32],
+    ) -> BoxFuture<'a, Result<Vec<u32>, ItemDBError>> {
+        async move {
+            let input_ids = ids.iter().filter_map(|id| self.input_ids.get(id)).flatten();
+            let mut all_ids = input_ids.chain(ids).copied().unique().collect_vec();
+            all_ids.sort();
+            Ok(all_ids)
+        }
+        .boxed()
+    }
+
+    fn items_from_ids<'a>(
+        &'a self,
+        ids: &'a [u32],
+    ) -> BoxFuture<'a, Result<Vec<ItemInfo>, ItemDBError>> {
+        async move {
+            Ok(ids
+                .iter()
+                .sorted()
+                .dedup()
+                .filter_map(|id| Some(self.items.get(id)?.info.clone()))
+                .collect())
+        }
+        .boxed()
+    }
+}
+
+////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use crate::{Ingredient, RecipeBook, RecipeDifficulty, RecipeKind};
+
+    use super::*;
+
+    fn item(id: u32, name: &str, inputs: &[u32]) -> ItemInfo {
+        let recipe = (!inputs.is_empty()).then(|| Recipe {
+            output: Ingredient {
+                count: 1,
+                item_id: id,
+            },
+            inputs: inputs
+                .iter()
+                .map(|&item_id| Ingredient { count: 2, item_id })
+                .collect(),
+            level: 90,
+            stars: 0,
+            difficulty: RecipeDifficulty::default(),
+            book: (id == 3).then(|| RecipeBook {
+                item_id: 100,
+                name: "Master Blacksmith IX".into(),
+            }),
+            kind: RecipeKind::Crafter,
+            phases: Vec::new(),
+        });
+        ItemInfo {
+            id,
+            name: name.into(),
+            recipe,
+            stats: vec![("Control".into(), id as i32 * 10)],
+            food: None,
+            is_marketable: true,
+        }
+    }
+
+    fn repository() -> MemoryItemRepository {
+        MemoryItemRepository::from_items(vec![
+            item(1, "Iron Ore", &[]),
+            item(2, "Iron Ingot", &[1]),
+            item(3, "Iron Rivets", &[2]),
+            item(4, "Maple Lumber", &[]),
+        ])
+    }
+
+    fn ids(query: &str) -> Vec<u32> {
+        block_on(repository().ids_from_query(query)).unwrap()
+    }
+
+    #[test]
+    fn test_memory_query() {
+        assert_eq!(ids(":name iron"), vec![1, 2, 3]);
+        assert_eq!(ids(":name !iron ingot|Maple Lumber"), vec![2, 4]);
+        assert_eq!(ids(":name ^Iron (Ore|Rivets)$"), vec![1, 3]);
+        assert_eq!(ids("Iron In"), vec![2]);
+        assert_eq!(ids(":id 4|2"), vec![2, 4]);
+        assert_eq!(ids(":rlevel 90, :name Iron"), vec![2, 3]);
+        assert_eq!(ids(":contains Ore"), vec![2]);
+        assert_eq!(ids(":includes Ore"), vec![2, 3]);
+        assert_eq!(ids(":book none"), vec![2]);
+        assert_eq!(ids(":book Blacksmith"), vec![3]);
+        assert_eq!(ids(":stat Control>=30"), vec![3, 4]);
+        assert_eq!(ids(":name Ore; :name Lumber"), vec![1, 4]);
+        assert_eq!(ids(":ilevel 1|999"), Vec::<u32>::new());
+        assert_eq!(ids(":count 5"), Vec::<u32>::new());
+        assert!(block_on(repository().ids_from_query("@saved")).is_err());
+    }
+
+    #[test]
+    fn test_memory_query_errors() {
+        let error = |query| block_on(repository().ids_from_query(query)).unwrap_err();
+        assert!(matches!(
+            error(":name Iron; :rlevel 90, :gcsupply CUL"),
+            ItemDBError::Query { offset: 24, .. }
+        ));
+        assert!(matches!(
+            error(":name Iron (Ore"),
+            ItemDBError::Query { offset: 0, .. }
+        ));
+        assert_eq!(
+            error("@saved"),
+            ItemDBError::SavedQuery(SavedQueryError::UnknownAlias("saved".into()))
+        );
+    }
+
+    #[test]
+    fn test_memory_all_info() {
+        let (top_ids, all_ids, items) =
+            block_on(repository().all_info_from_query(":name Rivets")).unwrap();
+        assert_eq!(top_ids, vec![3]);
+        assert_eq!(all_ids, vec![1, 2, 3]);
+        let names = items.iter().map(|item| &item.name[..]).collect_vec();
+        assert_eq!(names, vec!["Iron Ore", "Iron Ingot", "Iron Rivets"]);
+    }
+}
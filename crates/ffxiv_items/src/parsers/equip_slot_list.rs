@@ -0,0 +1,55 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+
+use crate::{csv_parse, CsvColumns};
+
+/// The slots in EquipSlotCategory.csv, with the names used by `:slot`. Rings
+/// may go in either finger, so only the left is named.
+const SLOTS: [(&str, &str); 13] = [
+    ("MainHand", "Main Hand"),
+    ("OffHand", "Off Hand"),
+    ("Head", "Head"),
+    ("Body", "Body"),
+    ("Gloves", "Hands"),
+    ("Waist", "Waist"),
+    ("Legs", "Legs"),
+    ("Feet", "Feet"),
+    ("Ears", "Ears"),
+    ("Neck", "Neck"),
+    ("Wrists", "Wrists"),
+    ("FingerL", "Ring"),
+    ("SoulCrystal", "Soul Crystal"),
+];
+
+/// The slot each equip slot category is worn in. Categories also block the
+/// slots they cover with -1, e.g. two-handed weapons block the off hand, but
+/// only the slot actually taken up is kept.
+#[derive(Default)]
+pub struct EquipSlotList {
+    slots: BTreeMap<u32, &'static str>,
+}
+
+impl EquipSlotList {
+    pub fn from_csv(file_name: &'static str, contents: &str) -> Result<Self> {
+        let columns = CsvColumns::new(file_name, contents)?;
+        let slot_columns = SLOTS
+            .into_iter()
+            .map(|(column, name)| Ok((columns.get(column)?, name)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut slots = BTreeMap::new();
+        csv_parse!(file_name, contents.as_bytes(), info => {
+            id = U[0];
+            if let Some((_, name)) = slot_columns.iter().find(|(column, _)| info[*column] == "1") {
+                slots.insert(id, *name);
+            }
+        });
+
+        Ok(Self { slots })
+    }
+
+    /// The name of the slot, or an empty string for items that aren't worn.
+    pub fn name(&self, id: u32) -> &'static str {
+        self.slots.get(&id).copied().unwrap_or_default()
+    }
+}
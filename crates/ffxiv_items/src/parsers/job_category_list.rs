@@ -1,77 +1,80 @@
 use anyhow::Result;
-use csv::ReaderBuilder;
-use std::{collections::BTreeMap, io::Read, ops::Index};
+use std::collections::BTreeMap;
 
-#[derive(Default)]
-pub struct JobCategoryList {
-    jobs: BTreeMap<u32, Jobs>,
-}
+use crate::{csv_parse, CsvColumns};
+
+/// Every class & job, in bit order. Crafters come first, so that their bits
+/// are the same as before the other jobs were added.
+const JOBS: [&str; 43] = [
+    "CRP", "BSM", "ARM", "GSM", "LTW", "WVR", "ALC", "CUL", "MIN", "BTN", "FSH", "ADV", "GLA",
+    "PGL", "MRD", "LNC", "ARC", "CNJ", "THM", "PLD", "MNK", "WAR", "DRG", "BRD", "WHM", "BLM",
+    "ACN", "SMN", "SCH", "ROG", "NIN", "MCH", "DRK", "AST", "SAM", "RDM", "BLU", "GNB", "DNC",
+    "RPR", "SGE", "VPR", "PCT",
+];
 
+const DOH: [&str; 8] = ["CRP", "BSM", "ARM", "GSM", "LTW", "WVR", "ALC", "CUL"];
+const DOL: [&str; 3] = ["MIN", "BTN", "FSH"];
+const DOW: [&str; 19] = [
+    "GLA", "PGL", "MRD", "LNC", "ARC", "PLD", "MNK", "WAR", "DRG", "BRD", "ROG", "NIN", "MCH",
+    "DRK", "SAM", "GNB", "DNC", "RPR", "VPR",
+];
+const DOM: [&str; 12] = [
+    "CNJ", "THM", "ACN", "WHM", "BLM", "SMN", "SCH", "AST", "RDM", "BLU", "SGE", "PCT",
+];
+
+/// The jobs in each class job category, as bits in the order of `JOBS`.
 #[derive(Default)]
-pub struct Jobs {
-    pub id: u32,
-    pub bits: u32,
+pub struct JobCategoryList {
+    jobs: BTreeMap<u32, u64>,
 }
 
 impl JobCategoryList {
-    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
-        let mut jobs = BTreeMap::new();
+    pub fn from_csv(file_name: &'static str, contents: &str) -> Result<Self> {
+        let columns = CsvColumns::new(file_name, contents)?;
+        // Jobs newer than the game data are left out, rather than failing
+        let job_columns = (0..)
+            .zip(JOBS)
+            .filter_map(|(bit, job)| Some((bit, columns.get(job).ok()?)))
+            .collect::<Vec<(u32, usize)>>();
 
-        csv_parse!(reader => {
+        let mut jobs = BTreeMap::new();
+        csv_parse!(file_name, contents.as_bytes(), info => {
             id = U[0];
-            crp = B[9 + 1];
-            bsm = B[10 + 1];
-            arm = B[11 + 1];
-            gsm = B[12 + 1];
-            ltw = B[13 + 1];
-            wvr = B[14 + 1];
-            alc = B[15 + 1];
-            cul = B[16 + 1];
-
-            let bits = u32::from(crp)
-                + (u32::from(bsm) << 1)
-                + (u32::from(arm) << 2)
-                + (u32::from(gsm) << 3)
-                + (u32::from(ltw) << 4)
-                + (u32::from(wvr) << 5)
-                + (u32::from(alc) << 6)
-                + (u32::from(cul) << 7);
+            let bits = job_columns
+                .iter()
+                .filter(|(_, column)| info[*column] == "True")
+                .fold(0, |bits, (bit, _)| bits | (1 << bit));
 
-            jobs.insert(id, Jobs { id, bits });
+            jobs.insert(id, bits);
         });
 
         Ok(Self { jobs })
     }
-}
 
-impl Jobs {
-    pub fn matches_any<S: AsRef<str>>(&self, jobs: &[S]) -> bool {
-        let mut bits = 0;
-        for job in jobs {
-            let job = job.as_ref();
-            match job {
-                "crp" | "CRP" => bits |= 1 << 0,
-                "bsm" | "BSM" => bits |= 1 << 1,
-                "arm" | "ARM" => bits |= 1 << 2,
-                "gsm" | "GSM" => bits |= 1 << 3,
-                "ltw" | "LTW" => bits |= 1 << 4,
-                "wvr" | "WVR" => bits |= 1 << 5,
-                "alc" | "ALC" => bits |= 1 << 6,
-                "cul" | "CUL" => bits |= 1 << 7,
-                _ => panic!("Invalid job: {job}"),
-            }
-        }
-        jobs.is_empty() || (bits & self.bits) > 0
+    /// The bits of the jobs in the category, or 0 for unknown categories.
+    pub fn bits(&self, id: u32) -> u64 {
+        self.jobs.get(&id).copied().unwrap_or_default()
     }
 }
 
-impl Index<&u32> for JobCategoryList {
-    type Output = Jobs;
-
-    fn index(&self, index: &u32) -> &Self::Output {
-        match self.jobs.get(index) {
-            None => panic!("Missing job_category id: {index}"),
-            Some(value) => value,
+/// The bits of the jobs named by `jobs`, which may be abbreviations like `CUL`
+/// or groups like `DoH`, in any case. Unknown names are ignored.
+pub fn job_bits<S: AsRef<str>>(jobs: &[S]) -> u64 {
+    let mut bits = 0;
+    for job in jobs {
+        let job = job.as_ref().to_uppercase();
+        let group: &[&str] = match &job[..] {
+            "DOH" => &DOH,
+            "DOL" => &DOL,
+            "DOW" => &DOW,
+            "DOM" => &DOM,
+            job => &[job],
+        };
+        for job in group {
+            if let Some(bit) = JOBS.iter().position(|name| name == job) {
+                bits |= 1 << bit;
+            }
         }
     }
+    bits
 }
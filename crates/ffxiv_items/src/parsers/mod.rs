@@ -1,7 +1,9 @@
 // mod craft_leve_list;
-// mod job_category_list;
+mod equip_slot_list;
+mod job_category_list;
 // mod leve_list;
 
 // pub use craft_leve_list::*;
-// pub use job_category_list::*;
+pub use equip_slot_list::*;
+pub use job_category_list::*;
 // pub use leve_list::*;
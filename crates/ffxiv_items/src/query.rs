@@ -1,12 +1,26 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    ops::RangeInclusive,
+    time::Duration,
+};
 
 use itertools::Itertools;
+use regex::{Regex, RegexBuilder};
 
-use crate::tables::{IngredientTable, InputIdsTable, ItemInfoTable, RecipeTable, UiCategoryTable};
+use crate::{
+    parsers::job_bits,
+    tables::{
+        CollectableRewardTable, CompanyCraftTable, FoodEffectTable, GcSupplyDutyTable,
+        IngredientTable, InputIdsTable, ItemInfoTable, ItemStatTable, RecipeTable,
+        SearchCategoryTable, UiCategoryTable,
+    },
+    ItemDBError, RewardSource,
+};
 
 type QueryOptions = Vec<String>;
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Query {
     pub tag: String,
     pub options: QueryOptions,
@@ -19,18 +33,238 @@ pub struct QueryBindingInfo {
 
 type QueryFn = for<'a> fn(&'a [String]) -> Option<QueryBindingInfo>;
 
+/// A query string that was rejected for asking too much of the database,
+/// either before it ran or because it ran for too long.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QueryTooExpensive {
+    /// The query has too many `;` separated clauses.
+    TooManyClauses {
+        /// The number of clauses in the query.
+        count: usize,
+        /// The maximum number of clauses allowed.
+        max: usize,
+    },
+    /// The query has too many filters, across all of its clauses.
+    TooManyFilters {
+        /// The number of filters in the query.
+        count: usize,
+        /// The maximum number of filters allowed.
+        max: usize,
+    },
+    /// The query has too many `:contains` & `:includes` filters, each of
+    /// which joins against the recipe tables.
+    TooManyJoinFilters {
+        /// The number of joining filters in the query.
+        count: usize,
+        /// The maximum number of joining filters allowed.
+        max: usize,
+    },
+    /// A regex pattern is too long.
+    PatternTooLong {
+        /// The length of the pattern.
+        length: usize,
+        /// The maximum pattern length allowed.
+        max: usize,
+    },
+    /// A regex pattern uses a construct that can be slow to match.
+    DisallowedPattern {
+        /// The offending pattern.
+        pattern: String,
+        /// A description of the disallowed construct.
+        construct: &'static str,
+    },
+    /// The query ran for longer than it's allowed to.
+    TimedOut {
+        /// The time the query was allowed to run for.
+        limit: Duration,
+    },
+}
+
+// Each `:name` pattern that isn't a plain substring is matched with an
+// unindexed RLIKE over every item, so the limits are kept modest.
+const MAX_CLAUSES: usize = 8;
+const MAX_FILTERS: usize = 32;
+const MAX_JOIN_FILTERS: usize = 4;
+const MAX_PATTERN_LENGTH: usize = 100;
+const MAX_REPETITION: u32 = 100;
+
 impl Query {
-    pub(crate) fn from_query(query_str: &str) -> Option<QueryBindingInfo> {
+    /// Builds the SQL clause for a query string. `@name` aliases are replaced
+    /// by the saved queries in `aliases`, keyed by their lowercased names.
+    pub(crate) fn from_query(
+        query_str: &str,
+        aliases: &HashMap<String, String>,
+    ) -> Result<Option<QueryBindingInfo>, QueryTooExpensive> {
+        let clauses = Self::clauses(query_str, aliases)?;
+        Ok(QueryBindingInfo::join(
+            " OR ",
+            clauses.into_iter().map(Self::query_group_clause),
+        ))
+    }
+
+    /// Splits a query string into its `;` separated clauses of filters, with
+    /// `@name` aliases expanded. Queries that are too complex are rejected.
+    pub(crate) fn clauses(
+        query_str: &str,
+        aliases: &HashMap<String, String>,
+    ) -> Result<Vec<Vec<Query>>, QueryTooExpensive> {
         if query_str.trim().is_empty() {
-            return None;
+            return Ok(Vec::new());
         }
 
-        QueryBindingInfo::join(
-            " OR ",
-            Self::parse_all_clauses(query_str)
-                .into_iter()
-                .map(Self::query_group_clause),
-        )
+        let clauses = Self::expand_aliases(Self::parse_all_clauses(query_str), aliases)?;
+        Self::check_budget(&clauses)?;
+        Ok(clauses)
+    }
+
+    /// Checks that each regex pattern in a query string is valid, so that
+    /// mistakes can be pointed out, rather than failing in the database.
+    /// Invalid patterns fail with an [ItemDBError::Query] error.
+    pub(crate) fn check_syntax(query_str: &str) -> Result<(), ItemDBError> {
+        let clauses = Self::parse_all_clauses(query_str);
+        let offsets = Self::filter_offsets(query_str);
+        for (query, &offset) in clauses.iter().flatten().zip(offsets.iter().flatten()) {
+            let Some(options) = pattern_options(query) else {
+                continue;
+            };
+            let options = options.join("|");
+            let StringCompareType::Regexp(pattern) = regex_string_compare_type(&options) else {
+                continue;
+            };
+            if let Err(err) = pattern_regex(pattern) {
+                // Syntax errors span several lines, pointing at the problem
+                let err = err.to_string();
+                let err = err.lines().last().unwrap_or_default();
+                let err = err.strip_prefix("error: ").unwrap_or(err);
+                return Err(ItemDBError::Query {
+                    offset,
+                    reason: format!("invalid pattern '{pattern}': {err}"),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// The byte offsets in a query string of the filters returned by
+    /// [parse_all_clauses](Self::parse_all_clauses).
+    pub(crate) fn filter_offsets(query_str: &str) -> Vec<Vec<usize>> {
+        let mut offsets = Vec::new();
+        let mut start = 0;
+        for clause in query_str.split(';') {
+            let mut clause_offsets = Vec::new();
+            let mut continued = false;
+            for query in clause.split(',') {
+                if !continued {
+                    clause_offsets.push(start + query.len() - query.trim_start().len());
+                }
+                continued = query.ends_with('\\');
+                start += query.len() + 1;
+            }
+            offsets.push(clause_offsets);
+        }
+        offsets
+    }
+
+    /// The lowercased names of the `@name` aliases used in a query string.
+    pub(crate) fn alias_names(query_str: &str) -> Vec<String> {
+        Self::parse_all_clauses(query_str)
+            .iter()
+            .flatten()
+            .filter_map(|query| query.tag.strip_prefix('@'))
+            .map(str::to_lowercase)
+            .unique()
+            .collect()
+    }
+
+    /// Replaces each `@name` alias with the filters of its saved query. A
+    /// saved query with several `;` clauses splits the clause using it into
+    /// one clause for each of them. As each alias multiplies the clauses, the
+    /// clause & filter limits are checked as they grow, rather than at the end.
+    fn expand_aliases(
+        clauses: Vec<Vec<Query>>,
+        aliases: &HashMap<String, String>,
+    ) -> Result<Vec<Vec<Query>>, QueryTooExpensive> {
+        let mut expanded = Vec::new();
+        for clause in clauses {
+            let mut variants = vec![Vec::new()];
+            for query in clause {
+                let Some(name) = query.tag.strip_prefix('@') else {
+                    variants
+                        .iter_mut()
+                        .for_each(|variant| variant.push(query.clone()));
+                    continue;
+                };
+                let Some(alias) = aliases.get(&name.to_lowercase()) else {
+                    log::info!(target: "ffxiv_items", "Unknown saved query: {name}");
+                    continue;
+                };
+
+                let alias_clauses = Self::parse_all_clauses(alias);
+                let count = expanded.len() + variants.len().saturating_mul(alias_clauses.len());
+                if count > MAX_CLAUSES {
+                    return Err(QueryTooExpensive::TooManyClauses {
+                        count,
+                        max: MAX_CLAUSES,
+                    });
+                }
+
+                variants = variants
+                    .iter()
+                    .cartesian_product(alias_clauses)
+                    .map(|(variant, alias_clause)| {
+                        variant.iter().cloned().chain(alias_clause).collect()
+                    })
+                    .collect();
+
+                let count = expanded.iter().chain(&variants).map(Vec::len).sum();
+                if count > MAX_FILTERS {
+                    return Err(QueryTooExpensive::TooManyFilters {
+                        count,
+                        max: MAX_FILTERS,
+                    });
+                }
+            }
+            expanded.extend(variants);
+        }
+        Ok(expanded)
+    }
+
+    fn check_budget(clauses: &[Vec<Query>]) -> Result<(), QueryTooExpensive> {
+        if clauses.len() > MAX_CLAUSES {
+            return Err(QueryTooExpensive::TooManyClauses {
+                count: clauses.len(),
+                max: MAX_CLAUSES,
+            });
+        }
+
+        let queries = clauses.iter().flatten().collect_vec();
+        if queries.len() > MAX_FILTERS {
+            return Err(QueryTooExpensive::TooManyFilters {
+                count: queries.len(),
+                max: MAX_FILTERS,
+            });
+        }
+
+        let join_filters = queries
+            .iter()
+            .filter(|query| matches!(&query.tag[..], ":contains" | ":includes"))
+            .count();
+        if join_filters > MAX_JOIN_FILTERS {
+            return Err(QueryTooExpensive::TooManyJoinFilters {
+                count: join_filters,
+                max: MAX_JOIN_FILTERS,
+            });
+        }
+
+        for options in queries.into_iter().filter_map(pattern_options) {
+            if let StringCompareType::Regexp(pattern) =
+                regex_string_compare_type(&options.join("|"))
+            {
+                check_pattern(pattern)?;
+            }
+        }
+
+        Ok(())
     }
 
     fn query_group_clause(query_group: Vec<Query>) -> Option<QueryBindingInfo> {
@@ -45,13 +279,7 @@ impl Query {
                         continue;
                     }
                     log::info!(target: "ffxiv_items", "Missing query tag: {tag}, interpreting it as a :name query",);
-                    let mut new_options = options.clone();
-                    if let Some(option) = new_options.first_mut() {
-                        *option = format!("{tag} {option}");
-                    } else {
-                        new_options.push(tag);
-                    }
-                    filter_name(&new_options)
+                    filter_name(&name_options(&tag, &options))
                 }
             })
         }
@@ -59,7 +287,7 @@ impl Query {
         QueryBindingInfo::join(" AND ", db_queries.into_iter())
     }
 
-    fn parse_all_clauses(query_str: &str) -> Vec<Vec<Query>> {
+    pub(crate) fn parse_all_clauses(query_str: &str) -> Vec<Vec<Query>> {
         query_str.split(';').map(Self::parse_queries).collect()
     }
 
@@ -119,12 +347,23 @@ impl Query {
 
         // Normal filters
         map.insert(":name", filter_name);
+        map.insert(":id", filter_id);
         map.insert(":rlevel", filter_recipe_level);
         map.insert(":elevel", filter_equip_level);
         map.insert(":ilevel", filter_ilevel);
         map.insert(":cat", filter_ui_category);
+        map.insert(":mbcat", filter_search_category);
+        map.insert(":slot", filter_equip_slot);
+        map.insert(":class", filter_class_job);
+        map.insert(":stat", filter_stat);
+        map.insert(":food", filter_food);
+        map.insert(":book", filter_recipe_book);
         map.insert(":contains", filter_contains);
         map.insert(":includes", filter_includes);
+        map.insert(":workshop", filter_company_workshop);
+        map.insert(":gcsupply", filter_gc_supply);
+        map.insert(":delivery", filter_custom_delivery);
+        map.insert(":ishgard", filter_ishgard_restoration);
 
         // Result filters
         map.insert(":count", filter_noop);
@@ -135,6 +374,37 @@ impl Query {
     }
 }
 
+impl Display for QueryTooExpensive {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooManyClauses { count, max } => {
+                write!(f, "Query too expensive: {count} clauses, at most {max} are allowed")
+            }
+            Self::TooManyFilters { count, max } => {
+                write!(f, "Query too expensive: {count} filters, at most {max} are allowed")
+            }
+            Self::TooManyJoinFilters { count, max } => write!(
+                f,
+                "Query too expensive: {count} :contains/:includes filters, at most {max} are allowed"
+            ),
+            Self::PatternTooLong { length, max } => write!(
+                f,
+                "Query too expensive: pattern is {length} characters, at most {max} are allowed"
+            ),
+            Self::DisallowedPattern { pattern, construct } => {
+                write!(f, "Query too expensive: {construct} aren't allowed, in '{pattern}'")
+            }
+            Self::TimedOut { limit } => write!(
+                f,
+                "Query too expensive: it didn't finish within {} ms",
+                limit.as_millis()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for QueryTooExpensive {}
+
 impl QueryBindingInfo {
     fn from_op(table_name: &str, op: &str, bind_str: &str, binds: Vec<String>) -> Self {
         Self {
@@ -166,24 +436,27 @@ enum StringCompareType<'a> {
 }
 
 fn filter_generic_regex(table_name: &str, options: &[String]) -> Option<QueryBindingInfo> {
+    filter_generic_regex_field(&format!("{table_name}.name"), options)
+}
+
+fn filter_generic_regex_field(field: &str, options: &[String]) -> Option<QueryBindingInfo> {
     if options.is_empty() {
         return None;
     }
 
-    let table_name = format!("{table_name}.name");
     let pattern = options.join("|");
     Some(match regex_string_compare_type(&pattern) {
         StringCompareType::Exact(pattern) => {
             let binds = pattern.split('|').map(String::from).collect_vec();
             let bind_str = binds.iter().map(|_| "?").join(", ");
             let bind_str = format!("({bind_str})");
-            QueryBindingInfo::from_op(&table_name, "IN", &bind_str, binds)
+            QueryBindingInfo::from_op(field, "IN", &bind_str, binds)
         }
         StringCompareType::Regexp(pattern) => {
-            QueryBindingInfo::from_op(&table_name, "RLIKE", "?", vec![pattern.replace(' ', "\\s")])
+            QueryBindingInfo::from_op(field, "RLIKE", "?", vec![pattern.replace(' ', "\\s")])
         }
         StringCompareType::Like(pattern) => {
-            QueryBindingInfo::from_op(&table_name, "LIKE", "?", vec![format!("%{pattern}%")])
+            QueryBindingInfo::from_op(field, "LIKE", "?", vec![format!("%{pattern}%")])
         }
     })
 }
@@ -207,42 +480,225 @@ fn regex_string_compare_type(pattern: &str) -> StringCompareType<'_> {
     }
 }
 
-fn filter_generic_range(field: &str, options: &[String]) -> Option<QueryBindingInfo> {
-    if options.is_empty() {
-        return None;
+/// The options of a filter that are matched as strings, which may be patterns.
+fn pattern_options(query: &Query) -> Option<Vec<String>> {
+    match &query.tag[..] {
+        ":name" | ":cat" | ":mbcat" | ":slot" | ":food" | ":book" | ":contains" | ":includes"
+        | ":workshop" | ":gcsupply" | ":delivery" | ":ishgard" => Some(query.options.clone()),
+        tag if !tag.starts_with(':') => Some(name_options(tag, &query.options)),
+        _ => None,
     }
+}
+
+/// Compiles an RLIKE pattern, with spaces matching any whitespace as they do
+/// in the database.
+fn pattern_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(&pattern.replace(' ', "\\s"))
+        .case_insensitive(true)
+        .build()
+}
+
+/// Matches strings the same way as the SQL filters, for items that are held in
+/// memory. As with the database's collation, case is ignored.
+pub(crate) enum StringMatcher {
+    Exact(Vec<String>),
+    Pattern(Regex),
+}
+
+impl StringMatcher {
+    /// Builds a matcher for the options of a filter, or `None` if there
+    /// aren't any options.
+    pub(crate) fn new(options: &[String]) -> Result<Option<Self>, regex::Error> {
+        if options.is_empty() {
+            return Ok(None);
+        }
 
+        let pattern = options.join("|");
+        let matcher = match regex_string_compare_type(&pattern) {
+            StringCompareType::Exact(pattern) => {
+                Self::Exact(pattern.split('|').map(str::to_lowercase).collect())
+            }
+            StringCompareType::Regexp(pattern) => Self::Pattern(pattern_regex(pattern)?),
+            StringCompareType::Like(pattern) => {
+                // `%` & `_` are the only special characters of LIKE
+                let pattern = pattern
+                    .split('%')
+                    .map(|part| part.split('_').map(regex::escape).join("."))
+                    .join(".*");
+                Self::Pattern(RegexBuilder::new(&pattern).case_insensitive(true).build()?)
+            }
+        };
+        Ok(Some(matcher))
+    }
+
+    pub(crate) fn is_match(&self, value: &str) -> bool {
+        match self {
+            Self::Exact(values) => values.contains(&value.to_lowercase()),
+            Self::Pattern(regex) => regex.is_match(value),
+        }
+    }
+}
+
+/// Rejects regex constructs that can make MySQL's matcher backtrack badly, or
+/// that build very large automata.
+fn check_pattern(pattern: &str) -> Result<(), QueryTooExpensive> {
+    let disallowed = |construct| {
+        Err(QueryTooExpensive::DisallowedPattern {
+            pattern: pattern.to_string(),
+            construct,
+        })
+    };
+
+    if pattern.len() > MAX_PATTERN_LENGTH {
+        return Err(QueryTooExpensive::PatternTooLong {
+            length: pattern.len(),
+            max: MAX_PATTERN_LENGTH,
+        });
+    }
+    if pattern.contains("(?") {
+        return disallowed("lookarounds & special groups");
+    }
+
+    // Each open group tracks whether it contains a quantifier, so that
+    // quantified groups of quantifiers, e.g. `(a+)*`, can be found
+    let mut groups = vec![false];
+    let mut chars = pattern.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            // The escaped character is always consumed, so it's never special
+            '\\' if chars.next().is_some_and(|ch| ch.is_ascii_digit()) => {
+                return disallowed("backreferences");
+            }
+            '[' => {
+                // Skip character classes, where everything is literal
+                for ch in chars.by_ref() {
+                    if ch == ']' {
+                        break;
+                    }
+                }
+            }
+            '(' => groups.push(false),
+            ')' if groups.len() > 1 => {
+                let has_quantifier = groups.pop().unwrap_or_default();
+                let is_quantified = chars.peek().is_some_and(|ch| "*+?{".contains(*ch));
+                if has_quantifier && is_quantified {
+                    return disallowed("nested quantifiers");
+                }
+                *groups.last_mut().unwrap() |= has_quantifier;
+            }
+            '*' | '+' | '?' => *groups.last_mut().unwrap() = true,
+            '{' => {
+                let repetition = chars
+                    .by_ref()
+                    .take_while(|ch| *ch != '}')
+                    .collect::<String>();
+                let is_large = repetition
+                    .split(',')
+                    .filter_map(|count| count.trim().parse::<u32>().ok())
+                    .any(|count| count > MAX_REPETITION);
+                if is_large {
+                    return disallowed("repetition counts above 100");
+                }
+                *groups.last_mut().unwrap() = true;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn filter_generic_range(field: &str, options: &[String]) -> Option<QueryBindingInfo> {
+    let range = parse_range(options)?;
+    let (min, max) = (range.start(), range.end());
+    let clause = match min == max {
+        true => format!("{field} = {min}"),
+        false => format!("{field} >= {min} AND {field} <= {max}"),
+    };
+    Some(QueryBindingInfo {
+        clause,
+        binds: Vec::new(),
+    })
+}
+
+/// Parses the options of a range filter, e.g. `61|69`, as the range from the
+/// first to the last number. Options that aren't numbers are ignored.
+pub(crate) fn parse_range(options: &[String]) -> Option<RangeInclusive<u32>> {
     let values = options
         .iter()
         .filter_map(|level| level.parse::<u32>().ok())
-        .collect::<Vec<_>>();
-
-    let min = values.first().cloned().unwrap_or(0);
-    let max = values.last().cloned().unwrap_or(u16::MAX as u32);
-    let binds = Vec::new();
-    match values.len() {
-        0 => None,
-        1 => Some(QueryBindingInfo {
-            clause: format!("{field} = {min}"),
-            binds,
-        }),
-        _ => Some(QueryBindingInfo {
-            clause: format!("{field} >= {min} AND {field} <= {max}"),
-            binds,
-        }),
-    }
+        .collect_vec();
+    Some(*values.first()?..=*values.last()?)
 }
 
 ////////////////////////////////////////////////////////////
 
+/// Filters without a tag are treated as `:name` filters, with the first word
+/// as part of the name.
+pub(crate) fn name_options(tag: &str, options: &[String]) -> Vec<String> {
+    let mut new_options = options.to_vec();
+    if let Some(option) = new_options.first_mut() {
+        *option = format!("{tag} {option}");
+    } else {
+        new_options.push(tag.to_string());
+    }
+    new_options
+}
+
 fn filter_name(options: &[String]) -> Option<QueryBindingInfo> {
     filter_generic_regex("i", options)
 }
 
-fn filter_recipe_level(options: &[String]) -> Option<QueryBindingInfo> {
-    let Some(QueryBindingInfo { clause, binds }) = filter_generic_range("r.level", options) else {
+/// Matches items by their ids, e.g. `5358`. Options that aren't ids are
+/// ignored.
+fn filter_id(options: &[String]) -> Option<QueryBindingInfo> {
+    let ids = options
+        .iter()
+        .filter_map(|option| option.trim().parse::<u32>().ok())
+        .unique()
+        .collect_vec();
+    if ids.is_empty() {
         return None;
-    };
+    }
+
+    Some(QueryBindingInfo {
+        clause: format!("i.id IN ({})", ids.iter().join(", ")),
+        binds: Vec::new(),
+    })
+}
+
+fn filter_recipe_level(options: &[String]) -> Option<QueryBindingInfo> {
+    let QueryBindingInfo { clause, binds } = filter_generic_range("r.level", options)?;
+
+    Some(QueryBindingInfo {
+        clause: format!(
+            "i.id IN (
+                SELECT r.id
+                FROM {} AS r
+                WHERE {}
+            )",
+            RecipeTable::SQL_TABLE_NAME,
+            clause
+        ),
+        binds,
+    })
+}
+
+/// Matches recipes by the master recipe book needed to craft them, e.g.
+/// `Master Culinarian`, or `none` for recipes that don't need one.
+fn filter_recipe_book(options: &[String]) -> Option<QueryBindingInfo> {
+    let (none, books): (Vec<_>, Vec<_>) = options
+        .iter()
+        .cloned()
+        .partition(|option| option.eq_ignore_ascii_case("none"));
+    let without_book = (!none.is_empty()).then(|| QueryBindingInfo {
+        clause: "r.book IS NULL".to_string(),
+        binds: Vec::new(),
+    });
+    let QueryBindingInfo { clause, binds } = QueryBindingInfo::join(
+        " OR ",
+        [without_book, filter_generic_regex_field("r.book", &books)].into_iter(),
+    )?;
 
     Some(QueryBindingInfo {
         clause: format!(
@@ -267,9 +723,7 @@ fn filter_ilevel(options: &[String]) -> Option<QueryBindingInfo> {
 }
 
 fn filter_ui_category(options: &[String]) -> Option<QueryBindingInfo> {
-    let Some(QueryBindingInfo { clause, binds }) = filter_generic_regex("c", options) else {
-        return None;
-    };
+    let QueryBindingInfo { clause, binds } = filter_generic_regex("c", options)?;
 
     Some(QueryBindingInfo {
         clause: format!(
@@ -285,11 +739,122 @@ fn filter_ui_category(options: &[String]) -> Option<QueryBindingInfo> {
     })
 }
 
-fn filter_contains(options: &[String]) -> Option<QueryBindingInfo> {
-    let Some(QueryBindingInfo { clause, binds }) = filter_generic_regex("i_g", options) else {
+/// Matches items by their market board category. Without any options, matches
+/// every item that can be sold on the market board.
+fn filter_search_category(options: &[String]) -> Option<QueryBindingInfo> {
+    let QueryBindingInfo { clause, binds } =
+        filter_generic_regex("sc", options).unwrap_or_else(|| QueryBindingInfo {
+            clause: "TRUE".into(),
+            binds: Vec::new(),
+        });
+
+    Some(QueryBindingInfo {
+        clause: format!(
+            "i.search_category IN (
+                SELECT sc.id
+                FROM {} AS sc
+                WHERE {}
+            )",
+            SearchCategoryTable::SQL_TABLE_NAME,
+            clause
+        ),
+        binds,
+    })
+}
+
+/// Matches equipment by the slot it's worn in, e.g. `Main Hand` or `Ring`.
+fn filter_equip_slot(options: &[String]) -> Option<QueryBindingInfo> {
+    filter_generic_regex_field("i.equip_slot", options)
+}
+
+/// Matches equipment wearable by any of the jobs, e.g. `CUL`, or job groups,
+/// e.g. `DoH`. Unknown jobs are ignored.
+fn filter_class_job(options: &[String]) -> Option<QueryBindingInfo> {
+    let bits = job_bits(options);
+    if bits == 0 {
         return None;
+    }
+
+    Some(QueryBindingInfo {
+        clause: format!("i.class_jobs & {bits} != 0"),
+        binds: Vec::new(),
+    })
+}
+
+/// Matches items by their stats, e.g. `Control>=50`, with any of `=`, `<`,
+/// `<=`, `>` & `>=`. A stat without a comparison matches any item with it.
+/// Options that can't be parsed are ignored.
+fn filter_stat(options: &[String]) -> Option<QueryBindingInfo> {
+    let (clauses, binds): (Vec<_>, Vec<_>) = options
+        .iter()
+        .filter_map(|option| {
+            let (stat, comparison) = parse_stat(option)?;
+            let clause = match comparison {
+                Some((op, value)) => format!("(st.stat = ? AND st.value {op} {value})"),
+                None => "st.stat = ?".to_string(),
+            };
+            Some((clause, stat))
+        })
+        .unzip();
+    if clauses.is_empty() {
+        return None;
+    }
+
+    Some(QueryBindingInfo {
+        clause: format!(
+            "i.id IN (
+                SELECT st.item_id
+                FROM {} AS st
+                WHERE {}
+            )",
+            ItemStatTable::SQL_TABLE_NAME,
+            clauses.join(" OR ")
+        ),
+        binds,
+    })
+}
+
+/// Parses a `:stat` option into the stat's name, and its comparison operator &
+/// value if it has one. Comparisons that can't be parsed are `None`.
+pub(crate) fn parse_stat(option: &str) -> Option<(String, Option<(&'static str, i32)>)> {
+    let Some(index) = option.find(['<', '>', '=']) else {
+        return Some((option.trim().to_string(), None));
     };
 
+    let (stat, comparison) = option.split_at(index);
+    let (op, value) = ["<=", ">=", "<", ">", "="]
+        .into_iter()
+        .find_map(|op| Some((op, comparison.strip_prefix(op)?)))?;
+    let value = value.trim().parse::<i32>().ok()?;
+    Some((stat.trim().to_string(), Some((op, value))))
+}
+
+/// Matches meals & medicines by the stats they raise, e.g. `CP`. Without any
+/// options, every meal & medicine matches.
+fn filter_food(options: &[String]) -> Option<QueryBindingInfo> {
+    let QueryBindingInfo { clause, binds } = filter_generic_regex_field("f.stat", options)
+        .unwrap_or_else(|| QueryBindingInfo {
+            clause: "TRUE".into(),
+            binds: Vec::new(),
+        });
+
+    Some(QueryBindingInfo {
+        clause: format!(
+            "i.id IN (
+                SELECT f.item_id
+                FROM {} AS f
+                WHERE {}
+            )",
+            FoodEffectTable::SQL_TABLE_NAME,
+            clause
+        ),
+        binds,
+    })
+}
+
+fn filter_contains(options: &[String]) -> Option<QueryBindingInfo> {
+    let QueryBindingInfo { clause, binds } = filter_generic_regex("i_g", options)?;
+
     Some(QueryBindingInfo {
         clause: format!(
             "i.id IN (
@@ -307,9 +872,7 @@ fn filter_contains(options: &[String]) -> Option<QueryBindingInfo> {
 }
 
 fn filter_includes(options: &[String]) -> Option<QueryBindingInfo> {
-    let Some(QueryBindingInfo { clause, binds }) = filter_generic_regex("i_n", options) else {
-        return None;
-    };
+    let QueryBindingInfo { clause, binds } = filter_generic_regex("i_n", options)?;
 
     Some(QueryBindingInfo {
         clause: format!(
@@ -327,6 +890,85 @@ fn filter_includes(options: &[String]) -> Option<QueryBindingInfo> {
     })
 }
 
+/// Matches company workshop projects by their workshop category, e.g.
+/// `Submersible`. Without any options, every workshop project matches.
+fn filter_company_workshop(options: &[String]) -> Option<QueryBindingInfo> {
+    let QueryBindingInfo { clause, binds } = filter_generic_regex_field("w.craft_type", options)
+        .unwrap_or_else(|| QueryBindingInfo {
+            clause: "TRUE".into(),
+            binds: Vec::new(),
+        });
+
+    Some(QueryBindingInfo {
+        clause: format!(
+            "i.id IN (
+                SELECT w.id
+                FROM {} AS w
+                WHERE {}
+            )",
+            CompanyCraftTable::SQL_TABLE_NAME,
+            clause
+        ),
+        binds,
+    })
+}
+
+/// Matches items requested by Grand Company supply & provisioning missions,
+/// by the requesting job, e.g. `CUL`. Without any options, every requested
+/// item matches.
+fn filter_gc_supply(options: &[String]) -> Option<QueryBindingInfo> {
+    let QueryBindingInfo { clause, binds } = filter_generic_regex_field("s.job", options)
+        .unwrap_or_else(|| QueryBindingInfo {
+            clause: "TRUE".into(),
+            binds: Vec::new(),
+        });
+
+    Some(QueryBindingInfo {
+        clause: format!(
+            "i.id IN (
+                SELECT s.item_id
+                FROM {} AS s
+                WHERE {}
+            )",
+            GcSupplyDutyTable::SQL_TABLE_NAME,
+            clause
+        ),
+        binds,
+    })
+}
+
+fn filter_custom_delivery(options: &[String]) -> Option<QueryBindingInfo> {
+    filter_collectable(RewardSource::CustomDelivery, options)
+}
+
+fn filter_ishgard_restoration(options: &[String]) -> Option<QueryBindingInfo> {
+    filter_collectable(RewardSource::IshgardRestoration, options)
+}
+
+/// Matches collectables turned in to `source`, by the scrip they reward, e.g.
+/// `Purple`. Without any options, every collectable matches.
+fn filter_collectable(source: RewardSource, options: &[String]) -> Option<QueryBindingInfo> {
+    let QueryBindingInfo { clause, binds } = filter_generic_regex_field("r.currency", options)
+        .unwrap_or_else(|| QueryBindingInfo {
+            clause: "TRUE".into(),
+            binds: Vec::new(),
+        });
+
+    Some(QueryBindingInfo {
+        clause: format!(
+            "i.id IN (
+                SELECT r.item_id
+                FROM {} AS r
+                WHERE r.source = {} AND {}
+            )",
+            CollectableRewardTable::SQL_TABLE_NAME,
+            source.id(),
+            clause
+        ),
+        binds,
+    })
+}
+
 /*
 fn filter_leve<'a>(options: &[String]) {
     let categories = options;
@@ -354,3 +996,192 @@ fn filter_noop(_options: &[String]) -> Option<QueryBindingInfo> {
 }
 
 ////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cost(query_str: &str) -> Result<(), QueryTooExpensive> {
+        Query::from_query(query_str, &HashMap::new()).map(|_| ())
+    }
+
+    fn tags(clauses: &[Vec<Query>]) -> Vec<Vec<&str>> {
+        clauses
+            .iter()
+            .map(|clause| clause.iter().map(|query| &query.tag[..]).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_expand_aliases() {
+        let aliases = HashMap::from([
+            (
+                "scrips90".to_string(),
+                ":name ^Rarefied, :rlevel 90".to_string(),
+            ),
+            ("mats".to_string(), ":cat !Metal; :cat !Lumber".to_string()),
+        ]);
+        assert_eq!(
+            Query::alias_names("@Scrips90, :cat !Metal; @mats, @scrips90"),
+            vec!["scrips90", "mats"]
+        );
+
+        let clauses = Query::parse_all_clauses("@Scrips90, :cat !Metal; @mats, :ilevel 1");
+        let clauses = Query::expand_aliases(clauses, &aliases).unwrap();
+        assert_eq!(
+            tags(&clauses),
+            vec![
+                vec![":name", ":rlevel", ":cat"],
+                vec![":cat", ":ilevel"],
+                vec![":cat", ":ilevel"],
+            ]
+        );
+        assert_eq!(clauses[1][0].options, vec!["!Metal"]);
+        assert_eq!(clauses[2][0].options, vec!["!Lumber"]);
+
+        let clauses =
+            Query::expand_aliases(Query::parse_all_clauses("@unknown, a"), &aliases).unwrap();
+        assert_eq!(tags(&clauses), vec![vec!["a"]]);
+    }
+
+    #[test]
+    fn test_expand_aliases_budget() {
+        let aliases = HashMap::from([
+            ("mats".to_string(), ":cat !Metal; :cat !Lumber".to_string()),
+            ("iron".to_string(), ":name Iron".to_string()),
+        ]);
+
+        // Expanded in full, this would be 2^64 clauses
+        let start = std::time::Instant::now();
+        let query_str = ["@mats"; 64].join(", ");
+        assert!(matches!(
+            Query::clauses(&query_str, &aliases),
+            Err(QueryTooExpensive::TooManyClauses { .. })
+        ));
+
+        let query_str = ["@iron"; 10_000].join(", ");
+        assert!(matches!(
+            Query::clauses(&query_str, &aliases),
+            Err(QueryTooExpensive::TooManyFilters { .. })
+        ));
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_query_budget() {
+        assert_eq!(cost(":name ^Rarefied, :rlevel 61|69"), Ok(()));
+        assert_eq!(cost(":name !Iron Ingot|Iron Ore"), Ok(()));
+        assert_eq!(cost(":name Timeworn [O|K].*skin map"), Ok(()));
+        assert_eq!(cost(":name (Iron|Steel) Ingot{1,2}"), Ok(()));
+
+        let clauses = [":name Iron"; MAX_CLAUSES + 1].join("; ");
+        assert!(matches!(
+            cost(&clauses),
+            Err(QueryTooExpensive::TooManyClauses { .. })
+        ));
+
+        let includes = [":includes Iron"; MAX_JOIN_FILTERS + 1].join(", ");
+        assert!(matches!(
+            cost(&includes),
+            Err(QueryTooExpensive::TooManyJoinFilters { .. })
+        ));
+
+        let long_pattern = format!(":name ^{}", "a".repeat(MAX_PATTERN_LENGTH));
+        assert!(matches!(
+            cost(&long_pattern),
+            Err(QueryTooExpensive::PatternTooLong { .. })
+        ));
+        // Exact matches are an indexed lookup, so aren't limited
+        let long_exact = format!(":name !{}", "a".repeat(MAX_PATTERN_LENGTH));
+        assert_eq!(cost(&long_exact), Ok(()));
+    }
+
+    #[test]
+    fn test_disallowed_patterns() {
+        for pattern in ["(a+)+$", "^(.*a)*", "(\\w)\\1", "(?=Iron)", "^a{1000}"] {
+            assert!(
+                matches!(
+                    cost(&format!(":name {pattern}")),
+                    Err(QueryTooExpensive::DisallowedPattern { .. })
+                ),
+                "{pattern}"
+            );
+        }
+        assert_eq!(cost(":name Iron (Ore)+"), Ok(()));
+        // Without any regex characters, this is a plain substring match
+        assert_eq!(cost(":name a{1000}"), Ok(()));
+        // Untagged filters are checked as names too
+        assert!(cost("(a+)+$").is_err());
+        assert!(cost(":workshop (a+)+$").is_err());
+        assert!(cost(":gcsupply (a+)+$").is_err());
+        assert!(cost(":delivery (a+)+$").is_err());
+        assert!(cost(":slot (a+)+$").is_err());
+        assert!(cost(":food (a+)+$").is_err());
+        assert!(cost(":book (a+)+$").is_err());
+        assert!(cost(":mbcat (a+)+$").is_err());
+    }
+
+    #[test]
+    fn test_check_syntax() {
+        assert_eq!(Query::check_syntax(":name Iron (Ore|Ingot)"), Ok(()));
+        assert_eq!(Query::check_syntax(":name !(Iron Ore"), Ok(()));
+        // LIKE patterns are escaped, so aren't checked
+        assert_eq!(
+            Query::check_syntax(":cat Arms\\, Tools; :rlevel 90"),
+            Ok(())
+        );
+
+        let err = Query::check_syntax(":rlevel 90, :cat Arms; :name (Iron|Steel Ingot");
+        assert!(matches!(
+            err,
+            Err(ItemDBError::Query { offset: 23, ref reason }) if reason.contains("unclosed group")
+        ));
+        let err = Query::check_syntax(":cat Arms\\, Tools, [Iron");
+        assert!(matches!(err, Err(ItemDBError::Query { offset: 19, .. })));
+    }
+
+    #[test]
+    fn test_filter_id() {
+        let options = ["5358", " 5359", "5358", "wind shard"].map(String::from);
+        let QueryBindingInfo { clause, binds } = filter_id(&options).unwrap();
+        assert_eq!(clause, "i.id IN (5358, 5359)");
+        assert!(binds.is_empty());
+        assert!(filter_id(&["-1".into()]).is_none());
+    }
+
+    #[test]
+    fn test_filter_recipe_book() {
+        let options = ["none", "Master Culinarian"].map(String::from);
+        let QueryBindingInfo { clause, binds } = filter_recipe_book(&options).unwrap();
+        assert!(clause.contains("r.book IS NULL OR r.book LIKE ?"));
+        assert_eq!(binds, vec!["%Master Culinarian%"]);
+
+        let QueryBindingInfo { clause, binds } = filter_recipe_book(&["None".into()]).unwrap();
+        assert!(clause.contains("WHERE r.book IS NULL"));
+        assert!(binds.is_empty());
+        assert!(filter_recipe_book(&[]).is_none());
+    }
+
+    #[test]
+    fn test_filter_search_category() {
+        let QueryBindingInfo { clause, binds } =
+            filter_search_category(&["!Furnishings".into()]).unwrap();
+        assert!(clause.contains("WHERE sc.name IN (?)"));
+        assert_eq!(binds, vec!["Furnishings"]);
+
+        let QueryBindingInfo { clause, binds } = filter_search_category(&[]).unwrap();
+        assert!(clause.contains("WHERE TRUE"));
+        assert!(binds.is_empty());
+    }
+
+    #[test]
+    fn test_filter_stat() {
+        let options = ["Control>=50", "CP", "Gathering <10", "GP>>1"].map(String::from);
+        let QueryBindingInfo { clause, binds } = filter_stat(&options).unwrap();
+        assert!(clause.contains(
+            "(st.stat = ? AND st.value >= 50) OR st.stat = ? OR (st.stat = ? AND st.value < 10)"
+        ));
+        assert_eq!(binds, vec!["Control", "CP", "Gathering"]);
+        assert!(filter_stat(&["CP=lots".to_string()]).is_none());
+    }
+}
@@ -19,6 +19,84 @@ pub struct Recipe {
     /// The character level required to craft this recipe.
     pub level: u32,
     pub(crate) stars: u32,
+    /// The difficulty of crafting the recipe. Zeroed for company workshop
+    /// projects, which aren't crafted by a single crafter.
+    pub difficulty: RecipeDifficulty,
+    /// The master recipe book that must be read before the recipe can be
+    /// crafted, if any.
+    pub book: Option<RecipeBook>,
+    /// Where the recipe is crafted.
+    pub kind: RecipeKind,
+    /// The phases of a company workshop project, in order. The `inputs` are
+    /// the totals across every phase. Empty for other kinds of recipes.
+    pub phases: Vec<CraftPhase>,
+}
+
+/// The progress, quality & durability of a crafter recipe, along with the
+/// values that scale a crafter's stats against the recipe's level.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RecipeDifficulty {
+    /// The progress needed to complete the craft.
+    pub progress: u32,
+    /// The maximum quality of the craft.
+    pub quality: u32,
+    /// The durability the craft starts with.
+    pub durability: u32,
+    /// Divides a crafter's craftsmanship when calculating progress.
+    pub progress_divider: u32,
+    /// Divides a crafter's control when calculating quality.
+    pub quality_divider: u32,
+    /// The percentage of progress gained when the crafter's level is no
+    /// higher than the recipe's.
+    pub progress_modifier: u32,
+    /// The percentage of quality gained when the crafter's level is no
+    /// higher than the recipe's.
+    pub quality_modifier: u32,
+}
+
+/// A master recipe book, which unlocks recipes once read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecipeBook {
+    /// The item_id of the book.
+    pub item_id: u32,
+    /// The name of the book, e.g. `Master Culinarian X`.
+    pub name: String,
+}
+
+/// The kinds of recipes that items may be crafted with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RecipeKind {
+    /// A recipe crafted by a Disciple of the Hand.
+    #[default]
+    Crafter,
+    /// A free company workshop project, e.g. airship & submersible parts,
+    /// which is completed in phases.
+    CompanyWorkshop,
+}
+
+/// A single phase of a company workshop project.
+#[derive(Clone, Debug)]
+pub struct CraftPhase {
+    /// The items that must be supplied to complete the phase.
+    pub supplies: Vec<PhaseSupply>,
+}
+
+/// An item supplied to a company workshop phase, in sets.
+#[derive(Clone, Debug)]
+pub struct PhaseSupply {
+    /// The item_id of the supplied item.
+    pub item_id: u32,
+    /// The number of items in each set.
+    pub set_quantity: u32,
+    /// The number of sets required to complete the phase.
+    pub sets_required: u32,
+}
+
+impl PhaseSupply {
+    /// The total number of items supplied over every set.
+    pub fn count(&self) -> u32 {
+        self.set_quantity * self.sets_required
+    }
 }
 
 impl Recipe {
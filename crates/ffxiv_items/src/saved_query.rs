@@ -0,0 +1,160 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+};
+
+use anyhow::Result;
+
+use crate::{tables::SavedQueryTable, ItemDB, ItemDBError, Query, QueryTooExpensive};
+
+/// A query string saved under a name, along with the defaults to show its
+/// results with. Saved queries can be used within other queries as `@name`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SavedQuery {
+    /// The name of the query, made of letters, digits, `_` & `-`. Names are
+    /// compared ignoring case.
+    pub name: String,
+    /// The query string, e.g. `:name ^Rarefied, :rlevel 90`.
+    pub query: String,
+    /// The number of each item to craft.
+    pub count: Option<u32>,
+    /// The number of listings to consider for each item.
+    pub limit: Option<u32>,
+    /// The minimum sales per day for an item to be shown.
+    pub min_velocity: Option<f32>,
+}
+
+/// A saved query that was rejected, or an `@name` alias that doesn't match
+/// any saved query.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SavedQueryError {
+    /// The name is empty, too long, or has characters other than letters,
+    /// digits, `_` & `-`.
+    InvalidName(String),
+    /// The query string is empty.
+    EmptyQuery,
+    /// The query string is longer than can be saved.
+    QueryTooLong {
+        /// The length of the query string.
+        length: usize,
+        /// The maximum length allowed.
+        max: usize,
+    },
+    /// The query string uses an `@name` alias, which saved queries can't.
+    NestedAlias(String),
+    /// The query string is too expensive to run.
+    TooExpensive(QueryTooExpensive),
+    /// A query string uses an `@name` alias that isn't saved.
+    UnknownAlias(String),
+}
+
+const MAX_NAME_LENGTH: usize = 50;
+const MAX_QUERY_LENGTH: usize = 1000;
+
+////////////////////////////////////////////////////////////
+
+impl ItemDB {
+    /// Returns every saved query, ordered by name.
+    pub async fn saved_queries(&self) -> Result<Vec<SavedQuery>, ItemDBError> {
+        Ok(SavedQueryTable::new(self).all().await?)
+    }
+
+    /// Returns the saved query named `name`, ignoring case.
+    pub async fn saved_query(&self, name: &str) -> Result<Option<SavedQuery>, ItemDBError> {
+        let mut queries = SavedQueryTable::new(self)
+            .by_names(&[name.to_string()])
+            .await?;
+        Ok(queries.pop())
+    }
+
+    /// Saves a query, replacing any saved query with the same name. Queries
+    /// that would be rejected when run can't be saved, and fail with an
+    /// [ItemDBError::SavedQuery] error, or an [ItemDBError::Query] error for
+    /// invalid patterns.
+    pub async fn save_query(&self, query: &SavedQuery) -> Result<(), ItemDBError> {
+        query.validate()?;
+        Query::check_syntax(&query.query)?;
+        SavedQueryTable::new(self).upsert(query).await?;
+        self.cache.clear_queries();
+        Ok(())
+    }
+
+    /// Deletes the saved query named `name`, returning whether it existed.
+    pub async fn delete_saved_query(&self, name: &str) -> Result<bool, ItemDBError> {
+        let deleted = SavedQueryTable::new(self).delete(name).await?;
+        self.cache.clear_queries();
+        Ok(deleted)
+    }
+
+    /// Looks up the saved queries for the `@name` aliases in a query string,
+    /// keyed by their lowercased names.
+    pub(crate) async fn query_aliases(&self, query: &str) -> Result<HashMap<String, String>> {
+        let names = Query::alias_names(query);
+        if names.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let aliases = SavedQueryTable::new(self)
+            .by_names(&names)
+            .await?
+            .into_iter()
+            .map(|saved| (saved.name.to_lowercase(), saved.query))
+            .collect::<HashMap<_, _>>();
+        if let Some(name) = names.into_iter().find(|name| !aliases.contains_key(name)) {
+            return Err(SavedQueryError::UnknownAlias(name).into());
+        }
+        Ok(aliases)
+    }
+}
+
+impl SavedQuery {
+    fn validate(&self) -> Result<(), SavedQueryError> {
+        let is_valid_name = !self.name.is_empty()
+            && self.name.len() <= MAX_NAME_LENGTH
+            && self
+                .name
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-');
+        if !is_valid_name {
+            return Err(SavedQueryError::InvalidName(self.name.clone()));
+        }
+
+        if self.query.trim().is_empty() {
+            return Err(SavedQueryError::EmptyQuery);
+        }
+        if let Some(alias) = Query::alias_names(&self.query).into_iter().next() {
+            return Err(SavedQueryError::NestedAlias(alias));
+        }
+        if self.query.len() > MAX_QUERY_LENGTH {
+            return Err(SavedQueryError::QueryTooLong {
+                length: self.query.len(),
+                max: MAX_QUERY_LENGTH,
+            });
+        }
+        Query::from_query(&self.query, &HashMap::new()).map_err(SavedQueryError::TooExpensive)?;
+        Ok(())
+    }
+}
+
+impl Display for SavedQueryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidName(name) => write!(
+                f,
+                "Invalid saved query name '{name}': use 1 to {MAX_NAME_LENGTH} letters, digits, '_' or '-'"
+            ),
+            Self::EmptyQuery => write!(f, "Saved queries can't be empty"),
+            Self::QueryTooLong { length, max } => write!(
+                f,
+                "Saved query is {length} characters, at most {max} are allowed"
+            ),
+            Self::NestedAlias(name) => {
+                write!(f, "Saved queries can't use other saved queries, in '@{name}'")
+            }
+            Self::TooExpensive(err) => err.fmt(f),
+            Self::UnknownAlias(name) => write!(f, "Unknown saved query '@{name}'"),
+        }
+    }
+}
+
+impl std::error::Error for SavedQueryError {}
@@ -0,0 +1,354 @@
+use std::{
+    io::{BufRead, Write},
+    time::Instant,
+};
+
+use anyhow::{bail, Context, Result};
+use futures::{try_join, TryStreamExt};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{mysql::MySqlRow, Column, QueryBuilder, Row};
+
+use crate::{
+    tables::{
+        strip_whitespace, CollectableRewardTable, CompanyCraftSupplyTable, CompanyCraftTable,
+        DatasetFilesTable, FoodEffectTable, GcSupplyDutyTable, GcSupplyRewardTable,
+        IngredientTable, InputIdsTable, ItemInfoTable, ItemStatTable, MateriaJoinRateTable,
+        MateriaTable, RecipeTable, SearchCategoryTable, UiCategoryTable, UpdateTable, BIND_MAX,
+    },
+    ItemDB, ItemDBError,
+};
+
+/// Identifies the first line of a snapshot.
+const SNAPSHOT_FORMAT: &str = "ffxiv_items_snapshot";
+
+/// Bumped whenever the schema of any snapshot table changes, as snapshots are
+/// restored column-for-column.
+const SNAPSHOT_VERSION: u32 = 11;
+
+/// Every table built while initializing the database. The github response
+/// cache is deliberately left out, as it's only useful to the machine that
+/// made the requests, as are saved queries, which aren't built from the
+/// dataset.
+const SNAPSHOT_TABLES: [&str; 17] = [
+    UpdateTable::SQL_TABLE_NAME,
+    DatasetFilesTable::SQL_TABLE_NAME,
+    ItemInfoTable::SQL_TABLE_NAME,
+    ItemStatTable::SQL_TABLE_NAME,
+    FoodEffectTable::SQL_TABLE_NAME,
+    MateriaTable::SQL_TABLE_NAME,
+    MateriaJoinRateTable::SQL_TABLE_NAME,
+    UiCategoryTable::SQL_TABLE_NAME,
+    SearchCategoryTable::SQL_TABLE_NAME,
+    RecipeTable::SQL_TABLE_NAME,
+    IngredientTable::SQL_TABLE_NAME,
+    InputIdsTable::SQL_TABLE_NAME,
+    CompanyCraftTable::SQL_TABLE_NAME,
+    CompanyCraftSupplyTable::SQL_TABLE_NAME,
+    GcSupplyDutyTable::SQL_TABLE_NAME,
+    GcSupplyRewardTable::SQL_TABLE_NAME,
+    CollectableRewardTable::SQL_TABLE_NAME,
+];
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotHeader {
+    format: String,
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TableHeader {
+    table: String,
+    columns: Vec<String>,
+}
+
+struct PendingRows {
+    table: String,
+    columns: Vec<String>,
+    rows: Vec<Vec<Value>>,
+}
+
+////////////////////////////////////////////////////////////
+
+impl ItemDB {
+    /// Writes the contents of every item table to `writer` as JSON lines.
+    ///
+    /// The first line is a versioned header. Each table then starts with a line
+    /// naming the table & its columns, followed by one JSON array per row.
+    /// Rows are ordered by every column in turn, so the output is
+    /// deterministic even for tables without a unique first column.
+    pub async fn export_snapshot<W: Write>(&self, writer: W) -> Result<(), ItemDBError> {
+        Ok(self.write_snapshot(writer).await?)
+    }
+
+    async fn write_snapshot<W: Write>(&self, mut writer: W) -> Result<()> {
+        let start = Instant::now();
+        self.create_snapshot_tables().await?;
+
+        let header = SnapshotHeader {
+            format: SNAPSHOT_FORMAT.into(),
+            version: SNAPSHOT_VERSION,
+        };
+        writeln!(writer, "{}", serde_json::to_string(&header)?)?;
+
+        for table in SNAPSHOT_TABLES {
+            let order = self
+                .table_columns(table)
+                .await?
+                .iter()
+                .map(|column| format!("`{column}`"))
+                .join(", ");
+            let query_string = format!("SELECT * FROM {table} ORDER BY {order}");
+            let mut sql_query = sqlx::query(&query_string).fetch(self);
+            let mut wrote_header = false;
+            while let Some(row) = sql_query.try_next().await? {
+                if !wrote_header {
+                    let header = TableHeader {
+                        table: table.into(),
+                        columns: row.columns().iter().map(|c| c.name().into()).collect(),
+                    };
+                    writeln!(writer, "{}", serde_json::to_string(&header)?)?;
+                    wrote_header = true;
+                }
+
+                let values = (0..row.len())
+                    .map(|index| column_value(&row, index))
+                    .collect::<Result<Vec<_>>>()
+                    .with_context(|| format!("Couldn't export a row of {table}"))?;
+                writeln!(writer, "{}", serde_json::to_string(&values)?)?;
+            }
+        }
+
+        writer.flush()?;
+        log::debug!(target: "ffxiv_items", "Exported snapshot: {:.3}s", start.elapsed().as_secs_f32());
+        Ok(())
+    }
+
+    /// Replaces the contents of every item table with a snapshot written by
+    /// [export_snapshot](Self::export_snapshot).
+    ///
+    /// As with [update](Self::update), the snapshot is restored & validated in
+    /// the staging database, and only then replaces the current tables. If it
+    /// can't be restored, the current tables are kept.
+    pub async fn import_snapshot<R: BufRead>(&self, reader: R) -> Result<(), ItemDBError> {
+        let start = Instant::now();
+        let staging = self.create_staging().await?;
+        let restored = staging.db.restore_validated_snapshot(reader).await;
+        self.finish_staging(staging, restored, &SNAPSHOT_TABLES)
+            .await
+            .context("Couldn't import snapshot")?;
+        self.cache.clear();
+
+        log::debug!(target: "ffxiv_items", "Imported snapshot: {:.3}s", start.elapsed().as_secs_f32());
+        Ok(())
+    }
+
+    async fn restore_validated_snapshot<R: BufRead>(&self, reader: R) -> Result<()> {
+        self.create_snapshot_tables().await?;
+        self.restore_snapshot(reader).await?;
+        let report = self.validate().await?;
+        if !report.is_valid() {
+            bail!("Imported snapshot failed validation: {report}");
+        }
+        Ok(())
+    }
+
+    async fn restore_snapshot<R: BufRead>(&self, reader: R) -> Result<()> {
+        let mut lines = reader.lines();
+        let Some(header) = lines.next() else {
+            bail!("Snapshot is empty");
+        };
+        let header: SnapshotHeader = serde_json::from_str(&header?)?;
+        if header.format != SNAPSHOT_FORMAT {
+            bail!("Not a snapshot: unknown format '{}'", header.format);
+        }
+        if header.version != SNAPSHOT_VERSION {
+            bail!(
+                "Unsupported snapshot version {}, expected {SNAPSHOT_VERSION}",
+                header.version
+            );
+        }
+
+        let mut pending: Option<PendingRows> = None;
+        for (line_num, line) in (2..).zip(lines) {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match serde_json::from_str::<Value>(&line)? {
+                Value::Array(row) => {
+                    let Some(pending) = pending.as_mut() else {
+                        bail!("Line {line_num}: row found before any table header");
+                    };
+                    if row.len() != pending.columns.len() {
+                        bail!(
+                            "Line {line_num}: expected {} values for {}, found {}",
+                            pending.columns.len(),
+                            pending.table,
+                            row.len()
+                        );
+                    }
+                    pending.rows.push(row);
+                    if pending.rows.len() >= BIND_MAX / pending.columns.len() {
+                        self.insert_rows(pending).await?;
+                    }
+                }
+                object => {
+                    let TableHeader { table, columns } = serde_json::from_value(object)
+                        .with_context(|| format!("Line {line_num}: invalid table header"))?;
+                    check_table_header(&table, &columns)
+                        .with_context(|| format!("Line {line_num}: invalid table header"))?;
+                    if let Some(previous) = pending.as_mut() {
+                        self.insert_rows(previous).await?;
+                    }
+                    pending = Some(PendingRows {
+                        table,
+                        columns,
+                        rows: Vec::new(),
+                    });
+                }
+            }
+        }
+
+        if let Some(pending) = pending.as_mut() {
+            self.insert_rows(pending).await?;
+        }
+        Ok(())
+    }
+
+    async fn insert_rows(&self, pending: &mut PendingRows) -> Result<()> {
+        if pending.rows.is_empty() {
+            return Ok(());
+        }
+
+        let PendingRows {
+            table,
+            columns,
+            rows,
+        } = pending;
+        let query_string = format!("INSERT INTO {table} ({}) ", columns.join(", "));
+        let mut query_builder = QueryBuilder::new(strip_whitespace(query_string));
+        let mut bind_error = None;
+        query_builder.push_values(rows.iter(), |mut b, row| {
+            for value in row {
+                match value {
+                    Value::Null => b.push_bind(None::<String>),
+                    Value::Bool(value) => b.push_bind(*value),
+                    Value::Number(value) if value.is_u64() => b.push_bind(value.as_u64()),
+                    Value::Number(value) if value.is_i64() => b.push_bind(value.as_i64()),
+                    Value::String(value) => b.push_bind(value.clone()),
+                    value => {
+                        bind_error.get_or_insert_with(|| value.clone());
+                        b.push_bind(None::<String>)
+                    }
+                };
+            }
+        });
+        if let Some(value) = bind_error {
+            bail!("Unsupported value in {table}: {value}");
+        }
+
+        query_builder.build().execute(self).await?;
+        rows.clear();
+        Ok(())
+    }
+
+    pub(crate) async fn create_snapshot_tables(&self) -> Result<()> {
+        let (updates, dataset_files, items, ui_categories, recipes, ingredients, input_ids) = (
+            UpdateTable::new(self),
+            DatasetFilesTable::new(self),
+            ItemInfoTable::new(self),
+            UiCategoryTable::new(self),
+            RecipeTable::new(self),
+            IngredientTable::new(self),
+            InputIdsTable::new(self),
+        );
+        let (company_crafts, company_craft_supplies, gc_supply_duties, gc_supply_rewards) = (
+            CompanyCraftTable::new(self),
+            CompanyCraftSupplyTable::new(self),
+            GcSupplyDutyTable::new(self),
+            GcSupplyRewardTable::new(self),
+        );
+        let (collectable_rewards, item_stats, food_effects, materia, materia_join_rates) = (
+            CollectableRewardTable::new(self),
+            ItemStatTable::new(self),
+            FoodEffectTable::new(self),
+            MateriaTable::new(self),
+            MateriaJoinRateTable::new(self),
+        );
+        let search_categories = SearchCategoryTable::new(self);
+        try_join!(
+            updates.create(),
+            dataset_files.create(),
+            items.create(),
+            ui_categories.create(),
+            search_categories.create(),
+            recipes.create(),
+            ingredients.create(),
+            input_ids.create(),
+            company_crafts.create(),
+            company_craft_supplies.create(),
+            gc_supply_duties.create(),
+            gc_supply_rewards.create(),
+            collectable_rewards.create(),
+            item_stats.create(),
+            food_effects.create(),
+            materia.create(),
+            materia_join_rates.create(),
+        )?;
+        Ok(())
+    }
+
+    /// The names of a table's columns, in the order they were declared.
+    async fn table_columns(&self, table: &str) -> Result<Vec<String>> {
+        let columns: Vec<String> = sqlx::query_scalar(
+            "SELECT COLUMN_NAME FROM information_schema.COLUMNS
+            WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = ?
+            ORDER BY ORDINAL_POSITION",
+        )
+        .bind(table)
+        .fetch_all(self)
+        .await?;
+        if columns.is_empty() {
+            bail!("Couldn't find the columns of {table}");
+        }
+        Ok(columns)
+    }
+}
+
+/// Table & column names are interpolated into the insert query, so only known
+/// tables and plain identifiers are accepted.
+fn check_table_header(table: &str, columns: &[String]) -> Result<()> {
+    if !SNAPSHOT_TABLES.contains(&table) {
+        bail!("Unknown table '{table}'");
+    }
+    if columns.is_empty() {
+        bail!("No columns listed for {table}");
+    }
+    let is_identifier =
+        |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if let Some(column) = columns.iter().find(|column| !is_identifier(column)) {
+        bail!("Invalid column name '{column}' for {table}");
+    }
+    Ok(())
+}
+
+/// The item tables only hold integers, strings & booleans, which are stored
+/// as small integers.
+fn column_value(row: &MySqlRow, index: usize) -> Result<Value> {
+    if let Ok(value) = row.try_get::<Option<u64>, _>(index) {
+        return Ok(value.map_or(Value::Null, Value::from));
+    }
+    if let Ok(value) = row.try_get::<Option<i64>, _>(index) {
+        return Ok(value.map_or(Value::Null, Value::from));
+    }
+    if let Ok(value) = row.try_get::<Option<String>, _>(index) {
+        return Ok(value.map_or(Value::Null, Value::from));
+    }
+    bail!(
+        "Unsupported type for column {}",
+        row.columns()[index].name()
+    )
+}
@@ -0,0 +1,424 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    time::Instant,
+};
+
+use anyhow::Result;
+use const_format::formatcp;
+use futures::{future::try_join_all, try_join};
+use itertools::Itertools;
+use mock_traits::FileDownloader;
+use sqlx::{mysql::MySqlRow, QueryBuilder, Row};
+
+use crate::{
+    column_values, csv_parse, latest_commit_from_github, CollectableReward, CommitInfo, CsvColumns,
+    ItemDB, ItemId, RewardSource, RewardTier,
+};
+
+use super::{
+    download_csv, fetch_by_ids, impl_table, impl_table_builder, strip_whitespace,
+    DatasetFilesTable, BIND_MAX,
+};
+
+////////////////////////////////////////////////////////////
+
+impl_table!(CollectableRewardTable);
+impl_table_builder!(CollectableRewardTableBuilder, FileDownloader);
+
+impl CollectableRewardTable<'_> {
+    pub async fn by_source(&self, source: RewardSource) -> Result<Vec<CollectableReward>> {
+        let start = Instant::now();
+        let query_string = strip_whitespace(format!("{SQL_SELECT} WHERE source = ?"));
+        let rows = sqlx::query(&query_string)
+            .bind(source.id())
+            .fetch_all(self.db)
+            .await?;
+
+        let rewards = rewards_from_rows(rows);
+        log::debug!(target: "ffxiv_items", "Query for {source:?} collectable rewards ({} returned): {:.3}s", rewards.len(), start.elapsed().as_secs_f32());
+        Ok(rewards)
+    }
+
+    pub async fn by_item_ids<I: ItemId>(&self, ids: &[I]) -> Result<Vec<CollectableReward>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let start = Instant::now();
+        let sql_select = format!("{SQL_SELECT} WHERE item_id IN");
+        let rows = fetch_by_ids(self.db, &sql_select, ids).await?;
+
+        let rewards = rewards_from_rows(rows);
+        log::debug!(target: "ffxiv_items", "Query for {} collectable rewards ({} returned): {:.3}s", ids.len(), rewards.len(), start.elapsed().as_secs_f32());
+        Ok(rewards)
+    }
+}
+
+/// Groups the tiers of each collectable, ordered by item id & source.
+fn rewards_from_rows(rows: Vec<MySqlRow>) -> Vec<CollectableReward> {
+    let mut rewards = BTreeMap::new();
+    for row in rows {
+        let Some(source) = RewardSource::from_id(row.get(0)) else {
+            continue;
+        };
+        let item_id: u32 = row.get(1);
+        let tier: u32 = row.get(2);
+        let reward_tier = RewardTier {
+            collectability: row.get(3),
+            currency: row.get(4),
+            scrips: row.get(5),
+            experience: row.get(6),
+        };
+        rewards
+            .entry((item_id, source.id()))
+            .or_insert_with(|| (source, BTreeMap::new()))
+            .1
+            .insert(tier, reward_tier);
+    }
+
+    rewards
+        .into_iter()
+        .map(|((item_id, _), (source, tiers))| CollectableReward {
+            source,
+            item_id,
+            tiers: tiers.into_values().collect(),
+        })
+        .collect()
+}
+
+////////////////////////////////////////////////////////////
+
+struct CsvReward {
+    currency: String,
+    tiers: [u32; 3],
+    experience: u32,
+}
+
+////////////////////////////////////////////////////////////
+
+const CSV_FILE_DELIVERY: &str = "SatisfactionSupply.csv";
+const CSV_FILE_DELIVERY_REWARD: &str = "SatisfactionSupplyReward.csv";
+const CSV_FILE_ISHGARD: &str = "HWDCrafterSupply.csv";
+const CSV_FILE_ISHGARD_REWARD: &str = "HWDCrafterSupplyReward.csv";
+const CSV_FILE_SHOP: &str = "CollectablesShopItem.csv";
+const CSV_FILE_SHOP_REFINE: &str = "CollectablesShopRefine.csv";
+const CSV_FILE_SHOP_REWARD: &str = "CollectablesShopRewardScrip.csv";
+
+const CSV_FILES: [&str; 7] = [
+    CSV_FILE_DELIVERY,
+    CSV_FILE_DELIVERY_REWARD,
+    CSV_FILE_ISHGARD,
+    CSV_FILE_ISHGARD_REWARD,
+    CSV_FILE_SHOP,
+    CSV_FILE_SHOP_REFINE,
+    CSV_FILE_SHOP_REWARD,
+];
+
+const TIER_NAMES: [&str; 3] = ["Low", "Mid", "High"];
+const ISHGARD_TIER_NAMES: [&str; 3] = ["Base", "Mid", "High"];
+const ISHGARD_CURRENCY: &str = "Skybuilders' Scrip";
+
+impl<F: FileDownloader> CollectableRewardTableBuilder<'_, F> {
+    pub async fn initialize(&self) -> Result<()> {
+        let (deliveries, ishgard, shop) = try_join!(
+            self.download_custom_deliveries(),
+            self.download_ishgard(),
+            self.download_collectables_shop()
+        )?;
+
+        // Items may be turned in to several NPCs; only the first is kept
+        let mut seen = HashSet::new();
+        let rewards = deliveries
+            .into_iter()
+            .chain(ishgard)
+            .chain(shop)
+            .filter(|reward| seen.insert((reward.item_id, reward.source.id())))
+            .collect_vec();
+        let tiers = rewards
+            .iter()
+            .flat_map(|reward| {
+                (0u32..)
+                    .zip(&reward.tiers)
+                    .map(move |(tier, reward_tier)| (reward, tier, reward_tier))
+            })
+            .collect_vec();
+
+        println!("Initializing Collectable Rewards Database Table");
+        for tiers in tiers.chunks(BIND_MAX / 7) {
+            QueryBuilder::new(strip_whitespace(SQL_INSERT))
+                .push_values(tiers, |mut b, &(reward, tier, reward_tier)| {
+                    b.push_bind(reward.source.id())
+                        .push_bind(reward.item_id)
+                        .push_bind(tier)
+                        .push_bind(reward_tier.collectability)
+                        .push_bind(&reward_tier.currency)
+                        .push_bind(reward_tier.scrips)
+                        .push_bind(reward_tier.experience);
+                })
+                .build()
+                .execute(self.db)
+                .await?;
+        }
+
+        let files = DatasetFilesTable::new(self.db);
+        try_join_all(CSV_FILES.map(|file| files.mark_imported(file))).await?;
+        Ok(())
+    }
+
+    pub async fn latest_commits(&self) -> Result<Vec<CommitInfo>> {
+        try_join_all(CSV_FILES.map(|file| latest_commit_from_github::<F>(self.db, file))).await
+    }
+
+    async fn download_custom_deliveries(&self) -> Result<Vec<CollectableReward>> {
+        println!("Downloading Custom Deliveries from Github");
+
+        let (supplies, rewards) = try_join!(
+            download_csv::<F>(self.db, CSV_FILE_DELIVERY),
+            download_csv::<F>(self.db, CSV_FILE_DELIVERY_REWARD),
+        )?;
+
+        let columns = CsvColumns::new(CSV_FILE_DELIVERY_REWARD, &rewards)?;
+        let currency_column = columns.get("RewardCurrency[0]")?;
+        let quantity_columns = TIER_NAMES
+            .into_iter()
+            .map(|tier| columns.get(&format!("Quantity{tier}[0]")))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut csv_rewards = BTreeMap::new();
+        csv_parse!(CSV_FILE_DELIVERY_REWARD, rewards.as_bytes(), info => {
+            id = U[0];
+            currency = U[currency_column];
+            let tiers = column_values(&info, &quantity_columns);
+            csv_rewards.insert(id, CsvReward {
+                currency: scrip_name(currency),
+                tiers: [tiers[0], tiers[1], tiers[2]],
+                experience: 0,
+            });
+        });
+
+        let columns = CsvColumns::new(CSV_FILE_DELIVERY, &supplies)?;
+        let item_column = columns.get("Item")?;
+        let reward_column = columns.get("Reward")?;
+        let collectability_columns = TIER_NAMES
+            .into_iter()
+            .map(|tier| columns.get(&format!("Collectability{tier}")))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut deliveries = Vec::new();
+        csv_parse!(CSV_FILE_DELIVERY, supplies.as_bytes(), info => {
+            item_id = U[item_column];
+            reward = U[reward_column];
+            let Some(csv_reward) = csv_rewards.get(&reward) else {
+                continue;
+            };
+            if item_id == 0 {
+                continue;
+            }
+
+            let collectability = column_values(&info, &collectability_columns);
+            let tiers = collectability
+                .into_iter()
+                .zip(csv_reward.tiers)
+                .map(|(collectability, scrips)| (collectability, csv_reward, scrips))
+                .collect_vec();
+            deliveries.push(collectable_reward(RewardSource::CustomDelivery, item_id, tiers));
+        });
+
+        Ok(deliveries)
+    }
+
+    async fn download_ishgard(&self) -> Result<Vec<CollectableReward>> {
+        println!("Downloading Ishgard Restoration Supplies from Github");
+
+        let (supplies, rewards) = try_join!(
+            download_csv::<F>(self.db, CSV_FILE_ISHGARD),
+            download_csv::<F>(self.db, CSV_FILE_ISHGARD_REWARD),
+        )?;
+
+        let columns = CsvColumns::new(CSV_FILE_ISHGARD_REWARD, &rewards)?;
+        let scrips_column = columns.get("ScriptRewardAmount")?;
+        let experience_column = columns.get("ExpReward")?;
+
+        let mut csv_rewards = BTreeMap::new();
+        csv_parse!(CSV_FILE_ISHGARD_REWARD, rewards.as_bytes(), info => {
+            id = U[0];
+            scrips = U[scrips_column];
+            experience = U[experience_column];
+            csv_rewards.insert(id, CsvReward {
+                currency: ISHGARD_CURRENCY.into(),
+                tiers: [scrips; 3],
+                experience,
+            });
+        });
+
+        let columns = CsvColumns::new(CSV_FILE_ISHGARD, &supplies)?;
+        let count = columns.array_len("ItemTradeIn");
+        let item_columns = columns.array("ItemTradeIn", count)?;
+        let tier_columns = ISHGARD_TIER_NAMES
+            .into_iter()
+            .map(|tier| {
+                Ok((
+                    columns.array(&format!("{tier}CollectableRating"), count)?,
+                    columns.array(&format!("{tier}CollectableReward"), count)?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut supplies_list = Vec::new();
+        csv_parse!(CSV_FILE_ISHGARD, supplies.as_bytes(), info => {
+            for (index, item_id) in column_values(&info, &item_columns).into_iter().enumerate() {
+                if item_id == 0 {
+                    continue;
+                }
+
+                let tiers = tier_columns
+                    .iter()
+                    .filter_map(|(ratings, rewards)| {
+                        let collectability = info[ratings[index]].parse::<u32>().unwrap_or_default();
+                        let reward = info[rewards[index]].parse::<u32>().unwrap_or_default();
+                        let csv_reward = csv_rewards.get(&reward)?;
+                        Some((collectability, csv_reward, csv_reward.tiers[0]))
+                    })
+                    .collect_vec();
+                supplies_list.push(collectable_reward(RewardSource::IshgardRestoration, item_id, tiers));
+            }
+        });
+
+        Ok(supplies_list)
+    }
+
+    async fn download_collectables_shop(&self) -> Result<Vec<CollectableReward>> {
+        println!("Downloading Collectables Shop from Github");
+
+        let (items, refines, rewards) = try_join!(
+            download_csv::<F>(self.db, CSV_FILE_SHOP),
+            download_csv::<F>(self.db, CSV_FILE_SHOP_REFINE),
+            download_csv::<F>(self.db, CSV_FILE_SHOP_REWARD),
+        )?;
+
+        let columns = CsvColumns::new(CSV_FILE_SHOP_REWARD, &rewards)?;
+        let currency_column = columns.get("Currency")?;
+        let reward_columns = TIER_NAMES
+            .into_iter()
+            .map(|tier| columns.get(&format!("{tier}Reward")))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut csv_rewards = BTreeMap::new();
+        csv_parse!(CSV_FILE_SHOP_REWARD, rewards.as_bytes(), info => {
+            id = U[0];
+            currency = U[currency_column];
+            let tiers = column_values(&info, &reward_columns);
+            csv_rewards.insert(id, CsvReward {
+                currency: scrip_name(currency),
+                tiers: [tiers[0], tiers[1], tiers[2]],
+                experience: 0,
+            });
+        });
+
+        let columns = CsvColumns::new(CSV_FILE_SHOP_REFINE, &refines)?;
+        let collectability_columns = TIER_NAMES
+            .into_iter()
+            .map(|tier| columns.get(&format!("{tier}Collectability")))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut csv_refines = BTreeMap::new();
+        csv_parse!(CSV_FILE_SHOP_REFINE, refines.as_bytes(), info => {
+            id = U[0];
+            csv_refines.insert(id, column_values(&info, &collectability_columns));
+        });
+
+        // Rows are keyed by subrow ids, so only the named columns are read
+        let columns = CsvColumns::new(CSV_FILE_SHOP, &items)?;
+        let item_column = columns.get("Item")?;
+        let refine_column = columns.get("CollectablesShopRefine")?;
+        let reward_column = columns.get("CollectablesShopRewardScrip")?;
+
+        let mut shop_items = Vec::new();
+        csv_parse!(CSV_FILE_SHOP, items.as_bytes(), info => {
+            item_id = U[item_column];
+            refine = U[refine_column];
+            reward = U[reward_column];
+            let (Some(collectability), Some(csv_reward)) =
+                (csv_refines.get(&refine), csv_rewards.get(&reward))
+            else {
+                continue;
+            };
+            if item_id == 0 {
+                continue;
+            }
+
+            let tiers = collectability
+                .iter()
+                .zip(csv_reward.tiers)
+                .map(|(&collectability, scrips)| (collectability, csv_reward, scrips))
+                .collect_vec();
+            shop_items.push(collectable_reward(RewardSource::CollectablesShop, item_id, tiers));
+        });
+
+        Ok(shop_items)
+    }
+}
+
+/// Tiers that don't reward anything are left out.
+fn collectable_reward(
+    source: RewardSource,
+    item_id: u32,
+    tiers: Vec<(u32, &CsvReward, u32)>,
+) -> CollectableReward {
+    CollectableReward {
+        source,
+        item_id,
+        tiers: tiers
+            .into_iter()
+            .filter(|&(_, csv_reward, scrips)| scrips > 0 || csv_reward.experience > 0)
+            .map(|(collectability, csv_reward, scrips)| RewardTier {
+                collectability,
+                currency: csv_reward.currency.clone(),
+                scrips,
+                experience: csv_reward.experience,
+            })
+            .collect(),
+    }
+}
+
+/// The names of the scrip currencies used by the reward tables.
+fn scrip_name(currency: u32) -> String {
+    match currency {
+        2 => "White Crafters' Scrip".into(),
+        4 => "Purple Crafters' Scrip".into(),
+        6 => "White Gatherers' Scrip".into(),
+        7 => "Purple Gatherers' Scrip".into(),
+        currency => format!("Scrip {currency}"),
+    }
+}
+
+////////////////////////////////////////////////////////////
+
+const SQL_TABLE_NAME: &str = "collectable_rewards";
+
+const SQL_CREATE: &str = formatcp!(
+    "CREATE TABLE IF NOT EXISTS {SQL_TABLE_NAME} (
+        id              MEDIUMINT   UNSIGNED    AUTO_INCREMENT  PRIMARY KEY,
+        source          TINYINT     UNSIGNED    NOT NULL,
+        item_id         MEDIUMINT   UNSIGNED    NOT NULL,
+        tier            TINYINT     UNSIGNED    NOT NULL,
+        collectability  SMALLINT    UNSIGNED    NOT NULL,
+        currency        VARCHAR(50)             NOT NULL,
+        scrips          SMALLINT    UNSIGNED    NOT NULL,
+        experience      INT         UNSIGNED    NOT NULL,
+        INDEX           ( source ),
+        INDEX           ( item_id ),
+        INDEX           ( currency )
+    )"
+);
+
+const SQL_INSERT: &str = formatcp!(
+    "INSERT INTO {SQL_TABLE_NAME} (
+        source, item_id, tier, collectability, currency, scrips, experience
+    ) "
+);
+
+const SQL_SELECT: &str = formatcp!(
+    "SELECT source, item_id, tier, collectability, currency, scrips, experience
+    FROM {SQL_TABLE_NAME}"
+);
@@ -0,0 +1,117 @@
+use std::{collections::BTreeMap, time::Instant};
+
+use anyhow::Result;
+use const_format::formatcp;
+use itertools::Itertools;
+use sqlx::{QueryBuilder, Row};
+
+use crate::{CraftPhase, ItemDB, ItemId, PhaseSupply};
+
+use super::{fetch_by_ids, impl_table, strip_whitespace, CompanyCraft, BIND_MAX};
+
+////////////////////////////////////////////////////////////
+
+impl_table!(CompanyCraftSupplyTable);
+
+impl CompanyCraftSupplyTable<'_> {
+    pub async fn initialize(&self, crafts: &[CompanyCraft]) -> Result<()> {
+        println!("Initializing Company Craft Supplies Database Table");
+
+        let supplies = crafts
+            .iter()
+            .flat_map(|craft| {
+                let item_id = craft.recipe.output.item_id;
+                (1..)
+                    .zip(&craft.recipe.phases)
+                    .flat_map(move |(phase, craft_phase)| {
+                        craft_phase
+                            .supplies
+                            .iter()
+                            .map(move |supply| (item_id, phase, supply))
+                    })
+            })
+            .collect_vec();
+
+        for supplies in supplies.chunks(BIND_MAX / 5) {
+            QueryBuilder::new(strip_whitespace(SQL_INSERT))
+                .push_values(supplies, |mut b, &(item_id, phase, supply)| {
+                    b.push_bind(item_id)
+                        .push_bind(phase as u32)
+                        .push_bind(supply.item_id)
+                        .push_bind(supply.set_quantity)
+                        .push_bind(supply.sets_required);
+                })
+                .build()
+                .execute(self.db)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the phases of the company crafts of `ids`, by crafted item id.
+    pub async fn phases_by_item_ids<I: ItemId>(
+        &self,
+        ids: &[I],
+    ) -> Result<BTreeMap<u32, Vec<CraftPhase>>> {
+        if ids.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+
+        let start = Instant::now();
+        let num_ids = ids.len();
+        let mut rows = fetch_by_ids(self.db, SQL_SELECT, ids).await?;
+        // Supplies are listed in the order they were inserted
+        rows.sort_by_key(|row| row.get::<u32, _>(0));
+
+        let mut crafts = BTreeMap::<u32, Vec<CraftPhase>>::new();
+        for row in &rows {
+            let item_id: u32 = row.get(1);
+            // Phases are numbered from 1
+            let phase = row.get::<u32, _>(2).max(1) as usize;
+            let supply = PhaseSupply {
+                item_id: row.get(3),
+                set_quantity: row.get(4),
+                sets_required: row.get(5),
+            };
+
+            let phases = crafts.entry(item_id).or_default();
+            if phases.len() < phase {
+                phases.resize_with(phase, || CraftPhase {
+                    supplies: Vec::new(),
+                });
+            }
+            phases[phase - 1].supplies.push(supply);
+        }
+
+        log::debug!(target: "ffxiv_items", "Query for {num_ids} company craft supplies ({} returned): {:.3}s", rows.len(), start.elapsed().as_secs_f32());
+        Ok(crafts)
+    }
+}
+
+////////////////////////////////////////////////////////////
+
+const SQL_TABLE_NAME: &str = "company_craft_supplies";
+
+const SQL_CREATE: &str = formatcp!(
+    "CREATE TABLE IF NOT EXISTS {SQL_TABLE_NAME} (
+        id              MEDIUMINT   UNSIGNED    AUTO_INCREMENT  PRIMARY KEY,
+        item_id         MEDIUMINT   UNSIGNED    NOT NULL,
+        phase           TINYINT     UNSIGNED    NOT NULL,
+        input_id        MEDIUMINT   UNSIGNED    NOT NULL,
+        set_quantity    SMALLINT    UNSIGNED    NOT NULL,
+        sets_required   SMALLINT    UNSIGNED    NOT NULL,
+        INDEX           ( item_id ),
+        INDEX           ( input_id )
+    )"
+);
+
+const SQL_INSERT: &str = formatcp!(
+    "INSERT INTO {SQL_TABLE_NAME} (item_id, phase, input_id, set_quantity, sets_required) "
+);
+
+const SQL_SELECT: &str = formatcp!(
+    "SELECT id, item_id, phase, input_id, set_quantity, sets_required
+    FROM {SQL_TABLE_NAME}
+    WHERE item_id IN"
+);
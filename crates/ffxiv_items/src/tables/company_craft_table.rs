@@ -0,0 +1,254 @@
+use std::{collections::BTreeMap, future::Future};
+
+use anyhow::Result;
+use const_format::formatcp;
+use futures::{future::try_join_all, try_join};
+use itertools::Itertools;
+use mock_traits::FileDownloader;
+use sqlx::QueryBuilder;
+
+use crate::{
+    column_values, csv_parse, latest_commit_from_github, CommitInfo, CraftPhase, CsvColumns,
+    Ingredient, ItemDB, PhaseSupply, Recipe, RecipeDifficulty, RecipeKind,
+};
+
+use super::{
+    download_csv, impl_table, impl_table_builder, strip_whitespace, DatasetFilesTable, BIND_MAX,
+};
+
+////////////////////////////////////////////////////////////
+
+impl_table!(CompanyCraftTable);
+impl_table_builder!(CompanyCraftTableBuilder, FileDownloader);
+
+/// A company workshop project, along with its workshop category.
+pub struct CompanyCraft {
+    pub craft_type: String,
+    pub recipe: Recipe,
+}
+
+impl CompanyCraftTable<'_> {
+    pub async fn initialize(&self, crafts: &[CompanyCraft]) -> Result<()> {
+        println!("Initializing Company Crafts Database Table");
+        for crafts in crafts.chunks(BIND_MAX / 2) {
+            QueryBuilder::new(strip_whitespace(SQL_INSERT))
+                .push_values(crafts, |mut b, craft| {
+                    b.push_bind(craft.recipe.output.item_id)
+                        .push_bind(&craft.craft_type);
+                })
+                .build()
+                .execute(self.db)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A workshop project makes a single item, and its inputs are everything
+/// supplied over all of its phases.
+fn company_recipe(item_id: u32, phases: Vec<CraftPhase>) -> Recipe {
+    let mut inputs = BTreeMap::<u32, u32>::new();
+    for supply in phases.iter().flat_map(|phase| &phase.supplies) {
+        *inputs.entry(supply.item_id).or_default() += supply.count();
+    }
+
+    Recipe {
+        output: Ingredient { count: 1, item_id },
+        inputs: inputs
+            .into_iter()
+            .map(|(item_id, count)| Ingredient { count, item_id })
+            .collect(),
+        level: 0,
+        stars: 0,
+        difficulty: RecipeDifficulty::default(),
+        book: None,
+        kind: RecipeKind::CompanyWorkshop,
+        phases,
+    }
+}
+
+////////////////////////////////////////////////////////////
+
+const CSV_FILE_SEQUENCE: &str = "CompanyCraftSequence.csv";
+const CSV_FILE_PART: &str = "CompanyCraftPart.csv";
+const CSV_FILE_PROCESS: &str = "CompanyCraftProcess.csv";
+const CSV_FILE_SUPPLY_ITEM: &str = "CompanyCraftSupplyItem.csv";
+const CSV_FILE_TYPE: &str = "CompanyCraftType.csv";
+
+const CSV_FILES: [&str; 5] = [
+    CSV_FILE_SEQUENCE,
+    CSV_FILE_PART,
+    CSV_FILE_PROCESS,
+    CSV_FILE_SUPPLY_ITEM,
+    CSV_FILE_TYPE,
+];
+
+// The number of entries in each of the array columns
+const PARTS_PER_SEQUENCE: usize = 8;
+const PROCESSES_PER_PART: usize = 3;
+const SUPPLIES_PER_PROCESS: usize = 12;
+
+impl<F: FileDownloader> CompanyCraftTableBuilder<'_, F> {
+    /// Marks the company craft csv files as imported, once the company craft,
+    /// supply & input id tables have all been initialized from them.
+    pub async fn mark_imported(&self) -> Result<()> {
+        let files = DatasetFilesTable::new(self.db);
+        try_join_all(CSV_FILES.map(|file| files.mark_imported(file))).await?;
+        Ok(())
+    }
+
+    pub async fn latest_commits(&self) -> Result<Vec<CommitInfo>> {
+        try_join_all(CSV_FILES.map(|file| latest_commit_from_github::<F>(self.db, file))).await
+    }
+
+    pub async fn download(&self) -> Result<Vec<CompanyCraft>> {
+        println!("Downloading Company Crafts from Github");
+        download_company_crafts(|file| download_csv::<F>(self.db, file)).await
+    }
+}
+
+/// Parses the company workshop projects from their csv files, each fetched
+/// with `download`.
+pub async fn download_company_crafts<D, Fut>(download: D) -> Result<Vec<CompanyCraft>>
+where
+    D: Fn(&'static str) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let (sequences, parts, processes, supply_items, types) = try_join!(
+        download(CSV_FILE_SEQUENCE),
+        download(CSV_FILE_PART),
+        download(CSV_FILE_PROCESS),
+        download(CSV_FILE_SUPPLY_ITEM),
+        download(CSV_FILE_TYPE),
+    )?;
+
+    let supply_items = parse_supply_items(&supply_items)?;
+    let processes = parse_processes(&processes, &supply_items)?;
+    let parts = parse_parts(&parts)?;
+    let types = parse_types(&types)?;
+
+    let columns = CsvColumns::new(CSV_FILE_SEQUENCE, &sequences)?;
+    let result_item_column = columns.get("ResultItem")?;
+    let craft_type_column = columns.get("CompanyCraftType")?;
+    let part_columns = columns.array("CompanyCraftPart", PARTS_PER_SEQUENCE)?;
+
+    let mut crafts = BTreeMap::new();
+    csv_parse!(CSV_FILE_SEQUENCE, sequences.as_bytes(), info => {
+        item_id = U[result_item_column];
+        craft_type = U[craft_type_column];
+        if item_id == 0 {
+            continue;
+        }
+
+        let phases = column_values(&info, &part_columns)
+            .into_iter()
+            .filter_map(|part| parts.get(&part))
+            .flatten()
+            .filter_map(|process| processes.get(process))
+            .cloned()
+            .collect_vec();
+        if phases.iter().all(|phase| phase.supplies.is_empty()) {
+            continue;
+        }
+
+        crafts.insert(item_id, CompanyCraft {
+            craft_type: types.get(&craft_type).cloned().unwrap_or_default(),
+            recipe: company_recipe(item_id, phases),
+        });
+    });
+
+    Ok(crafts.into_values().collect())
+}
+
+/// Maps supply item ids to item ids
+fn parse_supply_items(contents: &str) -> Result<BTreeMap<u32, u32>> {
+    let columns = CsvColumns::new(CSV_FILE_SUPPLY_ITEM, contents)?;
+    let item_column = columns.get("Item")?;
+
+    let mut supply_items = BTreeMap::new();
+    csv_parse!(CSV_FILE_SUPPLY_ITEM, contents.as_bytes() => {
+        id = U[0];
+        item_id = U[item_column];
+        if item_id != 0 {
+            supply_items.insert(id, item_id);
+        }
+    });
+    Ok(supply_items)
+}
+
+/// Maps process ids to the items supplied during that phase
+fn parse_processes(
+    contents: &str,
+    supply_items: &BTreeMap<u32, u32>,
+) -> Result<BTreeMap<u32, CraftPhase>> {
+    let columns = CsvColumns::new(CSV_FILE_PROCESS, contents)?;
+    let supply_columns = columns.array("SupplyItem", SUPPLIES_PER_PROCESS)?;
+    let quantity_columns = columns.array("SetQuantity", SUPPLIES_PER_PROCESS)?;
+    let sets_columns = columns.array("SetsRequired", SUPPLIES_PER_PROCESS)?;
+
+    let mut processes = BTreeMap::new();
+    csv_parse!(CSV_FILE_PROCESS, contents.as_bytes(), info => {
+        id = U[0];
+        let supplies = column_values(&info, &supply_columns)
+            .into_iter()
+            .zip(column_values(&info, &quantity_columns))
+            .zip(column_values(&info, &sets_columns))
+            .filter_map(|((supply_item, set_quantity), sets_required)| {
+                let item_id = *supply_items.get(&supply_item)?;
+                (set_quantity > 0 && sets_required > 0).then_some(PhaseSupply {
+                    item_id,
+                    set_quantity,
+                    sets_required,
+                })
+            })
+            .collect_vec();
+        processes.insert(id, CraftPhase { supplies });
+    });
+    Ok(processes)
+}
+
+/// Maps part ids to their process ids, in order
+fn parse_parts(contents: &str) -> Result<BTreeMap<u32, Vec<u32>>> {
+    let columns = CsvColumns::new(CSV_FILE_PART, contents)?;
+    let process_columns = columns.array("CompanyCraftProcess", PROCESSES_PER_PART)?;
+
+    let mut parts = BTreeMap::new();
+    csv_parse!(CSV_FILE_PART, contents.as_bytes(), info => {
+        id = U[0];
+        let processes = column_values(&info, &process_columns)
+            .into_iter()
+            .filter(|&process| process != 0)
+            .collect_vec();
+        parts.insert(id, processes);
+    });
+    Ok(parts)
+}
+
+/// Maps workshop craft type ids to their names
+fn parse_types(contents: &str) -> Result<BTreeMap<u32, String>> {
+    let columns = CsvColumns::new(CSV_FILE_TYPE, contents)?;
+    let name_column = columns.get("Name")?;
+
+    let mut types = BTreeMap::new();
+    csv_parse!(CSV_FILE_TYPE, contents.as_bytes() => {
+        id = U[0];
+        name = S[name_column];
+        types.insert(id, name);
+    });
+    Ok(types)
+}
+
+////////////////////////////////////////////////////////////
+
+const SQL_TABLE_NAME: &str = "company_crafts";
+
+const SQL_CREATE: &str = formatcp!(
+    "CREATE TABLE IF NOT EXISTS {SQL_TABLE_NAME} (
+        id          MEDIUMINT       UNSIGNED    PRIMARY KEY,
+        craft_type  VARCHAR(50)                 NOT NULL,
+        INDEX       ( craft_type )
+    )"
+);
+
+const SQL_INSERT: &str = formatcp!("INSERT INTO {SQL_TABLE_NAME} (id, craft_type) ");
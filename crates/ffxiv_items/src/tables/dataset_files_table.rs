@@ -0,0 +1,145 @@
+use std::time::Instant;
+
+use anyhow::Result;
+use chrono::{DateTime, FixedOffset, Utc};
+use const_format::formatcp;
+use futures::TryStreamExt;
+use sqlx::Row;
+
+use crate::{CommitInfo, DatasetFile, ItemDB};
+
+use super::{impl_table, strip_whitespace};
+
+////////////////////////////////////////////////////////////
+
+impl_table!(DatasetFilesTable);
+
+impl DatasetFilesTable<'_> {
+    /// Records the commit that a file will be downloaded from. Any previous
+    /// download & import information for the file is cleared.
+    pub async fn set_commit(&self, commit: &CommitInfo) -> Result<()> {
+        sqlx::query(&strip_whitespace(SQL_SET_COMMIT))
+            .bind(commit.file_name)
+            .bind(&commit.sha)
+            .bind(commit.date.to_rfc3339())
+            .execute(self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// The commit sha that a file should be downloaded from, if known.
+    pub async fn commit_sha(&self, file_name: &str) -> Result<Option<String>> {
+        let query_string = strip_whitespace(SQL_SELECT_SHA);
+        let sha = sqlx::query_scalar::<_, Option<String>>(&query_string)
+            .bind(file_name)
+            .persistent(true)
+            .fetch_optional(self.db)
+            .await?;
+        Ok(sha.flatten())
+    }
+
+    pub async fn record_download(
+        &self,
+        file_name: &str,
+        sha: Option<&str>,
+        row_count: usize,
+        content_hash: &str,
+    ) -> Result<()> {
+        sqlx::query(&strip_whitespace(SQL_RECORD_DOWNLOAD))
+            .bind(file_name)
+            .bind(sha)
+            .bind(Utc::now().to_rfc3339())
+            .bind(row_count as u32)
+            .bind(content_hash)
+            .execute(self.db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn mark_imported(&self, file_name: &str) -> Result<()> {
+        sqlx::query(&strip_whitespace(SQL_MARK_IMPORTED))
+            .bind(file_name)
+            .execute(self.db)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn all(&self) -> Result<Vec<DatasetFile>> {
+        let start = Instant::now();
+        let query_string = strip_whitespace(SQL_SELECT);
+
+        let mut files = Vec::new();
+        let mut sql_query = sqlx::query(&query_string).persistent(true).fetch(self.db);
+        while let Some(row) = sql_query.try_next().await? {
+            files.push(DatasetFile {
+                file_name: row.try_get(0)?,
+                commit_sha: row.try_get(1)?,
+                commit_date: parse_date(row.try_get(2)?)?,
+                downloaded_at: parse_date(row.try_get(3)?)?,
+                row_count: row.try_get(4)?,
+                content_hash: row.try_get(5)?,
+                imported: row.try_get(6)?,
+            });
+        }
+        log::debug!(target: "ffxiv_items", "Query for dataset files: {:.3}s", start.elapsed().as_secs_f32());
+
+        Ok(files)
+    }
+}
+
+fn parse_date(date: Option<String>) -> Result<Option<DateTime<FixedOffset>>> {
+    Ok(match date {
+        None => None,
+        Some(date) => Some(DateTime::parse_from_rfc3339(&date)?),
+    })
+}
+
+////////////////////////////////////////////////////////////
+
+const SQL_TABLE_NAME: &str = "dataset_files";
+
+const SQL_CREATE: &str = formatcp!(
+    "CREATE TABLE IF NOT EXISTS {SQL_TABLE_NAME} (
+        file_name       VARCHAR(64)     PRIMARY KEY,
+        commit_sha      CHAR(40),
+        commit_date     VARCHAR(40),
+        downloaded_at   VARCHAR(40),
+        row_count       INT             UNSIGNED,
+        content_hash    CHAR(64),
+        imported        BOOLEAN         NOT NULL    DEFAULT FALSE
+    )"
+);
+
+const SQL_SET_COMMIT: &str = formatcp!(
+    "INSERT INTO {SQL_TABLE_NAME} (file_name, commit_sha, commit_date) VALUES (?, ?, ?)
+    ON DUPLICATE KEY UPDATE
+        commit_sha = VALUES(commit_sha),
+        commit_date = VALUES(commit_date),
+        downloaded_at = NULL,
+        row_count = NULL,
+        content_hash = NULL,
+        imported = FALSE"
+);
+
+const SQL_RECORD_DOWNLOAD: &str = formatcp!(
+    "INSERT INTO {SQL_TABLE_NAME} (file_name, commit_sha, downloaded_at, row_count, content_hash)
+    VALUES (?, ?, ?, ?, ?)
+    ON DUPLICATE KEY UPDATE
+        commit_sha = VALUES(commit_sha),
+        downloaded_at = VALUES(downloaded_at),
+        row_count = VALUES(row_count),
+        content_hash = VALUES(content_hash),
+        imported = FALSE"
+);
+
+const SQL_MARK_IMPORTED: &str =
+    formatcp!("UPDATE {SQL_TABLE_NAME} SET imported = TRUE WHERE file_name = ?");
+
+const SQL_SELECT_SHA: &str =
+    formatcp!("SELECT commit_sha FROM {SQL_TABLE_NAME} WHERE file_name = ?");
+
+const SQL_SELECT: &str = formatcp!(
+    "SELECT file_name, commit_sha, commit_date, downloaded_at, row_count, content_hash, imported
+    FROM {SQL_TABLE_NAME}
+    ORDER BY file_name"
+);
@@ -0,0 +1,117 @@
+use std::{collections::BTreeMap, time::Instant};
+
+use anyhow::Result;
+use const_format::formatcp;
+use sqlx::{QueryBuilder, Row};
+
+use crate::{Food, FoodEffect, FoodKind, ItemDB, ItemId};
+
+use super::{fetch_by_ids, impl_table, strip_whitespace, BIND_MAX};
+
+////////////////////////////////////////////////////////////
+
+impl_table!(FoodEffectTable);
+
+impl FoodEffectTable<'_> {
+    pub async fn initialize(&self, foods: &[(u32, Food)]) -> Result<()> {
+        println!("Initializing Food Effects Database Table");
+
+        let effects = foods
+            .iter()
+            .flat_map(|(item_id, food)| {
+                food.effects
+                    .iter()
+                    .map(move |effect| (*item_id, food.kind, effect))
+            })
+            .collect::<Vec<_>>();
+        for effects in effects.chunks(BIND_MAX / 8) {
+            QueryBuilder::new(strip_whitespace(SQL_INSERT))
+                .push_values(effects, |mut b, &(item_id, kind, effect)| {
+                    b.push_bind(item_id)
+                        .push_bind(kind.id())
+                        .push_bind(&effect.stat)
+                        .push_bind(effect.is_relative)
+                        .push_bind(effect.value)
+                        .push_bind(effect.max)
+                        .push_bind(effect.value_hq)
+                        .push_bind(effect.max_hq);
+                })
+                .build()
+                .execute(self.db)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the effects of the meals & medicines in `ids`, by item id.
+    pub async fn by_item_ids<I: ItemId>(&self, ids: &[I]) -> Result<BTreeMap<u32, Food>> {
+        if ids.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+
+        let start = Instant::now();
+        let num_ids = ids.len();
+        let mut rows = fetch_by_ids(self.db, SQL_SELECT, ids).await?;
+        // Effects are listed in the order they were inserted
+        rows.sort_by_key(|row| row.get::<u32, _>(0));
+
+        let mut foods = BTreeMap::<u32, Food>::new();
+        for row in &rows {
+            let Some(kind) = FoodKind::from_id(row.get(2)) else {
+                continue;
+            };
+            let effect = FoodEffect {
+                stat: row.get(3),
+                is_relative: row.get(4),
+                value: row.get(5),
+                max: row.get(6),
+                value_hq: row.get(7),
+                max_hq: row.get(8),
+            };
+            foods
+                .entry(row.get(1))
+                .or_insert_with(|| Food {
+                    kind,
+                    effects: Vec::new(),
+                })
+                .effects
+                .push(effect);
+        }
+
+        log::debug!(target: "ffxiv_items", "Query for {num_ids} food effects ({} returned): {:.3}s", rows.len(), start.elapsed().as_secs_f32());
+        Ok(foods)
+    }
+}
+
+////////////////////////////////////////////////////////////
+
+const SQL_TABLE_NAME: &str = "food_effects";
+
+const SQL_CREATE: &str = formatcp!(
+    "CREATE TABLE IF NOT EXISTS {SQL_TABLE_NAME} (
+        id          MEDIUMINT   UNSIGNED    AUTO_INCREMENT  PRIMARY KEY,
+        item_id     MEDIUMINT   UNSIGNED    NOT NULL,
+        kind        TINYINT     UNSIGNED    NOT NULL,
+        stat        VARCHAR(50)             NOT NULL,
+        is_relative BOOL                    NOT NULL,
+        value       SMALLINT                NOT NULL,
+        max         SMALLINT                NOT NULL,
+        value_hq    SMALLINT                NOT NULL,
+        max_hq      SMALLINT                NOT NULL,
+        INDEX       ( item_id ),
+        INDEX       ( stat )
+    )"
+);
+
+const SQL_INSERT: &str = formatcp!(
+    "INSERT INTO {SQL_TABLE_NAME} (
+        item_id, kind, stat, is_relative, value, max, value_hq, max_hq
+    ) "
+);
+
+const SQL_SELECT: &str = formatcp!(
+    "SELECT id, item_id, kind, stat, is_relative, value, max, value_hq, max_hq
+    FROM {SQL_TABLE_NAME}
+    WHERE item_id IN"
+);
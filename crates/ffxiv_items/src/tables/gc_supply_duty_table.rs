@@ -0,0 +1,217 @@
+use std::time::Instant;
+
+use anyhow::Result;
+use const_format::formatcp;
+use futures::try_join;
+use itertools::Itertools;
+use mock_traits::FileDownloader;
+use sqlx::{QueryBuilder, Row};
+
+use crate::{
+    csv_parse, latest_commit_from_github, CommitInfo, CsvColumns, GcSupplyDuty, GcSupplyKind,
+    ItemDB,
+};
+
+use super::{
+    download_csv, impl_table, impl_table_builder, strip_whitespace, CsvGcSupplyReward,
+    DatasetFilesTable, GcSupplyRewardTable, ItemInfoTable, BIND_MAX,
+};
+
+////////////////////////////////////////////////////////////
+
+impl_table!(GcSupplyDutyTable);
+impl_table_builder!(GcSupplyDutyTableBuilder, FileDownloader);
+
+impl GcSupplyDutyTable<'_> {
+    /// Returns the turn-ins for each of the `jobs`, or for every job if none
+    /// are passed in, along with their rewards.
+    pub async fn by_jobs<S: AsRef<str>>(&self, jobs: &[S]) -> Result<Vec<GcSupplyDuty>> {
+        let start = Instant::now();
+        let query_string = match jobs.is_empty() {
+            true => format!("{SQL_SELECT} ORDER BY d.job, d.level, d.item_id"),
+            false => format!(
+                "{SQL_SELECT} WHERE d.job IN ({}) ORDER BY d.job, d.level, d.item_id",
+                jobs.iter().map(|_| "?").join(", ")
+            ),
+        };
+        let query_string = strip_whitespace(query_string);
+        let mut sql_query = sqlx::query(&query_string);
+        for job in jobs {
+            sql_query = sql_query.bind(job.as_ref().to_uppercase());
+        }
+
+        let mut duties = Vec::new();
+        for row in sql_query.fetch_all(self.db).await? {
+            let job: String = row.get(1);
+            let kind = GcSupplyKind::from_job(&job);
+            let (experience, seals): (Option<u32>, Option<u32>) = match kind {
+                GcSupplyKind::Supply => (row.get(4), row.get(6)),
+                GcSupplyKind::Provisioning => (row.get(5), row.get(7)),
+            };
+            duties.push(GcSupplyDuty {
+                level: row.get(0),
+                job,
+                kind,
+                item_id: row.get(2),
+                count: row.get(3),
+                experience: experience.unwrap_or_default(),
+                seals: seals.unwrap_or_default(),
+            });
+        }
+
+        log::debug!(target: "ffxiv_items", "Query for GC supply duties ({} returned): {:.3}s", duties.len(), start.elapsed().as_secs_f32());
+        Ok(duties)
+    }
+}
+
+////////////////////////////////////////////////////////////
+
+struct CsvGcSupplyDuty {
+    level: u32,
+    job: &'static str,
+    item_id: u32,
+    count: u32,
+}
+
+////////////////////////////////////////////////////////////
+
+const CSV_FILE_DUTY: &str = "GCSupplyDuty.csv";
+const CSV_FILE_REWARD: &str = "GCSupplyDutyReward.csv";
+
+/// The jobs of each `Item[job]` group in GCSupplyDuty.csv, in order.
+const GC_SUPPLY_JOBS: [&str; 11] = [
+    "CRP", "BSM", "ARM", "GSM", "LTW", "WVR", "ALC", "CUL", "MIN", "BTN", "FSH",
+];
+
+// The number of items each job may be asked for, per duty
+const ITEMS_PER_JOB: usize = 3;
+
+impl<F: FileDownloader> GcSupplyDutyTableBuilder<'_, F> {
+    pub async fn initialize(&self) -> Result<()> {
+        let (duties, rewards) = try_join!(self.download_duties(), self.download_rewards())?;
+
+        println!("Initializing GC Supply Duties Database Table");
+        for duties in duties.chunks(BIND_MAX / 4) {
+            QueryBuilder::new(strip_whitespace(SQL_INSERT))
+                .push_values(duties, |mut b, duty| {
+                    b.push_bind(duty.level)
+                        .push_bind(duty.job)
+                        .push_bind(duty.item_id)
+                        .push_bind(duty.count);
+                })
+                .build()
+                .execute(self.db)
+                .await?;
+        }
+        GcSupplyRewardTable::new(self.db)
+            .initialize(&rewards)
+            .await?;
+
+        let files = DatasetFilesTable::new(self.db);
+        try_join!(
+            files.mark_imported(CSV_FILE_DUTY),
+            files.mark_imported(CSV_FILE_REWARD),
+        )?;
+        Ok(())
+    }
+
+    pub async fn latest_commits(&self) -> Result<Vec<CommitInfo>> {
+        let (duty, reward) = try_join!(
+            latest_commit_from_github::<F>(self.db, CSV_FILE_DUTY),
+            latest_commit_from_github::<F>(self.db, CSV_FILE_REWARD),
+        )?;
+        Ok(vec![duty, reward])
+    }
+
+    async fn download_duties(&self) -> Result<Vec<CsvGcSupplyDuty>> {
+        println!("Downloading GC Supply Duties from Github");
+
+        let contents = download_csv::<F>(self.db, CSV_FILE_DUTY).await?;
+        let columns = CsvColumns::new(CSV_FILE_DUTY, &contents)?;
+        let job_columns = (0..GC_SUPPLY_JOBS.len())
+            .map(|job| {
+                Ok((
+                    columns.array(&format!("Item[{job}]"), ITEMS_PER_JOB)?,
+                    columns.array(&format!("ItemCount[{job}]"), ITEMS_PER_JOB)?,
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut duties = Vec::new();
+        csv_parse!(CSV_FILE_DUTY, contents.as_bytes(), info => {
+            level = U[0];
+            for (job, (item_columns, count_columns)) in GC_SUPPLY_JOBS.into_iter().zip(&job_columns) {
+                for (&item_column, &count_column) in item_columns.iter().zip(count_columns) {
+                    let item_id = info[item_column].parse::<u32>().unwrap_or_default();
+                    let count = info[count_column].parse::<u32>().unwrap_or_default();
+                    if item_id != 0 && count > 0 {
+                        duties.push(CsvGcSupplyDuty { level, job, item_id, count });
+                    }
+                }
+            }
+        });
+
+        Ok(duties)
+    }
+
+    async fn download_rewards(&self) -> Result<Vec<CsvGcSupplyReward>> {
+        println!("Downloading GC Supply Rewards from Github");
+
+        let contents = download_csv::<F>(self.db, CSV_FILE_REWARD).await?;
+        let columns = CsvColumns::new(CSV_FILE_REWARD, &contents)?;
+        let experience_supply_column = columns.get("ExperienceSupply")?;
+        let experience_provisioning_column = columns.get("ExperienceProvisioning")?;
+        let seals_expert_delivery_column = columns.get("SealsExpertDelivery")?;
+        let seals_supply_column = columns.get("SealsSupply")?;
+        let seals_provisioning_column = columns.get("SealsProvisioning")?;
+
+        let mut rewards = Vec::new();
+        csv_parse!(CSV_FILE_REWARD, contents.as_bytes(), info => {
+            item_level = U[0];
+            experience_supply = U[experience_supply_column];
+            experience_provisioning = U[experience_provisioning_column];
+            seals_expert_delivery = U[seals_expert_delivery_column];
+            seals_supply = U[seals_supply_column];
+            seals_provisioning = U[seals_provisioning_column];
+            rewards.push(CsvGcSupplyReward {
+                item_level,
+                experience_supply,
+                experience_provisioning,
+                seals_expert_delivery,
+                seals_supply,
+                seals_provisioning,
+            });
+        });
+
+        Ok(rewards)
+    }
+}
+
+////////////////////////////////////////////////////////////
+
+const SQL_TABLE_NAME: &str = "gc_supply_duties";
+
+const SQL_CREATE: &str = formatcp!(
+    "CREATE TABLE IF NOT EXISTS {SQL_TABLE_NAME} (
+        id          MEDIUMINT   UNSIGNED    AUTO_INCREMENT  PRIMARY KEY,
+        level       SMALLINT    UNSIGNED    NOT NULL,
+        job         CHAR(3)                 NOT NULL,
+        item_id     MEDIUMINT   UNSIGNED    NOT NULL,
+        count       SMALLINT    UNSIGNED    NOT NULL,
+        INDEX       ( job ),
+        INDEX       ( item_id )
+    )"
+);
+
+const SQL_INSERT: &str = formatcp!("INSERT INTO {SQL_TABLE_NAME} (level, job, item_id, count) ");
+
+// Rewards depend on the item level of the item turned in
+const SQL_SELECT: &str = formatcp!(
+    "SELECT d.level, d.job, d.item_id, d.count,
+        r.experience_supply, r.experience_provisioning, r.seals_supply, r.seals_provisioning
+    FROM {SQL_TABLE_NAME} AS d
+    INNER JOIN {} AS i ON i.id = d.item_id
+    LEFT JOIN {} AS r ON r.id = i.item_level",
+    ItemInfoTable::SQL_TABLE_NAME,
+    GcSupplyRewardTable::SQL_TABLE_NAME,
+);
@@ -0,0 +1,65 @@
+use anyhow::Result;
+use const_format::formatcp;
+use sqlx::QueryBuilder;
+
+use crate::ItemDB;
+
+use super::{impl_table, strip_whitespace, BIND_MAX};
+
+////////////////////////////////////////////////////////////
+
+impl_table!(GcSupplyRewardTable);
+
+/// The rewards for turning in an item of a particular item level.
+pub struct CsvGcSupplyReward {
+    pub item_level: u32,
+    pub experience_supply: u32,
+    pub experience_provisioning: u32,
+    pub seals_expert_delivery: u32,
+    pub seals_supply: u32,
+    pub seals_provisioning: u32,
+}
+
+impl GcSupplyRewardTable<'_> {
+    pub async fn initialize(&self, rewards: &[CsvGcSupplyReward]) -> Result<()> {
+        println!("Initializing GC Supply Rewards Database Table");
+        for rewards in rewards.chunks(BIND_MAX / 6) {
+            QueryBuilder::new(strip_whitespace(SQL_INSERT))
+                .push_values(rewards, |mut b, reward| {
+                    b.push_bind(reward.item_level)
+                        .push_bind(reward.experience_supply)
+                        .push_bind(reward.experience_provisioning)
+                        .push_bind(reward.seals_expert_delivery)
+                        .push_bind(reward.seals_supply)
+                        .push_bind(reward.seals_provisioning);
+                })
+                .build()
+                .execute(self.db)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////
+
+const SQL_TABLE_NAME: &str = "gc_supply_rewards";
+
+const SQL_CREATE: &str = formatcp!(
+    "CREATE TABLE IF NOT EXISTS {SQL_TABLE_NAME} (
+        id                      SMALLINT    UNSIGNED    PRIMARY KEY,
+        experience_supply       INT         UNSIGNED    NOT NULL,
+        experience_provisioning INT         UNSIGNED    NOT NULL,
+        seals_expert_delivery   SMALLINT    UNSIGNED    NOT NULL,
+        seals_supply            SMALLINT    UNSIGNED    NOT NULL,
+        seals_provisioning      SMALLINT    UNSIGNED    NOT NULL
+    )"
+);
+
+const SQL_INSERT: &str = formatcp!(
+    "INSERT INTO {SQL_TABLE_NAME} (
+        id, experience_supply, experience_provisioning,
+        seals_expert_delivery, seals_supply, seals_provisioning
+    ) "
+);
@@ -0,0 +1,52 @@
+use anyhow::Result;
+use const_format::formatcp;
+use sqlx::Row;
+
+use crate::ItemDB;
+
+use super::{impl_table, strip_whitespace};
+
+////////////////////////////////////////////////////////////
+
+impl_table!(GithubCacheTable);
+
+impl GithubCacheTable<'_> {
+    /// Returns the cached `(etag, body)` for a github API url.
+    pub async fn get(&self, url: &str) -> Result<Option<(String, String)>> {
+        let row = sqlx::query(&strip_whitespace(SQL_SELECT))
+            .bind(url)
+            .persistent(true)
+            .fetch_optional(self.db)
+            .await?;
+        Ok(row.map(|row| (row.get(0), row.get(1))))
+    }
+
+    pub async fn set(&self, url: &str, etag: &str, body: &str) -> Result<()> {
+        sqlx::query(&strip_whitespace(SQL_UPSERT))
+            .bind(url)
+            .bind(etag)
+            .bind(body)
+            .execute(self.db)
+            .await?;
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////
+
+const SQL_TABLE_NAME: &str = "github_cache";
+
+const SQL_CREATE: &str = formatcp!(
+    "CREATE TABLE IF NOT EXISTS {SQL_TABLE_NAME} (
+        url         VARCHAR(255)    PRIMARY KEY,
+        etag        VARCHAR(255)    NOT NULL,
+        body        MEDIUMTEXT      NOT NULL
+    )"
+);
+
+const SQL_UPSERT: &str = formatcp!(
+    "INSERT INTO {SQL_TABLE_NAME} (url, etag, body) VALUES (?, ?, ?)
+    ON DUPLICATE KEY UPDATE etag = VALUES(etag), body = VALUES(body)"
+);
+
+const SQL_SELECT: &str = formatcp!("SELECT etag, body FROM {SQL_TABLE_NAME} WHERE url = ?");
@@ -2,13 +2,12 @@ use std::time::Instant;
 
 use anyhow::Result;
 use const_format::formatcp;
-use futures::TryStreamExt;
 use itertools::Itertools;
 use sqlx::{QueryBuilder, Row};
 
 use crate::{Ingredient, ItemDB, ItemId, Recipe};
 
-use super::{impl_table, strip_whitespace, RecipeTable, BIND_MAX};
+use super::{fetch_by_ids, impl_table, strip_whitespace, BIND_MAX};
 
 ////////////////////////////////////////////////////////////
 
@@ -49,12 +48,8 @@ impl IngredientTable<'_> {
 
         let start = Instant::now();
         let num_ids = ids.len();
-        let ids = ids.iter().map(|id| id.item_id().to_string()).join(",");
-        let query_string = strip_whitespace(format!("{} ({ids})", SQL_SELECT));
-
         let mut ingredients = Vec::new();
-        let mut sql_query = sqlx::query(&query_string).persistent(true).fetch(self.db);
-        while let Some(row) = sql_query.try_next().await? {
+        for row in fetch_by_ids(self.db, SQL_SELECT, ids).await? {
             let item_id: u64 = row.get(0);
             let input_id: u64 = row.get(1);
             let count: u64 = row.get(2);
@@ -90,10 +85,5 @@ const SQL_CREATE: &str = formatcp!(
 
 const SQL_INSERT: &str = formatcp!("INSERT INTO {SQL_TABLE_NAME} (item_id, input_id, count) ");
 
-const SQL_SELECT: &str = formatcp!(
-    "SELECT r.id, g.input_id, g.count
-    FROM {SQL_TABLE_NAME} AS g
-    INNER JOIN {} as r ON r.id = g.item_id
-    WHERE r.id IN",
-    RecipeTable::SQL_TABLE_NAME
-);
+const SQL_SELECT: &str =
+    formatcp!("SELECT item_id, input_id, count FROM {SQL_TABLE_NAME} WHERE item_id IN");
@@ -2,13 +2,12 @@ use std::{collections::BTreeMap, time::Instant};
 
 use anyhow::Result;
 use const_format::formatcp;
-use futures::TryStreamExt;
 use itertools::Itertools;
 use sqlx::{QueryBuilder, Row};
 
 use crate::{ItemDB, ItemId, Recipe};
 
-use super::{impl_table, strip_whitespace, BIND_MAX};
+use super::{fetch_by_ids, impl_table, strip_whitespace, BIND_MAX};
 
 ////////////////////////////////////////////////////////////
 
@@ -20,9 +19,9 @@ impl InputIdsTable<'_> {
 
         let recipes = Recipe::to_map_ref(recipes);
         let id_map = recipes
-            .iter()
-            .flat_map(|(_, recipe)| {
-                from_item_id(recipe.output.item_id, &recipes)
+            .values()
+            .flat_map(|recipe| {
+                recipe_tree_ids(recipe.output.item_id, &recipes)
                     .into_iter()
                     .map(|input_id| (recipe.output.item_id, input_id))
             })
@@ -48,12 +47,8 @@ impl InputIdsTable<'_> {
 
         let start = Instant::now();
         let num_ids = ids.len();
-        let ids = ids.iter().map(|id| id.item_id().to_string()).join(",");
-        let query_string = strip_whitespace(format!("{} ({ids})", SQL_SELECT));
-
         let mut input_ids = Vec::new();
-        let mut sql_query = sqlx::query(&query_string).persistent(true).fetch(self.db);
-        while let Some(row) = sql_query.try_next().await? {
+        for row in fetch_by_ids(self.db, SQL_SELECT, ids).await? {
             let input_id: u32 = row.get(0);
             input_ids.push(input_id);
         }
@@ -63,7 +58,8 @@ impl InputIdsTable<'_> {
     }
 }
 
-fn from_item_id<I: ItemId>(id: I, recipes: &BTreeMap<u32, &Recipe>) -> Vec<u32> {
+/// Returns the id of the item, and of every item in its recipe tree.
+pub fn recipe_tree_ids<I: ItemId>(id: I, recipes: &BTreeMap<u32, &Recipe>) -> Vec<u32> {
     fn push_ids(recipes: &BTreeMap<u32, &Recipe>, ids: &mut Vec<u32>, item_id: u32) {
         ids.push(item_id);
 
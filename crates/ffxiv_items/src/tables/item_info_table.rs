@@ -1,17 +1,21 @@
-use std::{collections::BTreeMap, io::Cursor, time::Instant};
+use std::{collections::BTreeMap, future::Future, io::Cursor, time::Instant};
 
 use anyhow::Result;
-use chrono::{DateTime, FixedOffset};
 use const_format::formatcp;
-use futures::TryStreamExt;
+use futures::{future::try_join_all, try_join};
 use itertools::Itertools;
 use mock_traits::FileDownloader;
 use sqlx::{QueryBuilder, Row};
 
-use crate::{csv_parse, last_updated_from_github, ItemDB, ItemId, ItemInfo};
+use crate::{
+    csv_parse, latest_commit_from_github,
+    parsers::{EquipSlotList, JobCategoryList},
+    CommitInfo, CsvColumns, Food, FoodEffect, FoodKind, ItemDB, ItemId, ItemInfo,
+};
 
 use super::{
-    download_csv, impl_table, impl_table_builder, strip_whitespace, RecipeTable, BIND_MAX,
+    download_csv, fetch_by_ids, impl_table, impl_table_builder, strip_whitespace,
+    DatasetFilesTable, FoodEffectTable, ItemStat, ItemStatTable, RecipeTable, BIND_MAX,
 };
 
 ////////////////////////////////////////////////////////////
@@ -26,26 +30,35 @@ impl ItemInfoTable<'_> {
         }
 
         let start = Instant::now();
-        let _ids = ids.iter().map(|id| id.item_id().to_string()).join(",");
-        let query_string = strip_whitespace(format!("{} ({_ids})", SQL_SELECT));
+        let recipes = RecipeTable::new(self.db);
+        let stats = ItemStatTable::new(self.db);
+        let foods = FoodEffectTable::new(self.db);
+        let (rows, recipes, mut stats, mut foods) = try_join!(
+            fetch_by_ids(self.db, SQL_SELECT, ids),
+            recipes.by_item_ids(ids),
+            stats.by_item_ids(ids),
+            foods.by_item_ids(ids)
+        )?;
 
         let mut items = BTreeMap::new();
-        let mut sql_query = sqlx::query(&query_string).persistent(true).fetch(self.db);
-        while let Some(row) = sql_query.try_next().await? {
+        for row in rows {
             let item_id: u32 = row.get(0);
             let name: String = row.get(1);
+            let search_category: u32 = row.get(2);
             items.insert(
                 item_id,
                 ItemInfo {
                     id: item_id,
                     name,
                     recipe: None,
+                    stats: stats.remove(&item_id).unwrap_or_default(),
+                    food: foods.remove(&item_id),
+                    is_marketable: search_category != 0,
                 },
             );
         }
         log::debug!(target: "ffxiv_items", "Query for {} items: {:.3}s", ids.len(), start.elapsed().as_secs_f32());
 
-        let recipes = RecipeTable::new(self.db).by_item_ids(ids).await?;
         for recipe in recipes {
             items.entry(recipe.output.item_id).and_modify(|item| {
                 item.recipe = Some(recipe);
@@ -54,74 +67,287 @@ impl ItemInfoTable<'_> {
 
         Ok(items.into_values().collect())
     }
+
+    /// Returns the number of guaranteed materia slots of `item_id`, and
+    /// whether it may be overmelded, if the item exists.
+    pub async fn materia_slots(&self, item_id: u32) -> Result<Option<(u32, bool)>> {
+        let query_string = strip_whitespace(SQL_SELECT_MATERIA_SLOTS);
+        let row = sqlx::query(&query_string)
+            .bind(item_id)
+            .fetch_optional(self.db)
+            .await?;
+        Ok(row.map(|row| (row.get(0), row.get(1))))
+    }
+
+    /// Returns the ids & names of the items named any of `names`, as compared
+    /// by the table's collation.
+    pub async fn by_names(&self, names: &[&str]) -> Result<Vec<(u32, String)>> {
+        let mut items = Vec::new();
+        for names in names.chunks(BIND_MAX) {
+            let mut builder = QueryBuilder::new(strip_whitespace(SQL_SELECT_BY_NAME));
+            let mut separated = builder.push(" (").separated(", ");
+            for name in names {
+                separated.push_bind(*name);
+            }
+            separated.push_unseparated(")");
+
+            let rows = builder.build().fetch_all(self.db).await?;
+            items.extend(rows.into_iter().map(|row| (row.get(0), row.get(1))));
+        }
+        Ok(items)
+    }
 }
 
 ////////////////////////////////////////////////////////////
 
-struct CsvItem {
+pub struct CsvItem {
     pub id: u32,
     pub name: String,
     pub ui_category: u32,
+    pub search_category: u32,
     pub ilevel: u32,
     pub equip_level: u32,
+    pub equip_slot: &'static str,
+    pub class_jobs: u64,
+    pub stats: Vec<(String, i32)>,
+    pub food: Option<Food>,
+    pub materia_slots: u32,
+    pub advanced_melding: bool,
 }
 
 ////////////////////////////////////////////////////////////
 
 const CSV_FILE: &str = "Item.csv";
+const CSV_FILE_EQUIP_SLOTS: &str = "EquipSlotCategory.csv";
+const CSV_FILE_JOB_CATEGORIES: &str = "ClassJobCategory.csv";
+const CSV_FILE_BASE_PARAMS: &str = "BaseParam.csv";
+const CSV_FILE_ITEM_ACTIONS: &str = "ItemAction.csv";
+const CSV_FILE_ITEM_FOOD: &str = "ItemFood.csv";
+
+const CSV_FILES: [&str; 6] = [
+    CSV_FILE,
+    CSV_FILE_EQUIP_SLOTS,
+    CSV_FILE_JOB_CATEGORIES,
+    CSV_FILE_BASE_PARAMS,
+    CSV_FILE_ITEM_ACTIONS,
+    CSV_FILE_ITEM_FOOD,
+];
+
+/// The number of base parameters listed on each item.
+const BASE_PARAM_COUNT: usize = 6;
+
+/// The statuses granted by item actions that raise stats through `ItemFood`.
+const STATUS_WELL_FED: u32 = 48;
+const STATUS_MEDICATED: u32 = 49;
 
 impl<F: FileDownloader> ItemInfoTableBuilder<'_, F> {
     pub async fn initialize(&self) -> Result<()> {
-        let items = Self::download().await?;
+        let items = self.download().await?;
 
         println!("Initializing Items Database Table");
-        let items = items.iter().filter(|item| !item.name.is_empty());
-        for items in &items.chunks(BIND_MAX / 5) {
+        let items = items
+            .into_iter()
+            .filter(|item| !item.name.is_empty())
+            .collect_vec();
+        let foods = items
+            .iter()
+            .filter_map(|item| Some((item.id, item.food.clone()?)))
+            .collect_vec();
+        let stats = items
+            .iter()
+            .flat_map(|item| {
+                item.stats.iter().map(|(stat, value)| ItemStat {
+                    item_id: item.id,
+                    stat: stat.clone(),
+                    value: *value,
+                })
+            })
+            .collect_vec();
+        for items in items.chunks(BIND_MAX / 10) {
             QueryBuilder::new(strip_whitespace(SQL_INSERT))
                 .push_values(items, |mut b, item| {
                     b.push_bind(item.id)
                         .push_bind(&item.name)
                         .push_bind(item.ui_category)
+                        .push_bind(item.search_category)
                         .push_bind(item.ilevel)
-                        .push_bind(item.equip_level);
+                        .push_bind(item.equip_level)
+                        .push_bind(item.equip_slot)
+                        .push_bind(item.class_jobs)
+                        .push_bind(item.materia_slots)
+                        .push_bind(item.advanced_melding);
                 })
                 .build()
                 .execute(self.db)
                 .await?;
         }
+        ItemStatTable::new(self.db).initialize(&stats).await?;
+        FoodEffectTable::new(self.db).initialize(&foods).await?;
 
+        let files = DatasetFilesTable::new(self.db);
+        try_join_all(CSV_FILES.map(|file| files.mark_imported(file))).await?;
         Ok(())
     }
 
-    pub async fn last_updated_github(&self) -> Result<DateTime<FixedOffset>> {
-        last_updated_from_github::<F>(CSV_FILE).await
+    pub async fn latest_commits(&self) -> Result<Vec<CommitInfo>> {
+        try_join_all(CSV_FILES.map(|file| latest_commit_from_github::<F>(self.db, file))).await
     }
 
-    async fn download() -> Result<Vec<CsvItem>> {
+    async fn download(&self) -> Result<Vec<CsvItem>> {
         println!("Downloading Items from Github");
+        download_items(|file| download_csv::<F>(self.db, file)).await
+    }
+}
 
-        let reader = Cursor::new(download_csv::<F>(CSV_FILE).await?);
-        let mut items = Vec::new();
-        csv_parse!(reader => {
-            id = U[0];
-            name = S[9 + 1];
-            ilevel = U[11 + 1];
-            ui_category = U[15 + 1];
-            equip_level = U[40 + 1];
-
-            let item = CsvItem {
-                id,
-                name: name.replace('\u{00A0}', " ").clone(),
-                ui_category,
-                ilevel,
-                equip_level,
-            };
-
-            items.push(item);
+/// Parses the items from their csv files, each fetched with `download`.
+pub async fn download_items<D, Fut>(download: D) -> Result<Vec<CsvItem>>
+where
+    D: Fn(&'static str) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let (items_csv, equip_slots, job_categories, base_params, item_actions, item_food) = try_join!(
+        download(CSV_FILE),
+        download(CSV_FILE_EQUIP_SLOTS),
+        download(CSV_FILE_JOB_CATEGORIES),
+        download(CSV_FILE_BASE_PARAMS),
+        download(CSV_FILE_ITEM_ACTIONS),
+        download(CSV_FILE_ITEM_FOOD),
+    )?;
+    let equip_slots = EquipSlotList::from_csv(CSV_FILE_EQUIP_SLOTS, &equip_slots)?;
+    let job_categories = JobCategoryList::from_csv(CSV_FILE_JOB_CATEGORIES, &job_categories)?;
+
+    let columns = CsvColumns::new(CSV_FILE_BASE_PARAMS, &base_params)?;
+    let name_column = columns.get("Name")?;
+    let mut stat_names = BTreeMap::new();
+    csv_parse!(CSV_FILE_BASE_PARAMS, base_params.as_bytes(), info => {
+        id = U[0];
+        name = S[name_column];
+        if !name.is_empty() {
+            stat_names.insert(id, name);
+        }
+    });
+
+    let foods = parse_foods(&item_actions, &item_food, &stat_names)?;
+
+    let columns = CsvColumns::new(CSV_FILE, &items_csv)?;
+    let item_action_column = columns.get("ItemAction")?;
+    let materia_slots_column = columns.get("MateriaSlotCount")?;
+    let advanced_melding_column = columns.get("IsAdvancedMeldingPermitted")?;
+    let stat_columns = columns
+        .array("BaseParam", BASE_PARAM_COUNT)?
+        .into_iter()
+        .zip(columns.array("BaseParamValue", BASE_PARAM_COUNT)?)
+        .collect_vec();
+
+    let reader = Cursor::new(&items_csv);
+    let mut items = Vec::new();
+    csv_parse!(CSV_FILE, reader, info => {
+        id = U[0];
+        name = S[9 + 1];
+        ilevel = U[11 + 1];
+        ui_category = U[15 + 1];
+        search_category = U[16 + 1];
+        equip_slot_category = U[17 + 1];
+        equip_level = U[40 + 1];
+        job_category = U[43 + 1];
+        item_action = U[item_action_column];
+        materia_slots = U[materia_slots_column];
+        advanced_melding = B[advanced_melding_column];
+        let stats = stat_columns
+            .iter()
+            .filter_map(|&(stat_column, value_column)| {
+                let stat = stat_names.get(&info[stat_column].parse::<u32>().ok()?)?;
+                let value = info[value_column].parse::<i32>().ok()?;
+                Some((stat.clone(), value))
+            })
+            .collect_vec();
+
+        let item = CsvItem {
+            id,
+            name: name.replace('\u{00A0}', " ").clone(),
+            ui_category,
+            search_category,
+            ilevel,
+            equip_level,
+            equip_slot: equip_slots.name(equip_slot_category),
+            class_jobs: job_categories.bits(job_category),
+            stats,
+            food: foods.get(&item_action).cloned(),
+            materia_slots,
+            advanced_melding,
+        };
+
+        items.push(item);
+    });
+
+    Ok(items)
+}
+
+/// Parses the meals & medicines, by the id of the item action that eats them.
+fn parse_foods(
+    item_actions: &str,
+    item_food: &str,
+    stat_names: &BTreeMap<u32, String>,
+) -> Result<BTreeMap<u32, Food>> {
+    let columns = CsvColumns::new(CSV_FILE_ITEM_FOOD, item_food)?;
+    let count = columns.array_len("BaseParam");
+    let effect_columns = [
+        "BaseParam",
+        "IsRelative",
+        "Value",
+        "Max",
+        "Value{HQ}",
+        "Max{HQ}",
+    ]
+    .into_iter()
+    .map(|name| columns.array(name, count))
+    .collect::<Result<Vec<_>>>()?;
+
+    let mut effects = BTreeMap::new();
+    csv_parse!(CSV_FILE_ITEM_FOOD, item_food.as_bytes(), info => {
+        id = U[0];
+        let food_effects = (0..count)
+            .filter_map(|index| {
+                let value = |column: usize| {
+                    info[effect_columns[column][index]].parse::<i32>().unwrap_or_default()
+                };
+                let stat = stat_names.get(&info[effect_columns[0][index]].parse::<u32>().ok()?)?;
+                Some(FoodEffect {
+                    stat: stat.clone(),
+                    is_relative: info[effect_columns[1][index]] == "True",
+                    value: value(2),
+                    max: value(3),
+                    value_hq: value(4),
+                    max_hq: value(5),
+                })
+            })
+            .collect_vec();
+        effects.insert(id, food_effects);
+    });
+
+    let columns = CsvColumns::new(CSV_FILE_ITEM_ACTIONS, item_actions)?;
+    let status_column = columns.get("Data[0]")?;
+    let food_column = columns.get("Data[1]")?;
+
+    let mut foods = BTreeMap::new();
+    csv_parse!(CSV_FILE_ITEM_ACTIONS, item_actions.as_bytes(), info => {
+        id = U[0];
+        let kind = match info[status_column].parse::<u32>().unwrap_or_default() {
+            STATUS_WELL_FED => FoodKind::Meal,
+            STATUS_MEDICATED => FoodKind::Medicine,
+            _ => continue,
+        };
+        let food_id = info[food_column].parse::<u32>().unwrap_or_default();
+        let Some(food_effects) = effects.get(&food_id).filter(|effects| !effects.is_empty()) else {
+            continue;
+        };
+        foods.insert(id, Food {
+            kind,
+            effects: food_effects.clone(),
         });
+    });
 
-        Ok(items)
-    }
+    Ok(foods)
 }
 
 ////////////////////////////////////////////////////////////
@@ -130,19 +356,36 @@ const SQL_TABLE_NAME: &str = "items";
 
 const SQL_CREATE: &str = formatcp!(
     "CREATE TABLE IF NOT EXISTS {SQL_TABLE_NAME} (
-        id          MEDIUMINT       UNSIGNED    PRIMARY KEY,
-        name        VARCHAR(100)                NOT NULL,
-        ui_category SMALLINT        UNSIGNED    NOT NULL,
-        item_level  SMALLINT        UNSIGNED    NOT NULL,
-        equip_level SMALLINT        UNSIGNED    NOT NULL,
-        INDEX       ( name ),
-        INDEX       ( item_level ),
-        INDEX       ( equip_level ),
-        INDEX       ( ui_category )
+        id                  MEDIUMINT   UNSIGNED    PRIMARY KEY,
+        name                VARCHAR(100)            NOT NULL,
+        ui_category         SMALLINT    UNSIGNED    NOT NULL,
+        search_category     SMALLINT    UNSIGNED    NOT NULL,
+        item_level          SMALLINT    UNSIGNED    NOT NULL,
+        equip_level         SMALLINT    UNSIGNED    NOT NULL,
+        equip_slot          VARCHAR(20)             NOT NULL,
+        class_jobs          BIGINT      UNSIGNED    NOT NULL,
+        materia_slots       TINYINT     UNSIGNED    NOT NULL,
+        advanced_melding    BOOL                    NOT NULL,
+        INDEX               ( name ),
+        INDEX               ( item_level ),
+        INDEX               ( equip_level ),
+        INDEX               ( ui_category ),
+        INDEX               ( search_category ),
+        INDEX               ( equip_slot )
     )"
 );
 
-const SQL_INSERT: &str =
-    formatcp!("INSERT INTO {SQL_TABLE_NAME} (id, name, ui_category, item_level, equip_level) ");
+const SQL_INSERT: &str = formatcp!(
+    "INSERT INTO {SQL_TABLE_NAME} (
+        id, name, ui_category, search_category, item_level, equip_level, equip_slot,
+        class_jobs, materia_slots, advanced_melding
+    ) "
+);
+
+const SQL_SELECT: &str =
+    formatcp!("SELECT id, name, search_category FROM {SQL_TABLE_NAME} WHERE id IN");
+
+const SQL_SELECT_BY_NAME: &str = formatcp!("SELECT id, name FROM {SQL_TABLE_NAME} WHERE name IN");
 
-const SQL_SELECT: &str = formatcp!("SELECT id, name FROM {SQL_TABLE_NAME} WHERE id IN");
+const SQL_SELECT_MATERIA_SLOTS: &str =
+    formatcp!("SELECT materia_slots, advanced_melding FROM {SQL_TABLE_NAME} WHERE id = ?");
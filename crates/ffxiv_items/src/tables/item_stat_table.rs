@@ -0,0 +1,91 @@
+use std::{collections::BTreeMap, time::Instant};
+
+use anyhow::Result;
+use const_format::formatcp;
+use sqlx::{QueryBuilder, Row};
+
+use crate::{ItemDB, ItemId};
+
+use super::{fetch_by_ids, impl_table, strip_whitespace, BIND_MAX};
+
+////////////////////////////////////////////////////////////
+
+impl_table!(ItemStatTable);
+
+/// A base parameter of an item, e.g. 50 `Control`.
+pub struct ItemStat {
+    pub item_id: u32,
+    pub stat: String,
+    pub value: i32,
+}
+
+impl ItemStatTable<'_> {
+    pub async fn initialize(&self, stats: &[ItemStat]) -> Result<()> {
+        println!("Initializing Item Stats Database Table");
+
+        for stats in stats.chunks(BIND_MAX / 3) {
+            QueryBuilder::new(strip_whitespace(SQL_INSERT))
+                .push_values(stats, |mut b, stat| {
+                    b.push_bind(stat.item_id)
+                        .push_bind(&stat.stat)
+                        .push_bind(stat.value);
+                })
+                .build()
+                .execute(self.db)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the stats of `ids`, by item id, in the order they're listed on
+    /// each item.
+    pub async fn by_item_ids<I: ItemId>(
+        &self,
+        ids: &[I],
+    ) -> Result<BTreeMap<u32, Vec<(String, i32)>>> {
+        if ids.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+
+        let start = Instant::now();
+        let num_ids = ids.len();
+        let mut rows = fetch_by_ids(self.db, SQL_SELECT, ids).await?;
+        // Stats are listed in the order they were inserted
+        rows.sort_by_key(|row| row.get::<u32, _>(0));
+
+        let mut stats = BTreeMap::<u32, Vec<(String, i32)>>::new();
+        for row in &rows {
+            stats
+                .entry(row.get(1))
+                .or_default()
+                .push((row.get(2), row.get(3)));
+        }
+
+        log::debug!(target: "ffxiv_items", "Query for {num_ids} item stats ({} returned): {:.3}s", rows.len(), start.elapsed().as_secs_f32());
+        Ok(stats)
+    }
+}
+
+////////////////////////////////////////////////////////////
+
+const SQL_TABLE_NAME: &str = "item_stats";
+
+const SQL_CREATE: &str = formatcp!(
+    "CREATE TABLE IF NOT EXISTS {SQL_TABLE_NAME} (
+        id          MEDIUMINT   UNSIGNED    AUTO_INCREMENT  PRIMARY KEY,
+        item_id     MEDIUMINT   UNSIGNED    NOT NULL,
+        stat        VARCHAR(50)             NOT NULL,
+        value       SMALLINT                NOT NULL,
+        INDEX       ( item_id ),
+        INDEX       ( stat, value )
+    )"
+);
+
+const SQL_INSERT: &str = formatcp!("INSERT INTO {SQL_TABLE_NAME} (item_id, stat, value) ");
+
+const SQL_SELECT: &str = formatcp!(
+    "SELECT id, item_id, stat, value
+    FROM {SQL_TABLE_NAME}
+    WHERE item_id IN"
+);
@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use const_format::formatcp;
+use sqlx::{QueryBuilder, Row};
+
+use crate::ItemDB;
+
+use super::{impl_table, strip_whitespace, CsvJoinRate, BIND_MAX};
+
+////////////////////////////////////////////////////////////
+
+impl_table!(MateriaJoinRateTable);
+
+impl MateriaJoinRateTable<'_> {
+    pub async fn initialize(&self, join_rates: &[CsvJoinRate]) -> Result<()> {
+        println!("Initializing Materia Join Rates Database Table");
+        for join_rates in join_rates.chunks(BIND_MAX / 4) {
+            QueryBuilder::new(strip_whitespace(SQL_INSERT))
+                .push_values(join_rates, |mut b, join_rate| {
+                    b.push_bind(join_rate.grade)
+                        .push_bind(join_rate.overmeld)
+                        .push_bind(join_rate.nq_rate)
+                        .push_bind(join_rate.hq_rate);
+                })
+                .build()
+                .execute(self.db)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns every success rate, by materia grade & overmeld slot.
+    pub async fn all(&self) -> Result<BTreeMap<(u32, u32), (u32, u32)>> {
+        let rows = sqlx::query(&strip_whitespace(SQL_SELECT))
+            .fetch_all(self.db)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| ((row.get(0), row.get(1)), (row.get(2), row.get(3))))
+            .collect())
+    }
+}
+
+////////////////////////////////////////////////////////////
+
+const SQL_TABLE_NAME: &str = "materia_join_rates";
+
+const SQL_CREATE: &str = formatcp!(
+    "CREATE TABLE IF NOT EXISTS {SQL_TABLE_NAME} (
+        id          SMALLINT    UNSIGNED    AUTO_INCREMENT  PRIMARY KEY,
+        grade       TINYINT     UNSIGNED    NOT NULL,
+        overmeld    TINYINT     UNSIGNED    NOT NULL,
+        nq_rate     TINYINT     UNSIGNED    NOT NULL,
+        hq_rate     TINYINT     UNSIGNED    NOT NULL,
+        UNIQUE      ( grade, overmeld )
+    )"
+);
+
+const SQL_INSERT: &str =
+    formatcp!("INSERT INTO {SQL_TABLE_NAME} (grade, overmeld, nq_rate, hq_rate) ");
+
+const SQL_SELECT: &str =
+    formatcp!("SELECT grade, overmeld, nq_rate, hq_rate FROM {SQL_TABLE_NAME}");
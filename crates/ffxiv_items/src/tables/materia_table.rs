@@ -0,0 +1,191 @@
+use std::{collections::BTreeMap, time::Instant};
+
+use anyhow::Result;
+use const_format::formatcp;
+use futures::{future::try_join_all, try_join};
+use itertools::Itertools;
+use mock_traits::FileDownloader;
+use sqlx::{QueryBuilder, Row};
+
+use crate::{
+    csv_parse, latest_commit_from_github, CommitInfo, CsvColumns, ItemDB, ItemId, Materia,
+};
+
+use super::{
+    download_csv, fetch_by_ids, impl_table, impl_table_builder, strip_whitespace,
+    DatasetFilesTable, MateriaJoinRateTable, BIND_MAX,
+};
+
+////////////////////////////////////////////////////////////
+
+impl_table!(MateriaTable);
+impl_table_builder!(MateriaTableBuilder, FileDownloader);
+
+impl MateriaTable<'_> {
+    pub async fn by_item_ids<I: ItemId>(&self, ids: &[I]) -> Result<Vec<Materia>> {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let start = Instant::now();
+        let rows = fetch_by_ids(self.db, SQL_SELECT, ids).await?;
+        let materia = rows
+            .into_iter()
+            .map(|row| Materia {
+                item_id: row.get(0),
+                stat: row.get(1),
+                grade: row.get(2),
+                value: row.get(3),
+            })
+            .sorted_by_key(|materia| materia.item_id)
+            .collect_vec();
+
+        log::debug!(target: "ffxiv_items", "Query for {} materia ({} returned): {:.3}s", ids.len(), materia.len(), start.elapsed().as_secs_f32());
+        Ok(materia)
+    }
+
+    /// Returns the overmeld success rates, in percent for normal & high
+    /// quality gear, by materia grade & overmeld slot from 0.
+    pub async fn join_rates(&self) -> Result<BTreeMap<(u32, u32), (u32, u32)>> {
+        MateriaJoinRateTable::new(self.db).all().await
+    }
+}
+
+////////////////////////////////////////////////////////////
+
+/// The overmeld success rates for a materia grade & overmeld slot, in whole
+/// percents.
+pub struct CsvJoinRate {
+    pub grade: u32,
+    pub overmeld: u32,
+    pub nq_rate: u32,
+    pub hq_rate: u32,
+}
+
+////////////////////////////////////////////////////////////
+
+const CSV_FILE_MATERIA: &str = "Materia.csv";
+const CSV_FILE_JOIN_RATES: &str = "MateriaJoinRate.csv";
+const CSV_FILE_BASE_PARAMS: &str = "BaseParam.csv";
+
+// The base params are marked imported by the item table
+const CSV_FILES: [&str; 2] = [CSV_FILE_MATERIA, CSV_FILE_JOIN_RATES];
+
+impl<F: FileDownloader> MateriaTableBuilder<'_, F> {
+    pub async fn initialize(&self) -> Result<()> {
+        println!("Downloading Materia from Github");
+        let (materia_csv, join_rates_csv, base_params) = try_join!(
+            download_csv::<F>(self.db, CSV_FILE_MATERIA),
+            download_csv::<F>(self.db, CSV_FILE_JOIN_RATES),
+            download_csv::<F>(self.db, CSV_FILE_BASE_PARAMS),
+        )?;
+
+        let columns = CsvColumns::new(CSV_FILE_BASE_PARAMS, &base_params)?;
+        let name_column = columns.get("Name")?;
+        let mut stat_names = BTreeMap::new();
+        csv_parse!(CSV_FILE_BASE_PARAMS, base_params.as_bytes(), info => {
+            id = U[0];
+            name = S[name_column];
+            stat_names.insert(id, name);
+        });
+
+        // Each row is a kind of materia, with an item for each grade
+        let columns = CsvColumns::new(CSV_FILE_MATERIA, &materia_csv)?;
+        let grades = columns.array_len("Item");
+        let item_columns = columns.array("Item", grades)?;
+        let value_columns = columns.array("Value", grades)?;
+        let stat_column = columns.get("BaseParam")?;
+
+        let mut materia = Vec::new();
+        csv_parse!(CSV_FILE_MATERIA, materia_csv.as_bytes(), info => {
+            stat = U[stat_column];
+            let Some(stat) = stat_names.get(&stat).filter(|stat| !stat.is_empty()) else {
+                continue;
+            };
+            for (grade, (&item_column, &value_column)) in (1..).zip(item_columns.iter().zip(&value_columns)) {
+                let item_id = info[item_column].parse::<u32>().unwrap_or_default();
+                if item_id == 0 {
+                    continue;
+                }
+                materia.push(Materia {
+                    item_id,
+                    stat: stat.clone(),
+                    grade,
+                    value: info[value_column].parse::<i32>().unwrap_or_default(),
+                });
+            }
+        });
+
+        // Each row is a materia grade, from 0
+        let columns = CsvColumns::new(CSV_FILE_JOIN_RATES, &join_rates_csv)?;
+        let overmelds = columns.array_len("NQOvermeldPercentSlot");
+        let nq_columns = columns.array("NQOvermeldPercentSlot", overmelds)?;
+        let hq_columns = columns.array("HQOvermeldPercentSlot", overmelds)?;
+
+        let mut join_rates = Vec::new();
+        csv_parse!(CSV_FILE_JOIN_RATES, join_rates_csv.as_bytes(), info => {
+            id = U[0];
+            for (overmeld, (&nq_column, &hq_column)) in (0..).zip(nq_columns.iter().zip(&hq_columns)) {
+                join_rates.push(CsvJoinRate {
+                    grade: id + 1,
+                    overmeld,
+                    nq_rate: percent(info[nq_column]),
+                    hq_rate: percent(info[hq_column]),
+                });
+            }
+        });
+
+        println!("Initializing Materia Database Table");
+        for materia in materia.chunks(BIND_MAX / 4) {
+            QueryBuilder::new(strip_whitespace(SQL_INSERT))
+                .push_values(materia, |mut b, materia| {
+                    b.push_bind(materia.item_id)
+                        .push_bind(&materia.stat)
+                        .push_bind(materia.grade)
+                        .push_bind(materia.value);
+                })
+                .build()
+                .execute(self.db)
+                .await?;
+        }
+        MateriaJoinRateTable::new(self.db)
+            .initialize(&join_rates)
+            .await?;
+
+        let files = DatasetFilesTable::new(self.db);
+        try_join_all(CSV_FILES.map(|file| files.mark_imported(file))).await?;
+        Ok(())
+    }
+
+    pub async fn latest_commits(&self) -> Result<Vec<CommitInfo>> {
+        try_join_all(CSV_FILES.map(|file| latest_commit_from_github::<F>(self.db, file))).await
+    }
+}
+
+/// Parses a success rate, which may be written as a decimal.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn percent(value: &str) -> u32 {
+    value.parse::<f32>().unwrap_or_default().round().max(0.0) as u32
+}
+
+////////////////////////////////////////////////////////////
+
+const SQL_TABLE_NAME: &str = "materia";
+
+const SQL_CREATE: &str = formatcp!(
+    "CREATE TABLE IF NOT EXISTS {SQL_TABLE_NAME} (
+        id          MEDIUMINT   UNSIGNED    PRIMARY KEY,
+        stat        VARCHAR(50)             NOT NULL,
+        grade       TINYINT     UNSIGNED    NOT NULL,
+        value       SMALLINT                NOT NULL,
+        INDEX       ( stat )
+    )"
+);
+
+const SQL_INSERT: &str = formatcp!("INSERT INTO {SQL_TABLE_NAME} (id, stat, grade, value) ");
+
+const SQL_SELECT: &str = formatcp!(
+    "SELECT id, stat, grade, value
+    FROM {SQL_TABLE_NAME}
+    WHERE id IN"
+);
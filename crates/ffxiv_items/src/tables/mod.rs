@@ -1,31 +1,124 @@
+mod collectable_reward_table;
+mod company_craft_supply_table;
+mod company_craft_table;
+mod dataset_files_table;
+mod food_effect_table;
+mod gc_supply_duty_table;
+mod gc_supply_reward_table;
+mod github_cache_table;
 mod ingredient_table;
 mod input_ids_table;
 mod item_info_table;
+mod item_stat_table;
+mod materia_join_rate_table;
+mod materia_table;
 mod recipe_table;
+mod saved_query_table;
+mod search_category_table;
 mod table;
 mod ui_category_table;
 mod update_table;
 
 use table::{impl_table, impl_table_builder};
 
+pub use collectable_reward_table::{CollectableRewardTable, CollectableRewardTableBuilder};
+pub use company_craft_supply_table::CompanyCraftSupplyTable;
+pub use company_craft_table::{
+    download_company_crafts, CompanyCraft, CompanyCraftTable, CompanyCraftTableBuilder,
+};
+pub use dataset_files_table::DatasetFilesTable;
+pub use food_effect_table::FoodEffectTable;
+pub use gc_supply_duty_table::{GcSupplyDutyTable, GcSupplyDutyTableBuilder};
+pub use gc_supply_reward_table::{CsvGcSupplyReward, GcSupplyRewardTable};
+pub use github_cache_table::GithubCacheTable;
 pub use ingredient_table::IngredientTable;
-pub use input_ids_table::InputIdsTable;
-pub use item_info_table::{ItemInfoTable, ItemInfoTableBuilder};
-pub use recipe_table::{RecipeTable, RecipeTableBuilder};
-pub use ui_category_table::{UiCategoryTable, UiCategoryTableBuilder};
+pub use input_ids_table::{recipe_tree_ids, InputIdsTable};
+pub use item_info_table::{download_items, ItemInfoTable, ItemInfoTableBuilder};
+pub use item_stat_table::{ItemStat, ItemStatTable};
+pub use materia_join_rate_table::MateriaJoinRateTable;
+pub use materia_table::{CsvJoinRate, MateriaTable, MateriaTableBuilder};
+pub use recipe_table::{download_recipe_info, RecipeTable, RecipeTableBuilder};
+pub use saved_query_table::SavedQueryTable;
+pub use search_category_table::{
+    download_search_categories, SearchCategoryTable, SearchCategoryTableBuilder,
+};
+pub use ui_category_table::{download_ui_categories, UiCategoryTable, UiCategoryTableBuilder};
 pub use update_table::UpdateTable;
 
 pub(super) const BIND_MAX: usize = 65535;
 
+/// Ids are looked up in fixed-size chunks of bound parameters, so each lookup
+/// only ever prepares a single statement, however many ids are passed in.
+const ID_CHUNK_SIZE: usize = 256;
+
+/// Runs `sql_select`, which must end with `IN`, for every one of the `ids`.
+/// Ids are deduplicated, so a row is never returned twice.
+async fn fetch_by_ids<I: crate::ItemId>(
+    db: &crate::ItemDB,
+    sql_select: &str,
+    ids: &[I],
+) -> anyhow::Result<Vec<sqlx::mysql::MySqlRow>> {
+    use itertools::Itertools;
+
+    let ids = ids.iter().map(I::item_id).sorted().dedup().collect_vec();
+    let placeholders = vec!["?"; ID_CHUNK_SIZE].join(", ");
+    let query_string = strip_whitespace(format!("{sql_select} ({placeholders})"));
+
+    let mut rows = Vec::new();
+    for chunk in ids.chunks(ID_CHUNK_SIZE) {
+        // Pad the last chunk by repeating an id, which doesn't change the result
+        let mut chunk = chunk.to_vec();
+        chunk.resize(ID_CHUNK_SIZE, chunk[0]);
+        let mut sql_query = sqlx::query(&query_string);
+        for id in chunk {
+            sql_query = sql_query.bind(id);
+        }
+        rows.extend(sql_query.persistent(true).fetch_all(db).await?);
+    }
+    Ok(rows)
+}
+
 pub fn strip_whitespace<S: AsRef<str>>(s: S) -> String {
     use regex::Regex;
     let re = Regex::new(r"\s+").unwrap();
     re.replace_all(s.as_ref(), " ").into()
 }
 
-async fn download_csv<F: mock_traits::FileDownloader>(file_name: &str) -> anyhow::Result<String> {
-    F::download(&format!(
-        "https://raw.githubusercontent.com/xivapi/ffxiv-datamining/master/csv/{file_name}"
-    ))
-    .await
+/// Downloads the latest version of a csv file from the datamining repo,
+/// without recording its provenance.
+pub async fn download_latest_csv<F: mock_traits::FileDownloader>(
+    file_name: &'static str,
+) -> anyhow::Result<String> {
+    F::download(&csv_url("master", file_name)).await
+}
+
+fn csv_url(git_ref: &str, file_name: &str) -> String {
+    format!("https://raw.githubusercontent.com/xivapi/ffxiv-datamining/{git_ref}/csv/{file_name}")
+}
+
+/// Downloads a csv file from the datamining repo, recording its provenance.
+/// If a commit has been recorded for the file, that exact version is fetched.
+async fn download_csv<F: mock_traits::FileDownloader>(
+    db: &crate::ItemDB,
+    file_name: &str,
+) -> anyhow::Result<String> {
+    use sha2::{Digest, Sha256};
+
+    let files = DatasetFilesTable::new(db);
+    let sha = files.commit_sha(file_name).await?;
+    let git_ref = sha.as_deref().unwrap_or("master");
+    let contents = F::download(&csv_url(git_ref, file_name)).await?;
+
+    // The first two records after the header are field names & types
+    let row_count = csv::ReaderBuilder::new()
+        .from_reader(contents.as_bytes())
+        .records()
+        .count()
+        .saturating_sub(2);
+    let content_hash = format!("{:x}", Sha256::digest(contents.as_bytes()));
+    files
+        .record_download(file_name, sha.as_deref(), row_count, &content_hash)
+        .await?;
+
+    Ok(contents)
 }
@@ -1,17 +1,20 @@
-use std::{collections::BTreeMap, io::Cursor, time::Instant};
+use std::{collections::BTreeMap, future::Future, time::Instant};
 
-use anyhow::Result;
-use chrono::{DateTime, FixedOffset};
+use anyhow::{bail, Result};
 use const_format::formatcp;
-use futures::{try_join, TryStreamExt};
+use futures::try_join;
 use itertools::Itertools;
 use mock_traits::FileDownloader;
 use sqlx::{QueryBuilder, Row};
 
-use crate::{csv_parse, last_updated_from_github, Ingredient, ItemDB, ItemId, Recipe};
+use crate::{
+    column_values, csv_parse, latest_commit_from_github, CommitInfo, CsvColumns, Ingredient,
+    ItemDB, ItemId, Recipe, RecipeBook, RecipeDifficulty, RecipeKind,
+};
 
 use super::{
-    download_csv, impl_table, impl_table_builder, strip_whitespace, IngredientTable, BIND_MAX,
+    download_csv, fetch_by_ids, impl_table, impl_table_builder, strip_whitespace,
+    CompanyCraftSupplyTable, DatasetFilesTable, IngredientTable, BIND_MAX,
 };
 
 ////////////////////////////////////////////////////////////
@@ -26,16 +29,38 @@ impl RecipeTable<'_> {
         }
 
         let start = Instant::now();
-        let _ids = ids.iter().map(|id| id.item_id().to_string()).join(",");
-        let query_string = strip_whitespace(format!("{} ({_ids})", SQL_SELECT));
+        let ingredients = IngredientTable::new(self.db);
+        let company_crafts = CompanyCraftSupplyTable::new(self.db);
+        let (rows, ingredients, mut company_phases) = try_join!(
+            fetch_by_ids(self.db, SQL_SELECT, ids),
+            ingredients.by_item_ids(ids),
+            company_crafts.phases_by_item_ids(ids),
+        )?;
 
         let mut recipes = BTreeMap::new();
-        let mut sql_query = sqlx::query(&query_string).persistent(true).fetch(self.db);
-        while let Some(row) = sql_query.try_next().await? {
+        for row in rows {
             let item_id: u32 = row.get(0);
             let count: u32 = row.get(1);
             let level: u32 = row.get(2);
             let stars: u32 = row.get(3);
+            let difficulty = RecipeDifficulty {
+                progress: row.get(4),
+                quality: row.get(5),
+                durability: row.get(6),
+                progress_divider: row.get(7),
+                quality_divider: row.get(8),
+                progress_modifier: row.get(9),
+                quality_modifier: row.get(10),
+            };
+            let book_item_id: Option<u32> = row.get(11);
+            let book_name: Option<String> = row.get(12);
+            let book = book_item_id
+                .zip(book_name)
+                .map(|(item_id, name)| RecipeBook { item_id, name });
+            let (kind, phases) = match company_phases.remove(&item_id) {
+                Some(phases) => (RecipeKind::CompanyWorkshop, phases),
+                None => (RecipeKind::Crafter, Vec::new()),
+            };
             recipes.insert(
                 item_id,
                 Recipe {
@@ -43,12 +68,15 @@ impl RecipeTable<'_> {
                     inputs: Vec::new(),
                     level,
                     stars,
+                    difficulty,
+                    book,
+                    kind,
+                    phases,
                 },
             );
         }
         log::debug!(target: "ffxiv_items", "Query for {} recipes: {:.3}s", ids.len(), start.elapsed().as_secs_f32());
 
-        let ingredients = IngredientTable::new(self.db).by_item_ids(ids).await?;
         for (item_id, ingredient) in ingredients {
             recipes.entry(item_id).and_modify(|recipe| {
                 recipe.inputs.push(ingredient);
@@ -65,29 +93,44 @@ pub struct CsvRecipe {
     pub output: Ingredient,
     pub inputs: Vec<Ingredient>,
     pub level_id: u32,
+    /// Percentages of the recipe level's difficulty, quality & durability.
+    pub factors: [u32; 3],
+    /// The id of the secret recipe book needed to craft it, or 0.
+    pub book_id: u32,
 }
 
 pub struct CsvRecipeLevel {
-    pub id: u32,
     pub level: u32,
     pub stars: u32,
+    pub difficulty: RecipeDifficulty,
 }
 
 ////////////////////////////////////////////////////////////
 
 const CSV_FILE_RECIPE: &str = "Recipe.csv";
 const CSV_FILE_RECIPE_LEVEL: &str = "RecipeLevelTable.csv";
+const CSV_FILE_BOOK: &str = "SecretRecipeBook.csv";
 
 impl<F: FileDownloader> RecipeTableBuilder<'_, F> {
     pub async fn initialize(&self, recipes: &[Recipe]) -> Result<()> {
         println!("Initializing Recipes Database Table");
-        for recipes in &recipes.iter().chunks(BIND_MAX / 4) {
+        for recipes in &recipes.iter().chunks(BIND_MAX / 13) {
             QueryBuilder::new(strip_whitespace(SQL_INSERT))
                 .push_values(recipes, |mut b, recipe| {
+                    let difficulty = &recipe.difficulty;
                     b.push_bind(recipe.output.item_id)
                         .push_bind(recipe.output.count)
                         .push_bind(recipe.level)
-                        .push_bind(recipe.stars);
+                        .push_bind(recipe.stars)
+                        .push_bind(difficulty.progress)
+                        .push_bind(difficulty.quality)
+                        .push_bind(difficulty.durability)
+                        .push_bind(difficulty.progress_divider)
+                        .push_bind(difficulty.quality_divider)
+                        .push_bind(difficulty.progress_modifier)
+                        .push_bind(difficulty.quality_modifier)
+                        .push_bind(recipe.book.as_ref().map(|book| book.item_id))
+                        .push_bind(recipe.book.as_ref().map(|book| &book.name));
                 })
                 .build()
                 .execute(self.db)
@@ -97,80 +140,172 @@ impl<F: FileDownloader> RecipeTableBuilder<'_, F> {
         Ok(())
     }
 
-    pub async fn last_updated_github(&self) -> Result<DateTime<FixedOffset>> {
-        let recipe_updated = last_updated_from_github::<F>(CSV_FILE_RECIPE).await?;
-        let recipe_level_updated = last_updated_from_github::<F>(CSV_FILE_RECIPE_LEVEL).await?;
-        Ok(recipe_updated.max(recipe_level_updated))
+    /// Marks the recipe csv files as imported, once the recipe, ingredient &
+    /// input id tables have all been initialized from them.
+    pub async fn mark_imported(&self) -> Result<()> {
+        let files = DatasetFilesTable::new(self.db);
+        try_join!(
+            files.mark_imported(CSV_FILE_RECIPE),
+            files.mark_imported(CSV_FILE_RECIPE_LEVEL),
+            files.mark_imported(CSV_FILE_BOOK),
+        )?;
+        Ok(())
     }
 
-    pub async fn download_recipe_info() -> Result<Vec<Recipe>> {
-        println!("Downloading Recipes from Github");
-
-        let (csv_recipes, csv_recipe_levels) = try_join!(
-            Self::download_recipe_csv(),
-            Self::download_recipe_level_csv()
+    pub async fn latest_commits(&self) -> Result<Vec<CommitInfo>> {
+        let (recipe, recipe_level, book) = try_join!(
+            latest_commit_from_github::<F>(self.db, CSV_FILE_RECIPE),
+            latest_commit_from_github::<F>(self.db, CSV_FILE_RECIPE_LEVEL),
+            latest_commit_from_github::<F>(self.db, CSV_FILE_BOOK),
         )?;
+        Ok(vec![recipe, recipe_level, book])
+    }
 
-        let recipes = csv_recipes
-            .into_iter()
-            .map(|csv_recipe| {
-                let recipe_level = &csv_recipe_levels[&csv_recipe.level_id];
-                Recipe {
-                    output: csv_recipe.output,
-                    inputs: csv_recipe.inputs,
-                    level: recipe_level.level,
-                    stars: recipe_level.stars,
-                }
-            })
-            .collect();
-
-        Ok(recipes)
+    pub async fn download_recipe_info(&self) -> Result<Vec<Recipe>> {
+        println!("Downloading Recipes from Github");
+        download_recipe_info(|file| download_csv::<F>(self.db, file)).await
     }
+}
 
-    async fn download_recipe_csv() -> Result<Vec<CsvRecipe>> {
-        let reader = Cursor::new(download_csv::<F>(CSV_FILE_RECIPE).await?);
-        let mut recipes = BTreeMap::new();
-        csv_parse!(reader => {
-            level_id = U[2 + 1];
-            arr = U[4..24];
-
-            let mut ingredients = Vec::new();
-            for (item_id, count) in arr.into_iter().tuples() {
-                if count > 0 {
-                    ingredients.push(Ingredient { count, item_id });
-                }
-            }
+/// Parses the crafter recipes from their csv files, each fetched with
+/// `download`.
+pub async fn download_recipe_info<D, Fut>(download: D) -> Result<Vec<Recipe>>
+where
+    D: Fn(&'static str) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let (recipes, recipe_levels, books) = try_join!(
+        download(CSV_FILE_RECIPE),
+        download(CSV_FILE_RECIPE_LEVEL),
+        download(CSV_FILE_BOOK),
+    )?;
+    let csv_recipes = parse_recipes(&recipes)?;
+    let csv_recipe_levels = parse_recipe_levels(&recipe_levels)?;
+    let books = parse_books(&books)?;
+
+    csv_recipes
+        .into_iter()
+        .map(|csv_recipe| {
+            let Some(recipe_level) = csv_recipe_levels.get(&csv_recipe.level_id) else {
+                bail!(
+                    "Recipe for item {} references missing recipe level {}",
+                    csv_recipe.output.item_id,
+                    csv_recipe.level_id
+                );
+            };
+            let [progress, quality, durability] = csv_recipe.factors;
+            Ok(Recipe {
+                output: csv_recipe.output,
+                inputs: csv_recipe.inputs,
+                level: recipe_level.level,
+                stars: recipe_level.stars,
+                difficulty: RecipeDifficulty {
+                    progress: recipe_level.difficulty.progress * progress / 100,
+                    quality: recipe_level.difficulty.quality * quality / 100,
+                    durability: recipe_level.difficulty.durability * durability / 100,
+                    ..recipe_level.difficulty
+                },
+                book: books.get(&csv_recipe.book_id).cloned(),
+                kind: RecipeKind::Crafter,
+                phases: Vec::new(),
+            })
+        })
+        .collect()
+}
 
-            if ingredients.is_empty() || ingredients[0].item_id == 0 {
-                continue;
+fn parse_recipes(contents: &str) -> Result<Vec<CsvRecipe>> {
+    let columns = CsvColumns::new(CSV_FILE_RECIPE, contents)?;
+    let factor_columns = [
+        columns.get("DifficultyFactor")?,
+        columns.get("QualityFactor")?,
+        columns.get("DurabilityFactor")?,
+    ];
+    let book_column = columns.get("SecretRecipeBook")?;
+
+    let mut recipes = BTreeMap::new();
+    csv_parse!(CSV_FILE_RECIPE, contents.as_bytes(), info => {
+        level_id = U[2 + 1];
+        arr = U[4..24];
+        book_id = U[book_column];
+
+        let mut ingredients = Vec::new();
+        for (item_id, count) in arr.into_iter().tuples() {
+            if count > 0 {
+                ingredients.push(Ingredient { count, item_id });
             }
+        }
 
-            let output = ingredients.remove(0);
-            let inputs = ingredients;
-            recipes.insert(output.item_id,
-                CsvRecipe {
-                    output,
-                    inputs,
-                    level_id,
-                },
-            );
-        });
+        if ingredients.is_empty() || ingredients[0].item_id == 0 {
+            continue;
+        }
 
-        Ok(recipes.into_values().collect_vec())
-    }
+        let output = ingredients.remove(0);
+        let inputs = ingredients;
+        recipes.insert(output.item_id,
+            CsvRecipe {
+                output,
+                inputs,
+                level_id,
+                factors: column_values(&info, &factor_columns).try_into().unwrap_or_default(),
+                book_id,
+            },
+        );
+    });
+
+    Ok(recipes.into_values().collect_vec())
+}
 
-    async fn download_recipe_level_csv() -> Result<BTreeMap<u32, CsvRecipeLevel>> {
-        let reader = Cursor::new(download_csv::<F>(CSV_FILE_RECIPE_LEVEL).await?);
-        let mut recipe_levels = BTreeMap::new();
-        csv_parse!(reader => {
-            id = U[0];
-            level = U[1];
-            stars = U[1 + 1];
-            recipe_levels.insert(id, CsvRecipeLevel { id, level, stars });
-        });
-
-        Ok(recipe_levels)
-    }
+fn parse_recipe_levels(contents: &str) -> Result<BTreeMap<u32, CsvRecipeLevel>> {
+    let columns = CsvColumns::new(CSV_FILE_RECIPE_LEVEL, contents)?;
+    let difficulty_columns = [
+        "Difficulty",
+        "Quality",
+        "Durability",
+        "ProgressDivider",
+        "QualityDivider",
+        "ProgressModifier",
+        "QualityModifier",
+    ]
+    .map(|name| columns.get(name))
+    .into_iter()
+    .collect::<Result<Vec<_>>>()?;
+
+    let mut recipe_levels = BTreeMap::new();
+    csv_parse!(CSV_FILE_RECIPE_LEVEL, contents.as_bytes(), info => {
+        id = U[0];
+        level = U[1];
+        stars = U[1 + 1];
+        let values = column_values(&info, &difficulty_columns);
+        let difficulty = RecipeDifficulty {
+            progress: values[0],
+            quality: values[1],
+            durability: values[2],
+            progress_divider: values[3],
+            quality_divider: values[4],
+            progress_modifier: values[5],
+            quality_modifier: values[6],
+        };
+        recipe_levels.insert(id, CsvRecipeLevel { level, stars, difficulty });
+    });
+
+    Ok(recipe_levels)
+}
+
+fn parse_books(contents: &str) -> Result<BTreeMap<u32, RecipeBook>> {
+    let columns = CsvColumns::new(CSV_FILE_BOOK, contents)?;
+    let (item_column, name_column) = (columns.get("Item")?, columns.get("Name")?);
+
+    let mut books = BTreeMap::new();
+    csv_parse!(CSV_FILE_BOOK, contents.as_bytes(), info => {
+        id = U[0];
+        item_id = U[item_column];
+        name = S[name_column];
+        if item_id != 0 && !name.is_empty() {
+            books.insert(id, RecipeBook { item_id, name });
+        }
+    });
+
+    Ok(books)
 }
 
 ////////////////////////////////////////////////////////////
@@ -179,15 +314,32 @@ const SQL_TABLE_NAME: &str = "recipes";
 
 const SQL_CREATE: &str = formatcp!(
     "CREATE TABLE IF NOT EXISTS {SQL_TABLE_NAME} (
-        id      MEDIUMINT   UNSIGNED    NOT NULL    PRIMARY KEY,
-        count   SMALLINT    UNSIGNED    NOT NULL,
-        level   SMALLINT    UNSIGNED    NOT NULL,
-        stars   SMALLINT    UNSIGNED    NOT NULL,
-        INDEX   ( level )
+        id                  MEDIUMINT   UNSIGNED    NOT NULL    PRIMARY KEY,
+        count               SMALLINT    UNSIGNED    NOT NULL,
+        level               SMALLINT    UNSIGNED    NOT NULL,
+        stars               SMALLINT    UNSIGNED    NOT NULL,
+        progress            INT         UNSIGNED    NOT NULL,
+        quality             INT         UNSIGNED    NOT NULL,
+        durability          SMALLINT    UNSIGNED    NOT NULL,
+        progress_divider    SMALLINT    UNSIGNED    NOT NULL,
+        quality_divider     SMALLINT    UNSIGNED    NOT NULL,
+        progress_modifier   SMALLINT    UNSIGNED    NOT NULL,
+        quality_modifier    SMALLINT    UNSIGNED    NOT NULL,
+        book_item_id        MEDIUMINT   UNSIGNED,
+        book                VARCHAR(100),
+        INDEX   ( level ),
+        INDEX   ( book )
     )"
 );
 
-const SQL_INSERT: &str = formatcp!("INSERT INTO {SQL_TABLE_NAME} (id, count, level, stars) ");
+const SQL_INSERT: &str = formatcp!(
+    "INSERT INTO {SQL_TABLE_NAME} (id, count, level, stars, progress, quality, durability,
+    progress_divider, quality_divider, progress_modifier, quality_modifier, book_item_id, book) "
+);
 
-const SQL_SELECT: &str =
-    formatcp!("SELECT id, count, level, stars FROM {SQL_TABLE_NAME} WHERE id IN");
+const SQL_SELECT: &str = formatcp!(
+    "SELECT id, count, level, stars, progress, quality, durability,
+    progress_divider, quality_divider, progress_modifier, quality_modifier, book_item_id, book
+    FROM {SQL_TABLE_NAME}
+    WHERE id IN"
+);
@@ -0,0 +1,103 @@
+use anyhow::Result;
+use const_format::formatcp;
+use sqlx::{mysql::MySqlRow, QueryBuilder, Row};
+
+use crate::{ItemDB, SavedQuery};
+
+use super::{impl_table, strip_whitespace, BIND_MAX};
+
+////////////////////////////////////////////////////////////
+
+impl_table!(SavedQueryTable);
+
+impl SavedQueryTable<'_> {
+    pub async fn all(&self) -> Result<Vec<SavedQuery>> {
+        let rows = sqlx::query(&strip_whitespace(SQL_SELECT_ALL))
+            .fetch_all(self.db)
+            .await?;
+        Ok(rows.iter().map(saved_query).collect())
+    }
+
+    /// Returns the saved queries named any of `names`, ignoring case.
+    pub async fn by_names(&self, names: &[String]) -> Result<Vec<SavedQuery>> {
+        let mut queries = Vec::new();
+        for names in names.chunks(BIND_MAX) {
+            let mut builder = QueryBuilder::new(strip_whitespace(SQL_SELECT_BY_NAME));
+            let mut separated = builder.push(" (").separated(", ");
+            for name in names {
+                separated.push_bind(name);
+            }
+            separated.push_unseparated(")");
+
+            let rows = builder.build().fetch_all(self.db).await?;
+            queries.extend(rows.iter().map(saved_query));
+        }
+        Ok(queries)
+    }
+
+    /// Inserts the saved query, replacing any with the same name.
+    pub async fn upsert(&self, query: &SavedQuery) -> Result<()> {
+        sqlx::query(&strip_whitespace(SQL_UPSERT))
+            .bind(&query.name)
+            .bind(&query.query)
+            .bind(query.count)
+            .bind(query.limit)
+            .bind(query.min_velocity)
+            .execute(self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes the saved query, returning whether it existed.
+    pub async fn delete(&self, name: &str) -> Result<bool> {
+        let result = sqlx::query(&strip_whitespace(SQL_DELETE))
+            .bind(name)
+            .execute(self.db)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+fn saved_query(row: &MySqlRow) -> SavedQuery {
+    SavedQuery {
+        name: row.get(0),
+        query: row.get(1),
+        count: row.get(2),
+        limit: row.get(3),
+        min_velocity: row.get(4),
+    }
+}
+
+////////////////////////////////////////////////////////////
+
+const SQL_TABLE_NAME: &str = "saved_queries";
+
+const SQL_CREATE: &str = formatcp!(
+    "CREATE TABLE IF NOT EXISTS {SQL_TABLE_NAME} (
+        name            VARCHAR(50)     PRIMARY KEY,
+        query           VARCHAR(1000)   NOT NULL,
+        count           INT             UNSIGNED,
+        result_limit    INT             UNSIGNED,
+        min_velocity    FLOAT
+    )"
+);
+
+const SQL_UPSERT: &str = formatcp!(
+    "INSERT INTO {SQL_TABLE_NAME} (name, query, count, result_limit, min_velocity)
+    VALUES (?, ?, ?, ?, ?)
+    ON DUPLICATE KEY UPDATE
+        query = VALUES(query),
+        count = VALUES(count),
+        result_limit = VALUES(result_limit),
+        min_velocity = VALUES(min_velocity)"
+);
+
+const SQL_SELECT_ALL: &str = formatcp!(
+    "SELECT name, query, count, result_limit, min_velocity FROM {SQL_TABLE_NAME} ORDER BY name"
+);
+
+const SQL_SELECT_BY_NAME: &str = formatcp!(
+    "SELECT name, query, count, result_limit, min_velocity FROM {SQL_TABLE_NAME} WHERE name IN"
+);
+
+const SQL_DELETE: &str = formatcp!("DELETE FROM {SQL_TABLE_NAME} WHERE name = ?");
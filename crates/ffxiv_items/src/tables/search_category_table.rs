@@ -0,0 +1,95 @@
+use std::{future::Future, io::Cursor};
+
+use anyhow::Result;
+use const_format::formatcp;
+use itertools::Itertools;
+use mock_traits::FileDownloader;
+use sqlx::QueryBuilder;
+
+use crate::{csv_parse, latest_commit_from_github, CommitInfo, ItemDB};
+
+use super::{
+    download_csv, impl_table, impl_table_builder, strip_whitespace, DatasetFilesTable, BIND_MAX,
+};
+
+////////////////////////////////////////////////////////////
+
+pub struct CsvSearchCategory {
+    pub id: u32,
+    pub name: String,
+}
+
+////////////////////////////////////////////////////////////
+
+const CSV_FILE: &str = "ItemSearchCategory.csv";
+
+impl_table!(SearchCategoryTable);
+impl_table_builder!(SearchCategoryTableBuilder, FileDownloader);
+
+impl<F: FileDownloader> SearchCategoryTableBuilder<'_, F> {
+    pub async fn initialize(&self) -> Result<()> {
+        let categories = self.download().await?;
+
+        println!("Initializing Search Categories Table");
+        let id_map = categories.iter();
+        for id_map in &id_map.chunks(BIND_MAX / 2) {
+            QueryBuilder::new(strip_whitespace(SQL_INSERT))
+                .push_values(id_map, |mut b, data| {
+                    b.push_bind(data.id).push_bind(&data.name);
+                })
+                .build()
+                .execute(self.db)
+                .await?;
+        }
+
+        DatasetFilesTable::new(self.db)
+            .mark_imported(CSV_FILE)
+            .await
+    }
+
+    pub async fn latest_commits(&self) -> Result<Vec<CommitInfo>> {
+        Ok(vec![
+            latest_commit_from_github::<F>(self.db, CSV_FILE).await?,
+        ])
+    }
+
+    async fn download(&self) -> Result<Vec<CsvSearchCategory>> {
+        println!("Downloading Search Categories from Github");
+        download_search_categories(|file| download_csv::<F>(self.db, file)).await
+    }
+}
+
+/// Parses the market board categories from their csv file, fetched with `download`.
+pub async fn download_search_categories<D, Fut>(download: D) -> Result<Vec<CsvSearchCategory>>
+where
+    D: Fn(&'static str) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let reader = Cursor::new(download(CSV_FILE).await?);
+    let mut categories = Vec::new();
+    csv_parse!(CSV_FILE, reader => {
+        id = U[0];
+        name = S[1];
+        if id == 0 || name.is_empty() {
+            continue;
+        }
+
+        categories.push(CsvSearchCategory { id, name: name.to_string() });
+    });
+
+    Ok(categories)
+}
+
+////////////////////////////////////////////////////////////
+
+const SQL_TABLE_NAME: &str = "search_categories";
+
+const SQL_CREATE: &str = formatcp!(
+    "CREATE TABLE IF NOT EXISTS {SQL_TABLE_NAME} (
+        id          SMALLINT        UNSIGNED    PRIMARY KEY,
+        name        VARCHAR(50)     NOT NULL,
+        INDEX       ( name )
+    )"
+);
+
+const SQL_INSERT: &str = formatcp!("INSERT INTO {SQL_TABLE_NAME} (id, name) ");
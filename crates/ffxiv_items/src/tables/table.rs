@@ -37,6 +37,17 @@ macro_rules! make_table_impl {
                 Ok(())
             }
 
+            #[allow(dead_code)]
+            pub async fn len(&self) -> Result<u64> {
+                let sql_count = format!("SELECT COUNT(*) FROM {SQL_TABLE_NAME}");
+                let count = sqlx::query_scalar::<_, i64>(&sql_count)
+                    .persistent(true)
+                    .fetch_one(&*self.db)
+                    .await?;
+                Ok(count as u64)
+            }
+
+            #[allow(dead_code)]
             pub async fn is_empty(&self) -> Result<bool> {
                 let sql_empty = format!("SELECT COUNT(*) FROM {SQL_TABLE_NAME}");
                 Ok(0 == sqlx::query_scalar::<_, i64>(&sql_empty)
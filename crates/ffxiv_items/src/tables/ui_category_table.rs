@@ -1,21 +1,22 @@
-use std::io::Cursor;
+use std::{future::Future, io::Cursor};
 
 use anyhow::Result;
-use chrono::{DateTime, FixedOffset};
 use const_format::formatcp;
 use itertools::Itertools;
 use mock_traits::FileDownloader;
 use sqlx::QueryBuilder;
 
-use crate::{csv_parse, last_updated_from_github, ItemDB};
+use crate::{csv_parse, latest_commit_from_github, CommitInfo, ItemDB};
 
-use super::{download_csv, impl_table, impl_table_builder, strip_whitespace, BIND_MAX};
+use super::{
+    download_csv, impl_table, impl_table_builder, strip_whitespace, DatasetFilesTable, BIND_MAX,
+};
 
 ////////////////////////////////////////////////////////////
 
-struct CsvUiCategory {
-    id: u32,
-    name: String,
+pub struct CsvUiCategory {
+    pub id: u32,
+    pub name: String,
 }
 
 ////////////////////////////////////////////////////////////
@@ -27,7 +28,7 @@ impl_table_builder!(UiCategoryTableBuilder, FileDownloader);
 
 impl<F: FileDownloader> UiCategoryTableBuilder<'_, F> {
     pub async fn initialize(&self) -> Result<()> {
-        let categories = Self::download().await?;
+        let categories = self.download().await?;
 
         println!("Initializing UI Categories Table");
         let id_map = categories.iter();
@@ -41,30 +42,42 @@ impl<F: FileDownloader> UiCategoryTableBuilder<'_, F> {
                 .await?;
         }
 
-        Ok(())
+        DatasetFilesTable::new(self.db)
+            .mark_imported(CSV_FILE)
+            .await
     }
 
-    pub async fn last_updated_github(&self) -> Result<DateTime<FixedOffset>> {
-        last_updated_from_github::<F>(CSV_FILE).await
+    pub async fn latest_commits(&self) -> Result<Vec<CommitInfo>> {
+        Ok(vec![
+            latest_commit_from_github::<F>(self.db, CSV_FILE).await?,
+        ])
     }
 
-    async fn download() -> Result<Vec<CsvUiCategory>> {
+    async fn download(&self) -> Result<Vec<CsvUiCategory>> {
         println!("Downloading UI Categories from Github");
+        download_ui_categories(|file| download_csv::<F>(self.db, file)).await
+    }
+}
 
-        let reader = Cursor::new(download_csv::<F>(CSV_FILE).await?);
-        let mut categories = Vec::new();
-        csv_parse!(reader => {
-            id = U[0];
-            name = S[1];
-            if id == 0 || name.is_empty() {
-                continue;
-            }
+/// Parses the UI categories from their csv file, fetched with `download`.
+pub async fn download_ui_categories<D, Fut>(download: D) -> Result<Vec<CsvUiCategory>>
+where
+    D: Fn(&'static str) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let reader = Cursor::new(download(CSV_FILE).await?);
+    let mut categories = Vec::new();
+    csv_parse!(CSV_FILE, reader => {
+        id = U[0];
+        name = S[1];
+        if id == 0 || name.is_empty() {
+            continue;
+        }
 
-            categories.push(CsvUiCategory { id, name: name.to_string() });
-        });
+        categories.push(CsvUiCategory { id, name: name.to_string() });
+    });
 
-        Ok(categories)
-    }
+    Ok(categories)
 }
 
 ////////////////////////////////////////////////////////////
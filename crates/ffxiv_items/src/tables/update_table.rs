@@ -29,26 +29,27 @@ impl UpdateTable<'_> {
 
     pub async fn insert(&self, last_updated: &DateTime<FixedOffset>) -> Result<()> {
         println!("Initializing Update Table");
-        let last_updated = vec![last_updated.to_rfc3339()];
+        let dates = vec![last_updated.to_rfc3339()];
         QueryBuilder::new(strip_whitespace(SQL_INSERT))
-            .push_values(last_updated, |mut b, item| {
+            .push_values(dates, |mut b, item| {
                 b.push_bind(item);
             })
             .build()
             .execute(self.db)
             .await?;
+        self.db.cache.clear_if_updated(Some(*last_updated));
 
         Ok(())
     }
 
     pub async fn update(&self, last_updated: &DateTime<FixedOffset>) -> Result<()> {
         println!("Updating Update Table");
-        let last_updated = last_updated.to_rfc3339();
         QueryBuilder::new(strip_whitespace(SQL_UPDATE))
-            .push_bind(last_updated.to_string())
+            .push_bind(last_updated.to_rfc3339())
             .build()
             .execute(self.db)
             .await?;
+        self.db.cache.clear_if_updated(Some(*last_updated));
 
         Ok(())
     }
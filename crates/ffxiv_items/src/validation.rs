@@ -0,0 +1,262 @@
+use std::fmt::{self, Display, Formatter};
+
+use anyhow::Result;
+use futures::{future::try_join_all, TryStreamExt};
+use itertools::Itertools;
+use sqlx::Row;
+
+use crate::{
+    tables::{
+        strip_whitespace, CollectableRewardTable, CompanyCraftSupplyTable, CompanyCraftTable,
+        FoodEffectTable, GcSupplyDutyTable, GcSupplyRewardTable, IngredientTable, InputIdsTable,
+        ItemInfoTable, ItemStatTable, MateriaJoinRateTable, MateriaTable, RecipeTable,
+        SearchCategoryTable, UiCategoryTable,
+    },
+    ItemDB, ItemDBError,
+};
+
+/// The results of checking the item database for missing or inconsistent data.
+#[derive(Clone, Debug, Default)]
+pub struct ValidationReport {
+    /// The number of rows in each of the checked tables.
+    pub row_counts: Vec<(&'static str, u64)>,
+    /// Every problem found. The database is considered valid if this is empty.
+    pub issues: Vec<ValidationIssue>,
+}
+
+/// A single problem found while validating the item database.
+#[derive(Clone, Debug)]
+pub enum ValidationIssue {
+    /// A table has fewer rows than any complete import would produce.
+    TooFewRows {
+        /// The table with too few rows.
+        table: &'static str,
+        /// The number of rows in the table.
+        rows: u64,
+        /// The minimum number of rows expected.
+        minimum: u64,
+    },
+    /// Rows in a table reference ids that don't exist in another table.
+    DanglingReference {
+        /// The table holding the reference.
+        table: &'static str,
+        /// The referencing column.
+        column: &'static str,
+        /// The table that should contain the referenced ids.
+        references: &'static str,
+        /// The number of distinct ids that are missing.
+        count: usize,
+        /// A few of the missing ids, for diagnostics.
+        sample: Vec<u32>,
+    },
+}
+
+struct Reference {
+    table: &'static str,
+    column: &'static str,
+    references: &'static str,
+}
+
+////////////////////////////////////////////////////////////
+
+// Comfortably below the size of any real import, but enough to catch a file
+// that failed to download or parse.
+const MIN_ROWS: [(&str, u64); 15] = [
+    (ItemInfoTable::SQL_TABLE_NAME, 10_000),
+    (UiCategoryTable::SQL_TABLE_NAME, 50),
+    (SearchCategoryTable::SQL_TABLE_NAME, 50),
+    (RecipeTable::SQL_TABLE_NAME, 1_000),
+    (IngredientTable::SQL_TABLE_NAME, 1_000),
+    (InputIdsTable::SQL_TABLE_NAME, 1_000),
+    (CompanyCraftTable::SQL_TABLE_NAME, 10),
+    (CompanyCraftSupplyTable::SQL_TABLE_NAME, 100),
+    (GcSupplyDutyTable::SQL_TABLE_NAME, 100),
+    (GcSupplyRewardTable::SQL_TABLE_NAME, 100),
+    (CollectableRewardTable::SQL_TABLE_NAME, 100),
+    (ItemStatTable::SQL_TABLE_NAME, 10_000),
+    (FoodEffectTable::SQL_TABLE_NAME, 100),
+    (MateriaTable::SQL_TABLE_NAME, 100),
+    (MateriaJoinRateTable::SQL_TABLE_NAME, 10),
+];
+
+// Every reference is to an `id` column
+const REFERENCES: [Reference; 13] = [
+    Reference {
+        table: RecipeTable::SQL_TABLE_NAME,
+        column: "id",
+        references: ItemInfoTable::SQL_TABLE_NAME,
+    },
+    Reference {
+        table: IngredientTable::SQL_TABLE_NAME,
+        column: "item_id",
+        references: RecipeTable::SQL_TABLE_NAME,
+    },
+    Reference {
+        table: IngredientTable::SQL_TABLE_NAME,
+        column: "input_id",
+        references: ItemInfoTable::SQL_TABLE_NAME,
+    },
+    Reference {
+        table: InputIdsTable::SQL_TABLE_NAME,
+        column: "item_id",
+        references: RecipeTable::SQL_TABLE_NAME,
+    },
+    Reference {
+        table: InputIdsTable::SQL_TABLE_NAME,
+        column: "input_id",
+        references: ItemInfoTable::SQL_TABLE_NAME,
+    },
+    Reference {
+        table: CompanyCraftTable::SQL_TABLE_NAME,
+        column: "id",
+        references: RecipeTable::SQL_TABLE_NAME,
+    },
+    Reference {
+        table: CompanyCraftSupplyTable::SQL_TABLE_NAME,
+        column: "item_id",
+        references: CompanyCraftTable::SQL_TABLE_NAME,
+    },
+    Reference {
+        table: CompanyCraftSupplyTable::SQL_TABLE_NAME,
+        column: "input_id",
+        references: ItemInfoTable::SQL_TABLE_NAME,
+    },
+    Reference {
+        table: GcSupplyDutyTable::SQL_TABLE_NAME,
+        column: "item_id",
+        references: ItemInfoTable::SQL_TABLE_NAME,
+    },
+    Reference {
+        table: CollectableRewardTable::SQL_TABLE_NAME,
+        column: "item_id",
+        references: ItemInfoTable::SQL_TABLE_NAME,
+    },
+    Reference {
+        table: ItemStatTable::SQL_TABLE_NAME,
+        column: "item_id",
+        references: ItemInfoTable::SQL_TABLE_NAME,
+    },
+    Reference {
+        table: FoodEffectTable::SQL_TABLE_NAME,
+        column: "item_id",
+        references: ItemInfoTable::SQL_TABLE_NAME,
+    },
+    Reference {
+        table: MateriaTable::SQL_TABLE_NAME,
+        column: "id",
+        references: ItemInfoTable::SQL_TABLE_NAME,
+    },
+];
+
+const MAX_SAMPLE: usize = 10;
+
+////////////////////////////////////////////////////////////
+
+impl ItemDB {
+    /// Checks the item tables for missing rows & references between the
+    /// recipe, ingredient, input id, company craft and item tables that don't
+    /// resolve.
+    pub async fn validate(&self) -> Result<ValidationReport, ItemDBError> {
+        let mut report = ValidationReport::default();
+
+        // Counted by name, so that each minimum is checked against its own table
+        let row_counts = try_join_all(MIN_ROWS.map(|(table, _)| self.row_count(table))).await?;
+        for ((table, minimum), rows) in MIN_ROWS.into_iter().zip(row_counts) {
+            report.row_counts.push((table, rows));
+            if rows < minimum {
+                report.issues.push(ValidationIssue::TooFewRows {
+                    table,
+                    rows,
+                    minimum,
+                });
+            }
+        }
+
+        for reference in REFERENCES {
+            let missing = self.dangling_ids(&reference).await?;
+            if !missing.is_empty() {
+                report.issues.push(ValidationIssue::DanglingReference {
+                    table: reference.table,
+                    column: reference.column,
+                    references: reference.references,
+                    count: missing.len(),
+                    sample: missing.into_iter().take(MAX_SAMPLE).collect(),
+                });
+            }
+        }
+
+        Ok(report)
+    }
+
+    async fn row_count(&self, table: &str) -> Result<u64> {
+        let count = sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM {table}"))
+            .fetch_one(self)
+            .await?;
+        Ok(count as u64)
+    }
+
+    async fn dangling_ids(&self, reference: &Reference) -> Result<Vec<u32>> {
+        let Reference {
+            table,
+            column,
+            references,
+        } = reference;
+        let query_string = strip_whitespace(format!(
+            "SELECT DISTINCT t.{column} FROM {table} AS t
+            LEFT JOIN {references} AS r ON r.id = t.{column}
+            WHERE r.id IS NULL
+            ORDER BY t.{column}"
+        ));
+
+        let mut ids = Vec::new();
+        let mut sql_query = sqlx::query(&query_string).fetch(self);
+        while let Some(row) = sql_query.try_next().await? {
+            ids.push(row.get::<u32, _>(0));
+        }
+        Ok(ids)
+    }
+}
+
+impl ValidationReport {
+    /// Whether the database passed validation.
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let row_counts = self
+            .row_counts
+            .iter()
+            .map(|(table, rows)| format!("{table}: {rows}"))
+            .join(", ");
+        write!(f, "Row counts ({row_counts})")?;
+        for issue in &self.issues {
+            write!(f, "\n  {issue}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for ValidationIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooFewRows {
+                table,
+                rows,
+                minimum,
+            } => write!(f, "{table} has {rows} rows, expected at least {minimum}"),
+            Self::DanglingReference {
+                table,
+                column,
+                references,
+                count,
+                sample,
+            } => write!(
+                f,
+                "{table}.{column} has {count} ids missing from {references}, e.g. {sample:?}"
+            ),
+        }
+    }
+}
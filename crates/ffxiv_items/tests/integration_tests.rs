@@ -3,11 +3,164 @@ mod docker {
     use std::collections::HashSet;
 
     use anyhow::Result;
-    use ffxiv_items::ItemDB;
+    use ffxiv_items::{
+        GcSupplyKind, ItemDB, ItemDBError, ItemRepository, MemoryItemRepository, RecipeKind,
+        Resolution, RewardSource, SavedQuery, SavedQueryError,
+    };
+    use mock_traits::ReqwestDownloader;
 
     async fn database() -> Result<ItemDB> {
         let item_db_conn = std::env::var("FFXIV_ITEM_DB_CONN").unwrap();
-        ItemDB::connect(item_db_conn).await
+        Ok(ItemDB::connect(item_db_conn).await?)
+    }
+
+    #[tokio::test]
+    async fn test_dataset_info() -> Result<()> {
+        let db = database().await?;
+        let info = db.dataset_info().await?;
+        assert!(!info.files.is_empty());
+        for file in info.files {
+            assert!(file.imported, "{} wasn't imported", file.file_name);
+            assert!(file.row_count.unwrap_or_default() > 0);
+            assert_eq!(file.content_hash.map(|hash| hash.len()), Some(64));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_validate() -> Result<()> {
+        let db = database().await?;
+        let report = db.validate().await?;
+        assert!(report.is_valid(), "{report}");
+        assert!(report.row_counts.iter().all(|(_, rows)| *rows > 0));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_export_snapshot() -> Result<()> {
+        let db = database().await?;
+        let mut snapshot = Vec::new();
+        db.export_snapshot(&mut snapshot).await?;
+
+        let snapshot = String::from_utf8(snapshot)?;
+        let mut lines = snapshot.lines();
+        assert_eq!(
+            lines.next(),
+            Some(r#"{"format":"ffxiv_items_snapshot","version":11}"#)
+        );
+        let tables = lines
+            .filter(|line| line.starts_with('{'))
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["table"].clone())
+            .collect::<Vec<_>>();
+        for table in ["items", "recipes", "ingredients", "input_ids"] {
+            assert!(
+                tables.contains(&table.into()),
+                "{table} missing from snapshot"
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_import_bad_snapshot() -> Result<()> {
+        let db = database().await?;
+        let mut snapshot = Vec::new();
+        db.export_snapshot(&mut snapshot).await?;
+
+        // A snapshot cut off partway through its tables fails validation
+        let truncated = snapshot
+            .split(|&byte| byte == b'\n')
+            .take(5)
+            .collect::<Vec<_>>();
+        assert!(db
+            .import_snapshot(truncated.join(&b'\n').as_slice())
+            .await
+            .is_err());
+        let corrupt = br#"{"format":"ffxiv_items_snapshot","version":11}
+{"table":"items","columns":["id"]}
+not json"#;
+        assert!(db.import_snapshot(corrupt.as_slice()).await.is_err());
+
+        // A new connection, so that nothing is served from the cache
+        let db = database().await?;
+        let items = db.items_from_ids(&[5358]).await?;
+        assert_eq!(items[0].name, "Eagle Feather");
+        assert!(!db.ids_from_query(":name ^Maple").await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_items_from_many_ids() -> Result<()> {
+        let db = database().await?;
+        let ids = db.ids_from_query(":name e").await?;
+        assert!(ids.len() > 1000);
+
+        // Ids are looked up in chunks, and duplicates are ignored
+        let doubled_ids = ids.iter().chain(&ids).collect::<Vec<_>>();
+        let items = db.items_from_ids(&doubled_ids).await?;
+        let item_ids = items.iter().map(|item| item.id).collect::<Vec<_>>();
+        assert_eq!(item_ids, ids);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_saved_query_alias() -> Result<()> {
+        let db = database().await?;
+        let saved = SavedQuery {
+            name: "test_feathers".into(),
+            query: ":name Feather; :name Branch".into(),
+            count: Some(20),
+            limit: None,
+            min_velocity: Some(5.0),
+        };
+        db.save_query(&saved).await?;
+        assert_eq!(db.saved_query("TEST_FEATHERS").await?, Some(saved.clone()));
+        assert!(db.saved_queries().await?.contains(&saved));
+
+        let ids = db
+            .ids_from_query("@Test_Feathers, :name !Eagle Feather|Maple Branch")
+            .await?;
+        // Eagle Feather, Maple Branch
+        assert_eq!(ids, vec![5358, 5396]);
+
+        // Changing the saved query isn't hidden by the query cache
+        let saved = SavedQuery {
+            query: ":name Feather".into(),
+            ..saved
+        };
+        db.save_query(&saved).await?;
+        let ids = db
+            .ids_from_query("@Test_Feathers, :name !Eagle Feather|Maple Branch")
+            .await?;
+        assert_eq!(ids, vec![5358]);
+
+        let nested = SavedQuery {
+            name: "test_nested".into(),
+            query: "@test_feathers".into(),
+            ..saved.clone()
+        };
+        let err = db.save_query(&nested).await.unwrap_err();
+        assert!(matches!(
+            err,
+            ItemDBError::SavedQuery(SavedQueryError::NestedAlias(_))
+        ));
+
+        let invalid = SavedQuery {
+            name: "test_invalid".into(),
+            query: ":name Eagle (Feather".into(),
+            ..saved.clone()
+        };
+        let err = db.save_query(&invalid).await.unwrap_err();
+        assert!(matches!(err, ItemDBError::Query { offset: 0, .. }));
+
+        assert!(db.delete_saved_query("test_feathers").await?);
+        assert!(!db.delete_saved_query("test_feathers").await?);
+        let err = db.ids_from_query("@test_feathers").await.unwrap_err();
+        assert_eq!(
+            err,
+            ItemDBError::SavedQuery(SavedQueryError::UnknownAlias("test_feathers".into()))
+        );
+        Ok(())
     }
 
     #[tokio::test]
@@ -103,6 +256,25 @@ mod docker {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_recipe_difficulty() -> Result<()> {
+        let db = database().await?;
+        let ids = db.ids_from_query(":rlevel 90, :name Mind Alkahest").await?;
+        let items = db.items_from_ids(&ids).await?;
+        assert!(!items.is_empty());
+
+        for item in items {
+            let difficulty = item.recipe.unwrap().difficulty;
+            assert!(difficulty.progress > 0);
+            assert!(difficulty.quality > 0);
+            assert!(difficulty.durability > 0);
+            assert!(difficulty.progress_divider > 0);
+            assert!(difficulty.quality_divider > 0);
+        }
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_filter_rlevel_range() -> Result<()> {
         let db = database().await?;
@@ -254,6 +426,250 @@ mod docker {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_filter_slot_class() -> Result<()> {
+        let db = database().await?;
+        let main_hands = db.ids_from_query(":elevel 90, :slot main hand").await?;
+        let tools = db
+            .ids_from_query(":elevel 90, :slot main hand, :class DoH")
+            .await?;
+        let saws = db
+            .ids_from_query(":elevel 90, :slot main hand, :class CRP")
+            .await?;
+        assert!(!saws.is_empty());
+        assert!(tools.len() < main_hands.len());
+
+        // Job groups match every job in them
+        let tools = tools.into_iter().collect::<HashSet<_>>();
+        assert!(saws.iter().all(|id| tools.contains(id)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_filter_book() -> Result<()> {
+        let db = database().await?;
+        let ids = db
+            .ids_from_query(":rlevel 90, :book Master Culinarian")
+            .await?;
+        assert!(!ids.is_empty());
+        for item in db.items_from_ids(&ids).await? {
+            let book = item.recipe.unwrap().book.unwrap();
+            assert!(book.name.starts_with("Master Culinarian"));
+            assert_ne!(book.item_id, 0);
+        }
+
+        let without_books = db.ids_from_query(":rlevel 90, :book none").await?;
+        assert!(!without_books.is_empty());
+        for item in db.items_from_ids(&without_books).await? {
+            assert!(item.recipe.unwrap().book.is_none());
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_filter_id() -> Result<()> {
+        let db = database().await?;
+        let ids = db.ids_from_query(":id 5358|5396").await?;
+        // Eagle Feather, Maple Branch
+        assert_eq!(ids, vec![5358, 5396]);
+
+        let ids = db.ids_from_query(":id 5358|5396, :name maple").await?;
+        assert_eq!(ids, vec![5396]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_names() -> Result<()> {
+        let db = database().await?;
+        let resolved = db
+            .resolve_names(&["eagle feather", " Maple Branch ", "Eagle", "Not An Item"])
+            .await?;
+        assert_eq!(
+            resolved,
+            vec![
+                ("eagle feather".to_string(), Resolution::Unique(5358)),
+                ("Maple Branch".to_string(), Resolution::Unique(5396)),
+                ("Eagle".to_string(), Resolution::NotFound),
+                ("Not An Item".to_string(), Resolution::NotFound),
+            ]
+        );
+        assert!(db.resolve_names(&[]).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_filter_market_category() -> Result<()> {
+        let db = database().await?;
+        let furnishings = db.ids_from_query(":cat !Furnishing").await?;
+        let marketable = db.ids_from_query(":cat !Furnishing, :mbcat").await?;
+        assert!(!marketable.is_empty());
+        assert!(marketable.len() < furnishings.len());
+        for item in db.items_from_ids(&furnishings).await? {
+            assert_eq!(item.is_marketable, marketable.contains(&item.id));
+        }
+
+        let ids = db.ids_from_query(":rlevel 90, :mbcat lumber").await?;
+        assert!(!ids.is_empty());
+        assert!(db
+            .items_from_ids(&ids)
+            .await?
+            .iter()
+            .all(|item| item.is_marketable));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_filter_stat() -> Result<()> {
+        let db = database().await?;
+        let ids = db
+            .ids_from_query(":elevel 90, :slot main hand, :stat Control>=100")
+            .await?;
+        assert!(!ids.is_empty());
+        for item in db.items_from_ids(&ids).await? {
+            assert!(item
+                .stats
+                .iter()
+                .any(|(stat, value)| stat == "Control" && *value >= 100));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_filter_food() -> Result<()> {
+        let db = database().await?;
+        let ids = db.ids_from_query(":food !CP").await?;
+        assert!(!ids.is_empty());
+        for item in db.items_from_ids(&ids).await? {
+            let food = item.food.unwrap();
+            let effect = food
+                .effects
+                .iter()
+                .find(|effect| effect.stat == "CP")
+                .unwrap();
+            // High quality food raises stats by at least as much
+            assert!(effect.raise(500, true) >= effect.raise(500, false));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_meld_plan() -> Result<()> {
+        let db = database().await?;
+        let materia_ids = db
+            .ids_from_query(":name ^Craftsman's Command Materia")
+            .await?;
+        let materia = db.materia_by_item_ids(&materia_ids).await?;
+        assert_eq!(materia.len(), materia_ids.len());
+        assert!(materia.iter().all(|materia| materia.stat == "Control"));
+
+        // Crafting tools take 2 guaranteed melds & 3 overmelds
+        let lowest = materia.iter().min_by_key(|materia| materia.grade).unwrap();
+        let tool = db
+            .ids_from_query(":elevel 90, :slot main hand, :class CRP")
+            .await?[0];
+        let plan = db.meld_plan(tool, &[lowest.item_id; 5], true).await?;
+        assert_eq!(plan.melds.len(), 5);
+        for meld in &plan.melds[..plan.slots as usize] {
+            assert!(!meld.is_overmeld);
+            assert_eq!(meld.expected_count, 1.0);
+        }
+        assert!(plan.melds[plan.slots as usize..]
+            .iter()
+            .all(|meld| meld.is_overmeld && meld.expected_count >= 1.0));
+        assert!(db
+            .meld_plan(tool, &[lowest.item_id; 6], true)
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_filter_workshop() -> Result<()> {
+        let db = database().await?;
+        let all_ids = db.ids_from_query(":workshop").await?;
+        let ids = db.ids_from_query(":workshop submersible").await?;
+        assert!(!ids.is_empty());
+        assert!(ids.len() < all_ids.len());
+
+        // Workshop projects are completed in phases, whose supplies add up
+        // to the recipe's inputs
+        let items = db.items_from_ids(&ids).await?;
+        for item in items {
+            let recipe = item.recipe.unwrap();
+            assert_eq!(recipe.kind, RecipeKind::CompanyWorkshop);
+            assert!(!recipe.phases.is_empty());
+            let supplied = recipe
+                .phases
+                .iter()
+                .flat_map(|phase| &phase.supplies)
+                .map(|supply| supply.count())
+                .sum::<u32>();
+            let inputs = recipe.inputs.iter().map(|input| input.count).sum::<u32>();
+            assert_eq!(supplied, inputs);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_gc_supply_duties() -> Result<()> {
+        let db = database().await?;
+        let duties = db.gc_supply_duties(Some("cul")).await?;
+        assert!(!duties.is_empty());
+        for duty in &duties {
+            assert_eq!(duty.job, "CUL");
+            assert_eq!(duty.kind, GcSupplyKind::Supply);
+            assert!(duty.count > 0);
+            assert!(duty.seals > 0);
+        }
+
+        // The query tag matches the same items
+        let ids = db.ids_from_query(":gcsupply CUL").await?;
+        let duty_ids = duties
+            .iter()
+            .map(|duty| duty.item_id)
+            .collect::<HashSet<_>>();
+        assert_eq!(ids.into_iter().collect::<HashSet<_>>(), duty_ids);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_collectable_rewards() -> Result<()> {
+        let db = database().await?;
+        let rewards = db
+            .collectable_rewards(RewardSource::IshgardRestoration)
+            .await?;
+        assert!(!rewards.is_empty());
+        for reward in &rewards {
+            assert!(!reward.tiers.is_empty());
+            assert!(reward
+                .tiers
+                .iter()
+                .all(|tier| tier.currency == "Skybuilders' Scrip"));
+        }
+
+        // The query tag matches the same items
+        let ids = db.ids_from_query(":ishgard").await?;
+        let reward_ids = rewards
+            .iter()
+            .map(|reward| reward.item_id)
+            .collect::<HashSet<_>>();
+        assert_eq!(ids.into_iter().collect::<HashSet<_>>(), reward_ids);
+
+        // Collectables appraiser tiers reward more scrips as collectability rises
+        let shop = db
+            .collectable_rewards(RewardSource::CollectablesShop)
+            .await?;
+        assert!(!shop.is_empty());
+        for reward in &shop {
+            assert!(reward
+                .tiers
+                .windows(2)
+                .all(|tiers| tiers[0].collectability < tiers[1].collectability
+                    && tiers[0].scrips <= tiers[1].scrips));
+        }
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_filter_or_clauses_ilevel() -> Result<()> {
         let db = database().await?;
@@ -302,4 +718,28 @@ mod docker {
         assert_eq!(ids, vec![5081, 5091, 5396, 27795, 29967, 32943]);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_memory_repository() -> Result<()> {
+        let db = database().await?;
+        let memory = MemoryItemRepository::load::<ReqwestDownloader>().await?;
+        for query in [
+            ":name !(maple branch|eagle feather); :includes !maple branch",
+            ":cat Metal|Lumber, :ilevel 2; :rlevel 80, :name Mind Alkahest",
+            ":name ^Rarefied, :rlevel 61|69",
+            ":class CUL, :elevel 90, :slot Body",
+            ":stat Control>=300, :mbcat",
+            ":food CP, :book none",
+            ":workshop Submersible, :contains Ceruleum",
+        ] {
+            let ids = db.ids_from_query(query).await?;
+            assert_eq!(memory.ids_from_query(query).await?, ids, "{query}");
+            assert_eq!(
+                memory.associated_ids(&ids).await?,
+                db.associated_ids(&ids).await?,
+                "{query}"
+            );
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,21 @@
+use axum::http::{header::AUTHORIZATION, HeaderMap, StatusCode};
+
+////////////////////////////////////////////////////////////
+
+/// Admin routes are only enabled when `FFXIV_ADMIN_TOKEN` is set, and callers
+/// must pass it as an `Authorization: Bearer <token>` header.
+pub fn check_admin(headers: &HeaderMap) -> Result<(), (StatusCode, &'static str)> {
+    let Ok(token) = std::env::var("FFXIV_ADMIN_TOKEN") else {
+        return Err((StatusCode::FORBIDDEN, "Admin routes are disabled"));
+    };
+
+    let bearer = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match bearer {
+        Some(bearer) if !token.is_empty() && bearer == token => Ok(()),
+        _ => Err((StatusCode::UNAUTHORIZED, "Invalid admin token")),
+    }
+}
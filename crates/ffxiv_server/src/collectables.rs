@@ -0,0 +1,227 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use ffxiv_items::{ItemDB, RewardSource};
+use ffxiv_universalis::Processor;
+use mock_traits::FileDownloader;
+use serde::{Deserialize, Serialize};
+
+use crate::market::{fetch_listings, purchase_cost};
+
+////////////////////////////////////////////////////////////
+
+#[derive(Deserialize)]
+pub struct CollectablesParams {
+    source: SourceParam,
+}
+
+#[derive(Deserialize)]
+pub struct ScripRankingParams {
+    /// The world, data center or region to buy the ingredients from.
+    world: String,
+    /// Only ranks collectables turned in here. Every source is ranked if this
+    /// isn't set.
+    source: Option<SourceParam>,
+    /// Only ranks collectables rewarding a scrip whose name contains this,
+    /// e.g. `purple crafters`.
+    currency: Option<String>,
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SourceParam {
+    CustomDelivery,
+    IshgardRestoration,
+    CollectablesShop,
+}
+
+const SOURCES: [SourceParam; 3] = [
+    SourceParam::CustomDelivery,
+    SourceParam::IshgardRestoration,
+    SourceParam::CollectablesShop,
+];
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectableReward {
+    pub item_id: u32,
+    pub source: SourceParam,
+    pub tiers: Vec<RewardTier>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RewardTier {
+    pub collectability: u32,
+    pub currency: String,
+    pub scrips: u32,
+    pub experience: u32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScripRanking {
+    pub item_id: u32,
+    pub name: String,
+    pub source: SourceParam,
+    pub currency: String,
+    /// The scrips rewarded for the highest collectability tier.
+    pub scrips: u32,
+    /// The cost of buying the recipe's ingredients from the marketboard, if
+    /// enough of each are listed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gil_per_scrip: Option<f32>,
+}
+
+////////////////////////////////////////////////////////////
+
+pub async fn collectable_rewards(
+    State(db): State<Arc<ItemDB>>,
+    Query(params): Query<CollectablesParams>,
+) -> Result<Json<Vec<CollectableReward>>, (StatusCode, String)> {
+    match db.collectable_rewards(params.source.into()).await {
+        Ok(rewards) => Ok(Json(rewards.into_iter().map(Into::into).collect())),
+        Err(err) => {
+            log::error!(target: "ffxiv_server", "Couldn't fetch collectable rewards: {err}");
+            Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+        }
+    }
+}
+
+/// Ranks craftable collectables by the gil spent on their ingredients per
+/// scrip earned at the highest collectability tier, cheapest first.
+/// Collectables whose ingredients can't all be bought are ranked last.
+pub async fn scrip_ranking<F: FileDownloader>(
+    State((processor, db)): State<(Processor, Arc<ItemDB>)>,
+    Query(params): Query<ScripRankingParams>,
+) -> Result<Json<Vec<ScripRanking>>, (StatusCode, String)> {
+    let rankings = async {
+        let sources = params
+            .source
+            .map_or(SOURCES.to_vec(), |source| vec![source]);
+        let currency = params.currency.as_deref().map(str::to_lowercase);
+        let mut rewards = Vec::new();
+        for source in sources {
+            rewards.extend(db.collectable_rewards(source.into()).await?);
+        }
+
+        // Only the highest tier is ranked, since that's what crafters aim for
+        let rewards = rewards
+            .into_iter()
+            .filter_map(|reward| {
+                let tier = reward.tiers.last()?.clone();
+                let matches = match &currency {
+                    None => true,
+                    Some(currency) => tier.currency.to_lowercase().contains(currency),
+                };
+                matches.then_some((reward.item_id, reward.source, tier))
+            })
+            .collect::<Vec<_>>();
+
+        let reward_ids = rewards.iter().map(|(id, ..)| *id).collect::<Vec<_>>();
+        let items = db
+            .items_from_ids(&reward_ids)
+            .await?
+            .into_iter()
+            .filter_map(|item| Some((item.id, (item.name, item.recipe?))))
+            .collect::<BTreeMap<_, _>>();
+        let input_ids = items
+            .values()
+            .flat_map(|(_, recipe)| recipe.inputs.iter().map(|input| input.item_id))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        let listings = fetch_listings::<F>(&processor, &db, &input_ids, &params.world).await;
+
+        let mut rankings = rewards
+            .into_iter()
+            .filter_map(|(item_id, source, tier)| {
+                let (name, recipe) = items.get(&item_id)?;
+                let cost = recipe.inputs.iter().try_fold(0u32, |total, input| {
+                    listings
+                        .get(&input.item_id)
+                        .and_then(|listings| purchase_cost(listings, input.count))
+                        .and_then(|cost| total.checked_add(cost))
+                });
+                #[allow(clippy::cast_precision_loss)]
+                let gil_per_scrip = cost
+                    .filter(|_| tier.scrips > 0)
+                    .map(|cost| cost as f32 / tier.scrips as f32);
+                Some(ScripRanking {
+                    item_id,
+                    name: name.clone(),
+                    source: source.into(),
+                    currency: tier.currency,
+                    scrips: tier.scrips,
+                    cost,
+                    gil_per_scrip,
+                })
+            })
+            .collect::<Vec<_>>();
+        rankings.sort_by(|a, b| match (a.gil_per_scrip, b.gil_per_scrip) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        });
+        Ok::<_, anyhow::Error>(rankings)
+    };
+
+    match rankings.await {
+        Ok(rankings) => Ok(Json(rankings)),
+        Err(err) => {
+            log::error!(target: "ffxiv_server", "Couldn't rank collectables: {err}");
+            Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////
+
+impl From<SourceParam> for RewardSource {
+    fn from(source: SourceParam) -> Self {
+        match source {
+            SourceParam::CustomDelivery => Self::CustomDelivery,
+            SourceParam::IshgardRestoration => Self::IshgardRestoration,
+            SourceParam::CollectablesShop => Self::CollectablesShop,
+        }
+    }
+}
+
+impl From<RewardSource> for SourceParam {
+    fn from(source: RewardSource) -> Self {
+        match source {
+            RewardSource::CustomDelivery => Self::CustomDelivery,
+            RewardSource::IshgardRestoration => Self::IshgardRestoration,
+            RewardSource::CollectablesShop => Self::CollectablesShop,
+        }
+    }
+}
+
+impl From<ffxiv_items::CollectableReward> for CollectableReward {
+    fn from(reward: ffxiv_items::CollectableReward) -> Self {
+        Self {
+            item_id: reward.item_id,
+            source: reward.source.into(),
+            tiers: reward.tiers.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ffxiv_items::RewardTier> for RewardTier {
+    fn from(tier: ffxiv_items::RewardTier) -> Self {
+        Self {
+            collectability: tier.collectability,
+            currency: tier.currency,
+            scrips: tier.scrips,
+            experience: tier.experience,
+        }
+    }
+}
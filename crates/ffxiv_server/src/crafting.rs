@@ -0,0 +1,266 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use ffxiv_crafting::{
+    Action, CraftRecipe, CraftState, CrafterStats, Simulator, Solver, TrainedPerfection,
+};
+use ffxiv_items::{ItemDB, Recipe};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+
+////////////////////////////////////////////////////////////
+
+#[derive(Deserialize)]
+pub struct SimulateParams {
+    /// The id of the item the recipe crafts.
+    recipe: u32,
+    level: u32,
+    craftsmanship: u32,
+    control: u32,
+    cp: u32,
+    /// Comma separated action names, in the order they're used.
+    actions: String,
+}
+
+#[derive(Deserialize)]
+pub struct SolveParams {
+    /// The id of the item the recipe crafts.
+    recipe: u32,
+    level: u32,
+    craftsmanship: u32,
+    control: u32,
+    cp: u32,
+    /// The milliseconds to spend searching for a rotation.
+    budget: Option<u64>,
+}
+
+/// The time spent searching for a rotation when no budget is given.
+const DEFAULT_BUDGET_MS: u64 = 2_000;
+/// The most time a single request may spend searching.
+const MAX_BUDGET_MS: u64 = 10_000;
+/// Each solve keeps a blocking thread busy for up to its budget, so only this
+/// many run at once, and requests beyond it are turned away.
+pub const MAX_CONCURRENT_SOLVES: usize = 4;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Simulation {
+    pub item_id: u32,
+    pub progress: u32,
+    pub quality: u32,
+    pub durability: u32,
+    pub cp: u32,
+    pub steps: Vec<Step>,
+    pub is_complete: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Step {
+    pub action: String,
+    pub progress: u32,
+    pub quality: u32,
+    pub durability: u32,
+    pub cp: u32,
+    pub buffs: Buffs,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rotation {
+    #[serde(flatten)]
+    pub simulation: Simulation,
+    /// The rotation as in-game macros of up to 15 lines each.
+    pub macros: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Buffs {
+    pub inner_quiet: u32,
+    pub waste_not: u32,
+    pub veneration: u32,
+    pub great_strides: u32,
+    pub innovation: u32,
+    pub manipulation: u32,
+    pub muscle_memory: u32,
+    pub trained_perfection: bool,
+}
+
+////////////////////////////////////////////////////////////
+
+/// Simulates crafting a recipe with a rotation of actions, returning the
+/// state of the craft after every step.
+pub async fn simulate_craft(
+    State(db): State<Arc<ItemDB>>,
+    Query(params): Query<SimulateParams>,
+) -> Result<Json<Simulation>, (StatusCode, String)> {
+    let actions = parse_actions(&params.actions).map_err(|err| (StatusCode::BAD_REQUEST, err))?;
+    let recipe = fetch_recipe(&db, params.recipe).await?;
+    let crafter = CrafterStats {
+        level: params.level,
+        craftsmanship: params.craftsmanship,
+        control: params.control,
+        cp: params.cp,
+    };
+
+    let simulator = Simulator::new(craft_recipe(&recipe), crafter)
+        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?;
+    let states = simulator
+        .simulate(&actions)
+        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?;
+
+    Ok(Json(simulation(&simulator, params.recipe, actions, states)))
+}
+
+/// Searches for a rotation that completes a recipe with as much quality as
+/// possible, returning the state of the craft after every step along with
+/// the rotation as macros.
+pub async fn solve_craft(
+    State((db, permits)): State<(Arc<ItemDB>, Arc<Semaphore>)>,
+    Query(params): Query<SolveParams>,
+) -> Result<Json<Rotation>, (StatusCode, String)> {
+    let Ok(permit) = permits.try_acquire_owned() else {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Too many rotations are being solved, try again later".to_string(),
+        ));
+    };
+    let recipe = fetch_recipe(&db, params.recipe).await?;
+    let crafter = CrafterStats {
+        level: params.level,
+        craftsmanship: params.craftsmanship,
+        control: params.control,
+        cp: params.cp,
+    };
+    let simulator = Simulator::new(craft_recipe(&recipe), crafter)
+        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?;
+    let budget = params
+        .budget
+        .unwrap_or(DEFAULT_BUDGET_MS)
+        .min(MAX_BUDGET_MS);
+
+    let solver = Solver::new(simulator.clone(), Duration::from_millis(budget));
+    // The permit is held until the solver finishes, even if the request is dropped
+    let solve = move || {
+        let _permit = permit;
+        solver.solve()
+    };
+    let solution = match tokio::task::spawn_blocking(solve).await {
+        Ok(Some(solution)) => solution,
+        Ok(None) => {
+            return Err((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Couldn't find a rotation that completes the craft".to_string(),
+            ))
+        }
+        Err(err) => {
+            log::error!(target: "ffxiv_server", "Crafting solver failed: {err}");
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string()));
+        }
+    };
+
+    let macros = solution.macros();
+    let states = simulator
+        .simulate(&solution.actions)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    Ok(Json(Rotation {
+        simulation: simulation(&simulator, params.recipe, solution.actions, states),
+        macros,
+    }))
+}
+
+fn simulation(
+    simulator: &Simulator,
+    item_id: u32,
+    actions: Vec<Action>,
+    states: Vec<CraftState>,
+) -> Simulation {
+    let recipe = simulator.recipe();
+    let final_state = states
+        .last()
+        .copied()
+        .unwrap_or_else(|| simulator.initial_state());
+    Simulation {
+        item_id,
+        progress: recipe.progress,
+        quality: recipe.quality,
+        durability: recipe.durability,
+        cp: simulator.stats().cp,
+        is_complete: simulator.is_complete(&final_state),
+        steps: actions
+            .into_iter()
+            .zip(states)
+            .map(|(action, state)| Step {
+                action: action.name().to_string(),
+                progress: state.progress,
+                quality: state.quality,
+                durability: state.durability,
+                cp: state.cp,
+                buffs: state.buffs.into(),
+            })
+            .collect(),
+    }
+}
+
+/// Fetches the crafter recipe for `item_id`.
+pub async fn fetch_recipe(db: &ItemDB, item_id: u32) -> Result<Recipe, (StatusCode, String)> {
+    match db.items_from_ids(&[item_id]).await {
+        Ok(items) => items
+            .into_iter()
+            .find_map(|item| item.recipe)
+            .ok_or_else(|| {
+                (
+                    StatusCode::NOT_FOUND,
+                    format!("No recipe for item {item_id}"),
+                )
+            }),
+        Err(err) => {
+            log::error!(target: "ffxiv_server", "Couldn't fetch recipe: {err}");
+            Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+        }
+    }
+}
+
+pub fn craft_recipe(recipe: &Recipe) -> CraftRecipe {
+    let difficulty = &recipe.difficulty;
+    CraftRecipe {
+        level: recipe.level,
+        progress: difficulty.progress,
+        quality: difficulty.quality,
+        durability: difficulty.durability,
+        progress_divider: difficulty.progress_divider,
+        quality_divider: difficulty.quality_divider,
+        progress_modifier: difficulty.progress_modifier,
+        quality_modifier: difficulty.quality_modifier,
+    }
+}
+
+fn parse_actions(actions: &str) -> Result<Vec<Action>, String> {
+    actions
+        .split(',')
+        .filter(|action| !action.trim().is_empty())
+        .map(|action| action.parse::<Action>().map_err(|err| err.to_string()))
+        .collect()
+}
+
+////////////////////////////////////////////////////////////
+
+impl From<ffxiv_crafting::Buffs> for Buffs {
+    fn from(buffs: ffxiv_crafting::Buffs) -> Self {
+        Self {
+            inner_quiet: buffs.inner_quiet,
+            waste_not: buffs.waste_not,
+            veneration: buffs.veneration,
+            great_strides: buffs.great_strides,
+            innovation: buffs.innovation,
+            manipulation: buffs.manipulation,
+            muscle_memory: buffs.muscle_memory,
+            trained_perfection: buffs.trained_perfection == TrainedPerfection::Active,
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, Json};
+use ffxiv_items::ItemDB;
+use serde::Serialize;
+
+////////////////////////////////////////////////////////////
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatasetInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_updated: Option<String>,
+    pub files: Vec<DatasetFile>,
+    pub cache: CacheStats,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DatasetFile {
+    pub file_name: String,
+    pub commit_sha: Option<String>,
+    pub commit_date: Option<String>,
+    pub downloaded_at: Option<String>,
+    pub row_count: Option<u32>,
+    pub content_hash: Option<String>,
+    pub imported: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub query_hits: u64,
+    pub query_misses: u64,
+    pub item_hits: u64,
+    pub item_misses: u64,
+    pub cached_queries: usize,
+    pub cached_items: usize,
+}
+
+////////////////////////////////////////////////////////////
+
+pub async fn dataset_info(
+    State(db): State<Arc<ItemDB>>,
+) -> Result<Json<DatasetInfo>, (StatusCode, String)> {
+    match db.dataset_info().await {
+        Ok(info) => {
+            let mut info = DatasetInfo::from(info);
+            info.cache = db.cache_stats().into();
+            Ok(Json(info))
+        }
+        Err(err) => {
+            log::error!(target: "ffxiv_server", "Couldn't fetch dataset info: {err}");
+            Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////
+
+impl From<ffxiv_items::DatasetInfo> for DatasetInfo {
+    fn from(info: ffxiv_items::DatasetInfo) -> Self {
+        Self {
+            last_updated: info.last_updated.map(|date| date.to_rfc3339()),
+            files: info.files.into_iter().map(Into::into).collect(),
+            cache: ffxiv_items::CacheStats::default().into(),
+        }
+    }
+}
+
+impl From<ffxiv_items::DatasetFile> for DatasetFile {
+    fn from(file: ffxiv_items::DatasetFile) -> Self {
+        Self {
+            file_name: file.file_name,
+            commit_sha: file.commit_sha,
+            commit_date: file.commit_date.map(|date| date.to_rfc3339()),
+            downloaded_at: file.downloaded_at.map(|date| date.to_rfc3339()),
+            row_count: file.row_count,
+            content_hash: file.content_hash,
+            imported: file.imported,
+        }
+    }
+}
+
+impl From<ffxiv_items::CacheStats> for CacheStats {
+    fn from(stats: ffxiv_items::CacheStats) -> Self {
+        Self {
+            query_hits: stats.query_hits,
+            query_misses: stats.query_misses,
+            item_hits: stats.item_hits,
+            item_misses: stats.item_misses,
+            cached_queries: stats.cached_queries,
+            cached_items: stats.cached_items,
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use ffxiv_items::{GcSupplyKind, ItemDB};
+use ffxiv_universalis::Processor;
+use mock_traits::FileDownloader;
+use serde::{Deserialize, Serialize};
+
+use crate::market::{fetch_listings, purchase_cost};
+
+////////////////////////////////////////////////////////////
+
+#[derive(Deserialize)]
+pub struct GcSupplyParams {
+    /// The world, data center or region to buy the items from.
+    world: String,
+    /// Only ranks items requested from this job, e.g. `CUL`.
+    job: Option<String>,
+    /// Comma separated item ids, e.g. today's turn-ins. Every requested item
+    /// is ranked if this isn't set.
+    ids: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcSupplyRanking {
+    pub item_id: u32,
+    pub name: String,
+    pub job: String,
+    pub kind: GcSupplyKindOutput,
+    pub level: u32,
+    pub count: u32,
+    pub seals: u32,
+    pub experience: u32,
+    /// The cost of buying `count` items from the marketboard, if enough are listed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gil_per_seal: Option<f32>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GcSupplyKindOutput {
+    Supply,
+    Provisioning,
+}
+
+////////////////////////////////////////////////////////////
+
+/// Ranks Grand Company supply & provisioning turn-ins by the gil spent per
+/// seal earned, cheapest first. Items that can't be bought are ranked last.
+pub async fn gc_supply_ranking<F: FileDownloader>(
+    State((processor, db)): State<(Processor, Arc<ItemDB>)>,
+    Query(params): Query<GcSupplyParams>,
+) -> Result<Json<Vec<GcSupplyRanking>>, (StatusCode, String)> {
+    let ids = match params.ids.as_deref().map(parse_ids).transpose() {
+        Ok(ids) => ids,
+        Err(err) => return Err((StatusCode::BAD_REQUEST, err)),
+    };
+
+    let rankings = async {
+        let mut duties = db.gc_supply_duties(params.job.as_deref()).await?;
+        if let Some(ids) = &ids {
+            duties.retain(|duty| ids.contains(&duty.item_id));
+        }
+
+        let duty_ids = duties.iter().map(|duty| duty.item_id).collect::<Vec<_>>();
+        let names = db
+            .items_from_ids(&duty_ids)
+            .await?
+            .into_iter()
+            .map(|item| (item.id, item.name))
+            .collect::<BTreeMap<_, _>>();
+        let listings = fetch_listings::<F>(&processor, &db, &duty_ids, &params.world).await;
+
+        let mut rankings = duties
+            .into_iter()
+            .map(|duty| {
+                let cost = listings
+                    .get(&duty.item_id)
+                    .and_then(|listings| purchase_cost(listings, duty.count));
+                #[allow(clippy::cast_precision_loss)]
+                let gil_per_seal = cost
+                    .filter(|_| duty.seals > 0)
+                    .map(|cost| cost as f32 / duty.seals as f32);
+                GcSupplyRanking {
+                    name: names.get(&duty.item_id).cloned().unwrap_or_default(),
+                    item_id: duty.item_id,
+                    job: duty.job,
+                    kind: duty.kind.into(),
+                    level: duty.level,
+                    count: duty.count,
+                    seals: duty.seals,
+                    experience: duty.experience,
+                    cost,
+                    gil_per_seal,
+                }
+            })
+            .collect::<Vec<_>>();
+        rankings.sort_by(|a, b| match (a.gil_per_seal, b.gil_per_seal) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (a, b) => b.is_some().cmp(&a.is_some()),
+        });
+        Ok::<_, anyhow::Error>(rankings)
+    };
+
+    match rankings.await {
+        Ok(rankings) => Ok(Json(rankings)),
+        Err(err) => {
+            log::error!(target: "ffxiv_server", "Couldn't rank GC supply duties: {err}");
+            Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+        }
+    }
+}
+
+/// Parses comma separated item ids, e.g. `5057,5058`.
+pub fn parse_ids(ids: &str) -> Result<Vec<u32>, String> {
+    ids.split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            id.parse::<u32>()
+                .map_err(|_| format!("Invalid item id '{id}'"))
+        })
+        .collect()
+}
+
+////////////////////////////////////////////////////////////
+
+impl From<GcSupplyKind> for GcSupplyKindOutput {
+    fn from(kind: GcSupplyKind) -> Self {
+        match kind {
+            GcSupplyKind::Supply => Self::Supply,
+            GcSupplyKind::Provisioning => Self::Provisioning,
+        }
+    }
+}
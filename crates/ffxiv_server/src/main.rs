@@ -3,11 +3,21 @@
 #![allow(clippy::module_name_repetitions)]
 #![doc(hidden)]
 
+mod admin;
+mod collectables;
+mod crafting;
+mod dataset;
+mod gc_supply;
+mod market;
+mod melding;
+mod refresh;
+mod saved_queries;
 mod server;
 mod universalis;
 
 use std::{error::Error, time::Instant};
 
+use refresh::DatasetRefresher;
 use server::Server;
 use universalis::universalis_websocket;
 
@@ -25,7 +35,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
     db.initialize::<mock_traits::ReqwestDownloader>().await?;
     println!("Initialized in {} ms", start.elapsed().as_millis());
 
-    Server::run::<mock_traits::ReqwestDownloader>(db).await?;
+    let refresh_interval = DatasetRefresher::<mock_traits::ReqwestDownloader>::interval_from_env();
+    Server::run::<mock_traits::ReqwestDownloader>(db, refresh_interval).await?;
 
     Ok(())
 }
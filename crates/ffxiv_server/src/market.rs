@@ -0,0 +1,63 @@
+use ffxiv_items::ItemDB;
+use ffxiv_universalis::{ItemListing, ListingsMap, ListingsResults, Processor, RequestBuilder};
+use mock_traits::FileDownloader;
+
+////////////////////////////////////////////////////////////
+
+/// Fetches the current marketboard listings for `ids` from universalis,
+/// through the shared request processor. Items that can't be sold on the
+/// market board aren't requested, & items that couldn't be fetched are left
+/// out.
+pub async fn fetch_listings<F: FileDownloader>(
+    processor: &Processor,
+    db: &ItemDB,
+    ids: &[u32],
+    world: &str,
+) -> ListingsMap {
+    let ids = marketable_ids(db, ids).await;
+    if ids.is_empty() {
+        return ListingsMap::new();
+    }
+
+    let mut handle = RequestBuilder::new(&ids, world).execute::<F>(processor);
+    let ListingsResults {
+        listings, failures, ..
+    } = handle.collect_all().await;
+    if !failures.is_empty() {
+        log::warn!(target: "ffxiv_server", "Couldn't fetch listings for {} items", failures.len());
+    }
+    listings
+}
+
+/// Filters `ids` down to the items that can be sold on the market board. If
+/// the items can't be looked up, every id is kept.
+pub async fn marketable_ids(db: &ItemDB, ids: &[u32]) -> Vec<u32> {
+    match db.items_from_ids(ids).await {
+        Ok(items) => items
+            .into_iter()
+            .filter(|item| item.is_marketable)
+            .map(|item| item.id)
+            .collect(),
+        Err(err) => {
+            log::warn!(target: "ffxiv_server", "Couldn't check which items are marketable: {err}");
+            ids.to_vec()
+        }
+    }
+}
+
+/// The cheapest cost of buying `count` items from `listings`, which are sorted
+/// by price. Returns `None` if fewer than `count` items are listed, or if the
+/// cost doesn't fit in a `u32`.
+pub fn purchase_cost(listings: &[ItemListing], count: u32) -> Option<u32> {
+    let mut remaining = count;
+    let mut cost = 0u32;
+    for listing in listings {
+        if remaining == 0 {
+            break;
+        }
+        let bought = listing.count.min(remaining);
+        cost = cost.checked_add(listing.price.checked_mul(bought)?)?;
+        remaining -= bought;
+    }
+    (remaining == 0).then_some(cost)
+}
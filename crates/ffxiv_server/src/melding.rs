@@ -0,0 +1,143 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use ffxiv_items::ItemDB;
+use ffxiv_universalis::Processor;
+use mock_traits::FileDownloader;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gc_supply::parse_ids,
+    market::{fetch_listings, purchase_cost},
+};
+
+////////////////////////////////////////////////////////////
+
+#[derive(Deserialize)]
+pub struct MeldingParams {
+    /// The world, data center or region to buy the materia from.
+    world: String,
+    /// The id of the gear to meld.
+    item: u32,
+    /// Comma separated materia item ids, in the order they're melded.
+    materia: String,
+    /// Whether the gear is high quality, which improves overmeld rates.
+    #[serde(default)]
+    hq: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeldingCost {
+    pub item_id: u32,
+    pub slots: u32,
+    pub melds: Vec<Meld>,
+    pub materia: Vec<MateriaCost>,
+    /// The cost of buying every materia expected to be consumed, if enough
+    /// of each are listed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost: Option<u32>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Meld {
+    pub materia_id: u32,
+    pub stat: String,
+    pub grade: u32,
+    pub value: i32,
+    pub is_overmeld: bool,
+    pub success_rate: f32,
+    pub expected_count: f32,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MateriaCost {
+    pub materia_id: u32,
+    pub name: String,
+    /// The number of materia expected to be consumed, across every meld.
+    pub expected_count: f32,
+    /// The cost of buying the expected count, rounded up, from the marketboard.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost: Option<u32>,
+}
+
+////////////////////////////////////////////////////////////
+
+/// Estimates the cost of melding a set of materia into a piece of gear,
+/// including the materia lost to failed overmelds.
+pub async fn melding_cost<F: FileDownloader>(
+    State((processor, db)): State<(Processor, Arc<ItemDB>)>,
+    Query(params): Query<MeldingParams>,
+) -> Result<Json<MeldingCost>, (StatusCode, String)> {
+    let materia_ids = parse_ids(&params.materia).map_err(|err| (StatusCode::BAD_REQUEST, err))?;
+    let plan = db
+        .meld_plan(params.item, &materia_ids, params.hq)
+        .await
+        .map_err(|err| (StatusCode::BAD_REQUEST, err.to_string()))?;
+
+    let mut expected_counts = BTreeMap::<u32, f32>::new();
+    for meld in &plan.melds {
+        *expected_counts.entry(meld.materia.item_id).or_default() += meld.expected_count;
+    }
+
+    let ids = expected_counts.keys().copied().collect::<Vec<_>>();
+    let names = match db.items_from_ids(&ids).await {
+        Ok(items) => items
+            .into_iter()
+            .map(|item| (item.id, item.name))
+            .collect::<BTreeMap<_, _>>(),
+        Err(err) => {
+            log::error!(target: "ffxiv_server", "Couldn't fetch materia names: {err}");
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string()));
+        }
+    };
+    let listings = fetch_listings::<F>(&processor, &db, &ids, &params.world).await;
+
+    let materia = expected_counts
+        .into_iter()
+        .map(|(materia_id, expected_count)| {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let count = expected_count.ceil() as u32;
+            MateriaCost {
+                name: names.get(&materia_id).cloned().unwrap_or_default(),
+                cost: listings
+                    .get(&materia_id)
+                    .and_then(|listings| purchase_cost(listings, count)),
+                materia_id,
+                expected_count,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(Json(MeldingCost {
+        item_id: plan.item_id,
+        slots: plan.slots,
+        cost: materia
+            .iter()
+            .try_fold(0u32, |total, materia| total.checked_add(materia.cost?)),
+        melds: plan.melds.into_iter().map(Into::into).collect(),
+        materia,
+    }))
+}
+
+////////////////////////////////////////////////////////////
+
+impl From<ffxiv_items::Meld> for Meld {
+    fn from(meld: ffxiv_items::Meld) -> Self {
+        Self {
+            materia_id: meld.materia.item_id,
+            stat: meld.materia.stat,
+            grade: meld.materia.grade,
+            value: meld.materia.value,
+            is_overmeld: meld.is_overmeld,
+            success_rate: meld.success_rate,
+            expected_count: meld.expected_count,
+        }
+    }
+}
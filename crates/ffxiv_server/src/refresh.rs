@@ -0,0 +1,196 @@
+use std::{marker::PhantomData, sync::Arc, time::Duration};
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use chrono::Utc;
+use ffxiv_items::{ItemDB, ItemDBError};
+use mock_traits::FileDownloader;
+use serde::Deserialize;
+use tokio::{
+    select,
+    sync::mpsc::{self, error::TrySendError},
+    time::{interval, sleep_until, Instant, MissedTickBehavior},
+};
+
+use crate::admin::check_admin;
+
+////////////////////////////////////////////////////////////
+
+/// Each check for updates costs several github API requests, and unauthenticated
+/// requests are limited to 60 per hour, so checks are never spaced closer than this.
+const MIN_CHECK_INTERVAL: Duration = minutes(5);
+const DEFAULT_CHECK_INTERVAL: Duration = minutes(6 * 60);
+/// The github rate limit window; failed checks back off up to this long.
+const MAX_BACKOFF: Duration = minutes(60);
+/// How often the database is checked for rebuilds made by other processes,
+/// whose cached results would otherwise be served until the next refresh.
+const CACHE_SYNC_INTERVAL: Duration = minutes(1);
+
+/// Periodically checks github for new datamining files, and rebuilds the item
+/// database when they're found. Also clears the item cache when another process
+/// rebuilds the database.
+pub struct DatasetRefresher<F: FileDownloader> {
+    db: Arc<ItemDB>,
+    interval: Duration,
+    receiver: mpsc::Receiver<RefreshRequest>,
+    marker_f: PhantomData<fn() -> F>,
+}
+
+/// Used to ask a running [`DatasetRefresher`] for an immediate refresh.
+#[derive(Clone)]
+pub struct RefreshHandle {
+    sender: mpsc::Sender<RefreshRequest>,
+}
+
+#[derive(Clone, Copy)]
+struct RefreshRequest {
+    force: bool,
+}
+
+#[derive(Deserialize)]
+pub struct RefreshParams {
+    force: Option<bool>,
+}
+
+////////////////////////////////////////////////////////////
+
+impl<F: FileDownloader> DatasetRefresher<F> {
+    pub fn new(db: Arc<ItemDB>, interval: Duration) -> (Self, RefreshHandle) {
+        let (sender, receiver) = mpsc::channel(1);
+        let refresher = Self {
+            db,
+            interval: interval.max(MIN_CHECK_INTERVAL),
+            receiver,
+            marker_f: PhantomData,
+        };
+        (refresher, RefreshHandle { sender })
+    }
+
+    /// The check interval, read from `FFXIV_REFRESH_INTERVAL_MINS`.
+    pub fn interval_from_env() -> Duration {
+        let Ok(val) = std::env::var("FFXIV_REFRESH_INTERVAL_MINS") else {
+            return DEFAULT_CHECK_INTERVAL;
+        };
+
+        if let Ok(mins) = val.parse::<u64>() {
+            minutes(mins)
+        } else {
+            log::warn!(target: "ffxiv_server", "Invalid FFXIV_REFRESH_INTERVAL_MINS '{val}', using the default");
+            DEFAULT_CHECK_INTERVAL
+        }
+    }
+
+    pub async fn run(mut self) {
+        let mut wait = self.interval;
+        let mut next_check = Instant::now() + wait;
+        let mut last_check: Option<Instant> = None;
+        let mut cache_sync = interval(CACHE_SYNC_INTERVAL);
+        cache_sync.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            let request = select! {
+                () = sleep_until(next_check) => RefreshRequest { force: false },
+                _ = cache_sync.tick() => {
+                    self.sync_cache().await;
+                    continue;
+                }
+                request = self.receiver.recv() => match request {
+                    Some(request) => request,
+                    None => break,
+                },
+            };
+
+            // Requested checks still respect the github rate limit, but forced
+            // rebuilds don't talk to github at all.
+            if let Some(last_check) = last_check {
+                let elapsed = last_check.elapsed();
+                if !request.force && elapsed < MIN_CHECK_INTERVAL {
+                    log::info!(target: "ffxiv_server", "Skipping dataset refresh, last check was {}s ago", elapsed.as_secs());
+                    wait = MIN_CHECK_INTERVAL.saturating_sub(elapsed);
+                    next_check = Instant::now() + wait;
+                    continue;
+                }
+            }
+
+            last_check = Some(Instant::now());
+            wait = match self.refresh(request).await {
+                Ok(()) => self.interval,
+                Err(err) => {
+                    log::warn!(target: "ffxiv_server", "Dataset refresh failed: {err}");
+                    // Github says when its rate limit resets, so there's no need to guess
+                    let backoff = match err {
+                        ItemDBError::RateLimited { reset: Some(reset) } => {
+                            (reset - Utc::now()).to_std().unwrap_or_default()
+                        }
+                        _ => wait * 2,
+                    };
+                    backoff.clamp(MIN_CHECK_INTERVAL, MAX_BACKOFF)
+                }
+            };
+            next_check = Instant::now() + wait;
+        }
+    }
+
+    async fn sync_cache(&self) {
+        match self.db.sync_cache().await {
+            Ok(true) => {
+                log::info!(target: "ffxiv_server", "Dataset changed elsewhere, cleared the item cache");
+            }
+            Ok(false) => {}
+            Err(err) => {
+                log::warn!(target: "ffxiv_server", "Couldn't check the dataset for changes: {err}");
+            }
+        }
+    }
+
+    async fn refresh(&self, request: RefreshRequest) -> Result<(), ItemDBError> {
+        let start = Instant::now();
+        if request.force {
+            log::info!(target: "ffxiv_server", "Forcing dataset rebuild");
+            self.db.rebuild::<F>().await?;
+        } else if !self.db.update::<F>().await? {
+            log::info!(target: "ffxiv_server", "Dataset is up-to-date");
+            return Ok(());
+        }
+
+        log::info!(target: "ffxiv_server", "Dataset rebuilt in {} ms", start.elapsed().as_millis());
+        Ok(())
+    }
+}
+
+impl RefreshHandle {
+    /// Queues a refresh. Returns `false` if one is already pending.
+    fn request(&self, force: bool) -> bool {
+        match self.sender.try_send(RefreshRequest { force }) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_) | TrySendError::Closed(_)) => false,
+        }
+    }
+}
+
+const fn minutes(mins: u64) -> Duration {
+    Duration::from_secs(mins * 60)
+}
+
+////////////////////////////////////////////////////////////
+
+#[allow(clippy::unused_async)]
+pub async fn refresh_dataset(
+    State(handle): State<RefreshHandle>,
+    headers: HeaderMap,
+    Query(params): Query<RefreshParams>,
+) -> impl IntoResponse {
+    if let Err(rejection) = check_admin(&headers) {
+        return rejection;
+    }
+
+    let force = params.force.unwrap_or(false);
+    log::info!(target: "ffxiv_server", "Dataset refresh requested (force: {force})");
+    if handle.request(force) {
+        (StatusCode::ACCEPTED, "Refresh queued")
+    } else {
+        (StatusCode::CONFLICT, "Refresh already pending")
+    }
+}
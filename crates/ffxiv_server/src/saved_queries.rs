@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use ffxiv_items::ItemDB;
+use serde::{Deserialize, Serialize};
+
+use crate::admin::check_admin;
+
+////////////////////////////////////////////////////////////
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedQuery {
+    pub name: String,
+    pub query: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_velocity: Option<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedQueryBody {
+    query: String,
+    count: Option<u32>,
+    limit: Option<u32>,
+    min_velocity: Option<f32>,
+}
+
+////////////////////////////////////////////////////////////
+
+/// Lists every saved query, ordered by name.
+pub async fn list_saved_queries(
+    State(db): State<Arc<ItemDB>>,
+) -> Result<Json<Vec<SavedQuery>>, (StatusCode, String)> {
+    match db.saved_queries().await {
+        Ok(queries) => Ok(Json(queries.into_iter().map(Into::into).collect())),
+        Err(err) => {
+            log::error!(target: "ffxiv_server", "Couldn't fetch saved queries: {err}");
+            Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+        }
+    }
+}
+
+pub async fn get_saved_query(
+    State(db): State<Arc<ItemDB>>,
+    Path(name): Path<String>,
+) -> Result<Json<SavedQuery>, (StatusCode, String)> {
+    match db.saved_query(&name).await {
+        Ok(Some(query)) => Ok(Json(query.into())),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            format!("No saved query named '{name}'"),
+        )),
+        Err(err) => {
+            log::error!(target: "ffxiv_server", "Couldn't fetch saved query: {err}");
+            Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+        }
+    }
+}
+
+/// Saves a query under `name`, replacing any saved query with the same name.
+pub async fn put_saved_query(
+    State(db): State<Arc<ItemDB>>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Json(body): Json<SavedQueryBody>,
+) -> Result<Json<SavedQuery>, (StatusCode, String)> {
+    check_admin(&headers).map_err(|(status, message)| (status, message.to_string()))?;
+
+    let query = ffxiv_items::SavedQuery {
+        name,
+        query: body.query,
+        count: body.count,
+        limit: body.limit,
+        min_velocity: body.min_velocity,
+    };
+    match db.save_query(&query).await {
+        Ok(()) => {
+            log::info!(target: "ffxiv_server", "Saved query '{}'", query.name);
+            Ok(Json(query.into()))
+        }
+        Err(err) if err.is_query() => Err((StatusCode::BAD_REQUEST, err.to_string())),
+        Err(err) => {
+            log::error!(target: "ffxiv_server", "Couldn't save query: {err}");
+            Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+        }
+    }
+}
+
+pub async fn delete_saved_query(
+    State(db): State<Arc<ItemDB>>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    check_admin(&headers).map_err(|(status, message)| (status, message.to_string()))?;
+
+    match db.delete_saved_query(&name).await {
+        Ok(true) => {
+            log::info!(target: "ffxiv_server", "Deleted saved query '{name}'");
+            Ok(StatusCode::NO_CONTENT)
+        }
+        Ok(false) => Err((
+            StatusCode::NOT_FOUND,
+            format!("No saved query named '{name}'"),
+        )),
+        Err(err) => {
+            log::error!(target: "ffxiv_server", "Couldn't delete saved query: {err}");
+            Err((StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////
+
+impl From<ffxiv_items::SavedQuery> for SavedQuery {
+    fn from(query: ffxiv_items::SavedQuery) -> Self {
+        Self {
+            name: query.name,
+            query: query.query,
+            count: query.count,
+            limit: query.limit,
+            min_velocity: query.min_velocity,
+        }
+    }
+}
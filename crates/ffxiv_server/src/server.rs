@@ -1,34 +1,111 @@
 use anyhow::Result;
-use axum::{http::Method, routing::get, Router};
-use ffxiv_items::ItemDB;
+use axum::{
+    http::Method,
+    routing::{get, put},
+    Router,
+};
+use ffxiv_items::{ItemDB, ItemRepository};
 use ffxiv_universalis::Processor;
 use futures::join;
 use mock_traits::FileDownloader;
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::sync::Semaphore;
 use tower_http::cors::{Any, CorsLayer};
 
-use crate::universalis_websocket;
+use crate::{
+    collectables::{collectable_rewards, scrip_ranking},
+    crafting::{simulate_craft, solve_craft, MAX_CONCURRENT_SOLVES},
+    dataset::dataset_info,
+    gc_supply::gc_supply_ranking,
+    melding::melding_cost,
+    refresh::{refresh_dataset, DatasetRefresher, RefreshHandle},
+    saved_queries::{delete_saved_query, get_saved_query, list_saved_queries, put_saved_query},
+    universalis_websocket,
+};
 
 pub struct Server;
 
 #[allow(unused_must_use)]
 impl Server {
-    pub async fn run<F: FileDownloader + 'static>(db: ItemDB) -> Result<()> {
-        let universalis_processor = Processor::new();
-        let async_processor = universalis_processor.async_processor();
-        let db = Arc::new(db);
-
+    /// The routes that only look items up, which work with any
+    /// [`ItemRepository`], e.g. a `MemoryItemRepository` in tests.
+    pub fn repository_router<F: FileDownloader + 'static, R: ItemRepository + 'static>(
+        processor: Processor,
+        items: Arc<R>,
+    ) -> Router {
         let health_service = Router::new().route("/health", get(|| async { "OK" }));
 
         let market_service_ws = Router::new()
-            .route("/universalis", get(universalis_websocket::<F>))
-            .with_state((universalis_processor.clone(), db.clone()));
+            .route("/universalis", get(universalis_websocket::<F, R>))
+            .with_state((processor, items));
+
+        Router::new().merge(health_service).merge(market_service_ws)
+    }
+
+    /// The routes that need the item database itself, e.g. for recipes,
+    /// rewards & saved queries.
+    fn item_db_router<F: FileDownloader + 'static>(
+        processor: Processor,
+        db: Arc<ItemDB>,
+        refresh_handle: RefreshHandle,
+    ) -> Router {
+        let dataset_service = Router::new()
+            .route("/dataset", get(dataset_info))
+            .route("/collectables", get(collectable_rewards))
+            .route("/craft/simulate", get(simulate_craft))
+            .route("/queries", get(list_saved_queries))
+            .route(
+                "/queries/:name",
+                get(get_saved_query)
+                    .put(put_saved_query)
+                    .delete(delete_saved_query),
+            )
+            .with_state(db.clone());
+
+        let solver_service = Router::new()
+            .route("/craft/solve", get(solve_craft))
+            .with_state((db.clone(), Arc::new(Semaphore::new(MAX_CONCURRENT_SOLVES))));
+
+        let rankings_service = Router::new()
+            .route("/gc-supply", get(gc_supply_ranking::<F>))
+            .route("/collectables/scrips", get(scrip_ranking::<F>))
+            .route("/melding", get(melding_cost::<F>))
+            .with_state((processor, db));
+
+        let admin_service = Router::new()
+            .route("/admin/refresh", put(refresh_dataset))
+            .with_state(refresh_handle);
+
+        Router::new()
+            .merge(dataset_service)
+            .merge(solver_service)
+            .merge(rankings_service)
+            .merge(admin_service)
+    }
+
+    pub async fn run<F: FileDownloader + 'static>(
+        db: ItemDB,
+        refresh_interval: Duration,
+    ) -> Result<()> {
+        let universalis_processor = Processor::new();
+        let async_processor = universalis_processor.async_processor();
+        let db = Arc::new(db);
+        let (refresher, refresh_handle) = DatasetRefresher::<F>::new(db.clone(), refresh_interval);
 
-        let v1_router = Router::new().merge(health_service).merge(market_service_ws);
+        let v1_router = Router::new()
+            .merge(Self::repository_router::<F, ItemDB>(
+                universalis_processor.clone(),
+                db.clone(),
+            ))
+            .merge(Self::item_db_router::<F>(
+                universalis_processor.clone(),
+                db,
+                refresh_handle,
+            ));
 
         let app = Router::new().nest("/v1", v1_router).layer(
             CorsLayer::new()
-                .allow_methods(vec![Method::GET, Method::PUT])
+                .allow_methods(vec![Method::GET, Method::PUT, Method::DELETE])
                 .allow_headers(Any)
                 .allow_origin(Any),
         );
@@ -38,6 +115,7 @@ impl Server {
 
         join!(
             async_processor,
+            refresher.run(),
             axum::Server::bind(&addr).serve(app.into_make_service())
         );
 
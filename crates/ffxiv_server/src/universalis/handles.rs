@@ -17,7 +17,7 @@ use super::{write_message, DetailedStatus, Input, Output};
 ////////////////////////////////////////////////////////////
 
 const DUR_MIN_WAIT: Duration = Duration::from_millis(10);
-const DUR_TIMEOUT: Duration = Duration::from_millis(5000);
+const DUR_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub async fn wait_for_universalis<F: FileDownloader>(
     socket: &mut WebSocket,
@@ -116,7 +116,7 @@ impl RequestStream {
 
     fn time_to_stale(&self, timeout: Duration) -> Duration {
         // Don't want negative values
-        timeout - self.last_update.elapsed().min(timeout)
+        timeout.saturating_sub(self.last_update.elapsed())
     }
 
     fn retain_fresh_signals(&mut self) {
@@ -154,7 +154,7 @@ impl RequestStream {
                     return true;
                 }
             }
-            _ = sleep(timeout) => {}
+            () = sleep(timeout) => {}
         }
 
         self.is_stale(max_timeout)
@@ -186,7 +186,7 @@ impl RequestStream {
             let message_text = serde_json::to_string(&output)?;
             write_message(socket, message_text, self.is_compressed).await?;
 
-            if matches!(output, Output::Done {}) {
+            if matches!(output, Output::Done) {
                 break;
             }
         }
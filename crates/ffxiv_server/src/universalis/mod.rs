@@ -4,8 +4,11 @@ mod types;
 mod websocket;
 
 use handles::wait_for_universalis;
-use recipes::send_recipes;
-use types::{DetailedStatus, Ingredient, Input, ItemInfo, Output, Recipe};
+use recipes::{remove_unowned_recipes, send_recipes};
+use types::{
+    CraftPhase, DetailedStatus, Food, FoodEffect, FoodKind, Ingredient, Input, ItemInfo, Output,
+    PhaseSupply, Recipe, RecipeBook, RecipeKind, Stat,
+};
 use websocket::write_message;
 
 pub use websocket::universalis_websocket;
@@ -1,8 +1,13 @@
+use std::collections::{BTreeSet, HashMap};
+
 use anyhow::Result;
 use axum::extract::ws::WebSocket;
 use tokio::task::spawn_blocking;
 
-use super::{write_message, Ingredient, ItemInfo, Output, Recipe};
+use super::{
+    write_message, CraftPhase, Food, FoodEffect, FoodKind, Ingredient, ItemInfo, Output,
+    PhaseSupply, Recipe, RecipeBook, RecipeKind, Stat,
+};
 
 ////////////////////////////////////////////////////////////
 
@@ -17,6 +22,48 @@ pub async fn send_recipes(
     Ok(())
 }
 
+/// Leaves out the recipes needing a master recipe book that isn't one of
+/// `owned_books`. Top level items with such a recipe are removed, & the other
+/// items are treated as uncraftable, so ingredients only used by the removed
+/// recipes aren't requested from universalis. Returns the ids of every item
+/// still involved.
+pub fn remove_unowned_recipes(
+    owned_books: &[u32],
+    top_ids: &mut Vec<u32>,
+    items: &mut Vec<ffxiv_items::ItemInfo>,
+) -> Vec<u32> {
+    let mut unowned = BTreeSet::new();
+    for item in items.iter_mut() {
+        let needs_book = item
+            .recipe
+            .as_ref()
+            .and_then(|recipe| recipe.book.as_ref())
+            .is_some_and(|book| !owned_books.contains(&book.item_id));
+        if needs_book {
+            item.recipe = None;
+            unowned.insert(item.id);
+        }
+    }
+    top_ids.retain(|id| !unowned.contains(id));
+
+    let recipes = items
+        .iter()
+        .filter_map(|item| Some((item.id, item.recipe.as_ref()?)))
+        .collect::<HashMap<_, _>>();
+    let mut ids = BTreeSet::new();
+    let mut pending = top_ids.clone();
+    while let Some(id) = pending.pop() {
+        if ids.insert(id) {
+            if let Some(recipe) = recipes.get(&id) {
+                pending.extend(recipe.inputs.iter().map(|input| input.item_id));
+            }
+        }
+    }
+
+    items.retain(|item| ids.contains(&item.id));
+    ids.into_iter().collect()
+}
+
 ////////////////////////////////////////////////////////////
 
 async fn get_recipe_info_data(
@@ -33,6 +80,13 @@ async fn get_recipe_info_data(
                         item_id: item.id,
                         name: item.name,
                         recipe: item.recipe.map(Into::into),
+                        stats: item
+                            .stats
+                            .into_iter()
+                            .map(|(name, value)| Stat { name, value })
+                            .collect(),
+                        food: item.food.map(Into::into),
+                        is_marketable: item.is_marketable,
                     },
                 )
             })
@@ -54,6 +108,45 @@ impl From<ffxiv_items::Recipe> for Recipe {
             inputs: recipe.inputs.into_iter().map(Into::into).collect(),
             outputs: recipe.output.count,
             level: recipe.level,
+            kind: recipe.kind.into(),
+            phases: recipe.phases.into_iter().map(Into::into).collect(),
+            book: recipe.book.map(Into::into),
+        }
+    }
+}
+
+impl From<ffxiv_items::RecipeKind> for RecipeKind {
+    fn from(kind: ffxiv_items::RecipeKind) -> Self {
+        match kind {
+            ffxiv_items::RecipeKind::Crafter => Self::Crafter,
+            ffxiv_items::RecipeKind::CompanyWorkshop => Self::CompanyWorkshop,
+        }
+    }
+}
+
+impl From<ffxiv_items::RecipeBook> for RecipeBook {
+    fn from(book: ffxiv_items::RecipeBook) -> Self {
+        Self {
+            item_id: book.item_id,
+            name: book.name,
+        }
+    }
+}
+
+impl From<ffxiv_items::CraftPhase> for CraftPhase {
+    fn from(phase: ffxiv_items::CraftPhase) -> Self {
+        Self {
+            supplies: phase.supplies.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ffxiv_items::PhaseSupply> for PhaseSupply {
+    fn from(supply: ffxiv_items::PhaseSupply) -> Self {
+        Self {
+            item_id: supply.item_id,
+            set_quantity: supply.set_quantity,
+            sets_required: supply.sets_required,
         }
     }
 }
@@ -66,3 +159,34 @@ impl From<ffxiv_items::Ingredient> for Ingredient {
         }
     }
 }
+
+impl From<ffxiv_items::Food> for Food {
+    fn from(food: ffxiv_items::Food) -> Self {
+        Self {
+            kind: food.kind.into(),
+            effects: food.effects.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl From<ffxiv_items::FoodKind> for FoodKind {
+    fn from(kind: ffxiv_items::FoodKind) -> Self {
+        match kind {
+            ffxiv_items::FoodKind::Meal => Self::Meal,
+            ffxiv_items::FoodKind::Medicine => Self::Medicine,
+        }
+    }
+}
+
+impl From<ffxiv_items::FoodEffect> for FoodEffect {
+    fn from(effect: ffxiv_items::FoodEffect) -> Self {
+        Self {
+            stat: effect.stat,
+            is_relative: effect.is_relative,
+            value: effect.value,
+            max: effect.max,
+            value_hq: effect.value_hq,
+            max_hq: effect.max_hq,
+        }
+    }
+}
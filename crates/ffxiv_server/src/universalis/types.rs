@@ -13,6 +13,9 @@ pub struct Input {
     pub sell_to: String,
     pub retain_num_days: Option<f32>,
     pub is_compressed: Option<bool>,
+    /// The item ids of the master recipe books the user has read. Recipes
+    /// needing any other book are left out. Every recipe is kept if unset.
+    pub owned_books: Option<Vec<u32>>,
 }
 
 ////////////////////////////////////////////////////////////
@@ -67,6 +70,41 @@ pub struct ItemInfo {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub recipe: Option<Recipe>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stats: Vec<Stat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub food: Option<Food>,
+    pub is_marketable: bool,
+}
+
+#[derive(Serialize)]
+pub struct Stat {
+    pub name: String,
+    pub value: i32,
+}
+
+#[derive(Serialize)]
+pub struct Food {
+    pub kind: FoodKind,
+    pub effects: Vec<FoodEffect>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FoodKind {
+    Meal,
+    Medicine,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoodEffect {
+    pub stat: String,
+    pub is_relative: bool,
+    pub value: i32,
+    pub max: i32,
+    pub value_hq: i32,
+    pub max_hq: i32,
 }
 
 #[derive(Serialize)]
@@ -74,6 +112,38 @@ pub struct Recipe {
     pub inputs: Vec<Ingredient>,
     pub outputs: u32,
     pub level: u32,
+    pub kind: RecipeKind,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub phases: Vec<CraftPhase>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub book: Option<RecipeBook>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecipeBook {
+    pub item_id: u32,
+    pub name: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RecipeKind {
+    Crafter,
+    CompanyWorkshop,
+}
+
+#[derive(Serialize)]
+pub struct CraftPhase {
+    pub supplies: Vec<PhaseSupply>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseSupply {
+    pub item_id: u32,
+    pub set_quantity: u32,
+    pub sets_required: u32,
 }
 
 #[derive(Serialize)]
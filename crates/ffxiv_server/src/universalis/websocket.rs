@@ -1,3 +1,4 @@
+use std::collections::BTreeSet;
 use std::io::Write;
 use std::sync::Arc;
 
@@ -9,41 +10,55 @@ use axum::{
     },
     response::IntoResponse,
 };
-use ffxiv_items::ItemDB;
+use ffxiv_items::{ItemDBError, ItemRepository};
 use ffxiv_universalis::Processor;
 use flate2::{write::GzEncoder, Compression};
 use mock_traits::FileDownloader;
 use uuid::Uuid;
 
-use super::{send_recipes, wait_for_universalis, Input};
+use super::{remove_unowned_recipes, send_recipes, wait_for_universalis, Input};
+
+/// Close frames are control frames, whose payload is at most 125 bytes, two
+/// of which are the close code.
+const MAX_CLOSE_REASON_BYTES: usize = 123;
 
 ////////////////////////////////////////////////////////////
 
 #[allow(clippy::unused_async)]
-pub async fn universalis_websocket<F: FileDownloader + 'static>(
+pub async fn universalis_websocket<F: FileDownloader + 'static, R: ItemRepository + 'static>(
     ws: WebSocketUpgrade,
-    State((universalis_processor, db)): State<(Processor, Arc<ItemDB>)>,
+    State((universalis_processor, db)): State<(Processor, Arc<R>)>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket::<F>(socket, universalis_processor, db.clone()))
+    ws.on_upgrade(move |socket| handle_socket::<F, R>(socket, universalis_processor, db.clone()))
 }
 
 ////////////////////////////////////////////////////////////
 
-async fn handle_socket<F: FileDownloader>(
+async fn handle_socket<F: FileDownloader, R: ItemRepository>(
     mut socket: WebSocket,
     universalis_processor: Processor,
-    db: Arc<ItemDB>,
+    db: Arc<R>,
 ) {
-    async fn inner<F: FileDownloader>(
+    async fn inner<F: FileDownloader, R: ItemRepository>(
         socket: &mut WebSocket,
         universalis_processor: Processor,
-        db: Arc<ItemDB>,
+        db: Arc<R>,
     ) -> Result<()> {
         let server_uuid = Uuid::new_v4().to_string();
 
         let payload: Input = fetch_payload(socket).await?;
         log::info!(target: "ffxiv_server", "New request for '{}'", payload.query);
-        let (top_ids, all_ids, items) = db.all_info_from_query(&payload.query).await?;
+        let (mut top_ids, mut all_ids, mut items) = db.all_info_from_query(&payload.query).await?;
+        if let Some(owned_books) = &payload.owned_books {
+            all_ids = remove_unowned_recipes(owned_books, &mut top_ids, &mut items);
+        }
+        // Items that can't be sold would only come back empty from universalis
+        let marketable = items
+            .iter()
+            .filter(|item| item.is_marketable)
+            .map(|item| item.id)
+            .collect::<BTreeSet<_>>();
+        all_ids.retain(|id| marketable.contains(id));
         let is_compressed = payload.is_compressed.unwrap_or(false);
         send_recipes(socket, &top_ids, items, is_compressed).await?;
         wait_for_universalis::<F>(
@@ -57,17 +72,50 @@ async fn handle_socket<F: FileDownloader>(
         Ok(())
     }
 
-    if let Err(err) = inner::<F>(&mut socket, universalis_processor, db).await {
+    if let Err(err) = inner::<F, R>(&mut socket, universalis_processor, db).await {
         log::error!(target: "ffxiv_server", "WebSocket exiting: {err:}");
-        let _ = socket
-            .send(Message::Close(Some(CloseFrame {
-                code: close_code::ERROR,
-                reason: err.to_string().into(),
-            })))
-            .await;
+        let _ = socket.send(Message::Close(Some(close_frame(&err)))).await;
+    }
+}
+
+/// The close frame that tells the client why its request failed.
+fn close_frame(err: &anyhow::Error) -> CloseFrame<'static> {
+    let (code, reason) = match ItemDBError::find(err) {
+        // Rejected queries are the client's to fix, rather than a server error
+        Some(err) if err.is_query() => (close_code::POLICY, err.to_string()),
+        // The details of these are for the server's logs, not the client
+        Some(ItemDBError::Connection { .. }) => (
+            close_code::AGAIN,
+            "The item database is unavailable, please try again later".to_string(),
+        ),
+        Some(ItemDBError::RateLimited { .. }) => (
+            close_code::AGAIN,
+            "Github is rate limiting the server, please try again later".to_string(),
+        ),
+        Some(ItemDBError::Import { .. }) => (
+            close_code::ERROR,
+            "The item database couldn't be loaded".to_string(),
+        ),
+        Some(_) | None => (close_code::ERROR, err.to_string()),
+    };
+    CloseFrame {
+        code,
+        reason: truncate_reason(reason).into(),
     }
 }
 
+/// Longer reasons would fail to send, leaving the client without one at all.
+fn truncate_reason(mut reason: String) -> String {
+    if reason.len() > MAX_CLOSE_REASON_BYTES {
+        let end = (0..=MAX_CLOSE_REASON_BYTES)
+            .rev()
+            .find(|&end| reason.is_char_boundary(end))
+            .unwrap_or_default();
+        reason.truncate(end);
+    }
+    reason
+}
+
 async fn fetch_payload(socket: &mut WebSocket) -> Result<Input> {
     let Some(Ok(Message::Text(payload_str))) = socket.recv().await else {
         bail!("Invalid input recieved from websocket");
@@ -89,6 +137,179 @@ pub async fn write_message<S: Into<String>>(
         socket.send(Message::Binary(bytes)).await?;
     } else {
         socket.send(Message::Text(message.into())).await?;
-    };
+    }
     Ok(())
 }
+
+////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use ffxiv_items::{ItemInfo, MemoryItemRepository};
+    use ffxiv_universalis::json::{
+        HistoryView, ItemListingView, ListingView, MultipleHistoryView, MultipleListingView,
+    };
+    use futures::{future::BoxFuture, FutureExt, SinkExt, StreamExt};
+    use serde_json::{json, Value};
+    use tokio_tungstenite::{connect_async, tungstenite};
+
+    use super::*;
+    use crate::server::Server;
+
+    /// Answers universalis requests with a listing & a sale of each item.
+    struct MockDownloader;
+
+    impl FileDownloader for MockDownloader {
+        fn download(url: &str) -> BoxFuture<'_, Result<String>> {
+            async move {
+                let path = url.split('?').next().unwrap_or_default();
+                let ids = path.rsplit('/').next().unwrap_or_default().split(',');
+                let listing = |world_name: Option<String>| ItemListingView {
+                    price_per_unit: 100,
+                    hq: false,
+                    quantity: 1,
+                    last_review_time: Some(0),
+                    timestamp: Some(0),
+                    world_name,
+                    retainer_name: Some("Retainer".into()),
+                };
+
+                let json = if path.contains("/history/") {
+                    serde_json::to_string(&MultipleHistoryView {
+                        items: ids
+                            .map(|id| {
+                                let entries = vec![listing(Some("Halicarnassus".into()))];
+                                (id.to_string(), HistoryView { entries })
+                            })
+                            .collect(),
+                    })
+                } else {
+                    serde_json::to_string(&MultipleListingView {
+                        items: ids
+                            .map(|id| {
+                                let listings = vec![listing(None)];
+                                (id.to_string(), ListingView { listings })
+                            })
+                            .collect(),
+                    })
+                };
+                Ok(json?)
+            }
+            .boxed()
+        }
+    }
+
+    fn item(id: u32, name: &str) -> ItemInfo {
+        ItemInfo {
+            id,
+            name: name.into(),
+            recipe: None,
+            stats: Vec::new(),
+            food: None,
+            is_marketable: true,
+        }
+    }
+
+    /// Serves the repository-backed routes on a free port, returning the
+    /// websocket's url.
+    fn serve() -> Result<String> {
+        let items = MemoryItemRepository::from_items(vec![
+            item(5111, "Iron Ore"),
+            item(5057, "Iron Ingot"),
+            item(5094, "Iron Rivets"),
+            item(5380, "Maple Lumber"),
+        ]);
+        let processor = Processor::new();
+        tokio::spawn(processor.async_processor());
+        let app = Server::repository_router::<MockDownloader, _>(processor, Arc::new(items));
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let url = format!("ws://{}/universalis", listener.local_addr()?);
+        tokio::spawn(axum::Server::from_tcp(listener)?.serve(app.into_make_service()));
+        Ok(url)
+    }
+
+    fn payload(query: &str) -> tungstenite::Message {
+        let payload = json!({
+            "query": query,
+            "purchaseFrom": "Dynamis",
+            "sellTo": "Halicarnassus",
+        });
+        tungstenite::Message::Text(payload.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_websocket_recipes() -> Result<()> {
+        let (mut socket, _) = connect_async(serve()?).await?;
+        socket.send(payload(":name ^Iron (Ore|Rivets)")).await?;
+
+        let Some(tungstenite::Message::Text(message)) = socket.next().await.transpose()? else {
+            bail!("Expected the recipes first");
+        };
+        let recipes: Value = serde_json::from_str(&message)?;
+        assert_eq!(recipes["recipe"]["topIds"], json!([5094, 5111]));
+        let mut names = recipes["recipe"]["itemInfo"]
+            .as_object()
+            .unwrap()
+            .values()
+            .map(|item| item["name"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["Iron Ore", "Iron Rivets"]);
+
+        let mut listed_ids = Vec::new();
+        while let Some(tungstenite::Message::Text(message)) = socket.next().await.transpose()? {
+            let message: Value = serde_json::from_str(&message)?;
+            if message == "done" {
+                break;
+            }
+            if let Some(listings) = message["success"]["listings"].as_object() {
+                listed_ids.extend(listings.keys().cloned());
+            }
+        }
+        listed_ids.sort();
+        assert_eq!(listed_ids, vec!["5094", "5111"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_websocket_rejected_query() -> Result<()> {
+        let url = serve()?;
+        for (query, code) in [
+            ("@unknown", 1008),
+            (":gcsupply CUL", 1008),
+            (":name (Iron", 1008),
+            (":name ^(a+)+$", 1008),
+        ] {
+            let (mut socket, _) = connect_async(&url).await?;
+            socket.send(payload(query)).await?;
+            let Some(tungstenite::Message::Close(Some(frame))) = socket.next().await.transpose()?
+            else {
+                bail!("Expected '{query}' to close the socket");
+            };
+            assert_eq!(u16::from(frame.code), code, "{query}");
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_websocket_long_close_reason() -> Result<()> {
+        let pattern = format!("({}+)+$", "a".repeat(95));
+        assert_eq!(pattern.len(), 100);
+
+        let (mut socket, _) = connect_async(serve()?).await?;
+        socket.send(payload(&format!(":name {pattern}"))).await?;
+        let Some(tungstenite::Message::Close(Some(frame))) = socket.next().await.transpose()?
+        else {
+            bail!("Expected the long pattern to close the socket");
+        };
+        assert_eq!(u16::from(frame.code), 1008);
+        assert!(frame.reason.len() <= MAX_CLOSE_REASON_BYTES);
+        assert!(frame.reason.starts_with("Query too expensive"));
+
+        // Multi-byte characters aren't split
+        let frame = close_frame(&anyhow::anyhow!("é".repeat(100)));
+        assert_eq!(frame.reason, "é".repeat(61));
+        Ok(())
+    }
+}
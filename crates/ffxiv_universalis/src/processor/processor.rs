@@ -96,9 +96,8 @@ impl Processor {
     fn fetch_and_process_market_info<F: FileDownloader>(data: ProcessorData) -> PacketGroup {
         let id_chunks = data.id_chunks();
 
-        let mut chunk_id = 1;
         let mut handles = Vec::new();
-        for ids in &id_chunks {
+        for (chunk_id, ids) in (1..).zip(id_chunks.iter()) {
             let listings = Request::<F>::new(
                 data.clone(),
                 data.purchase_from.clone(),
@@ -118,7 +117,6 @@ impl Processor {
             .process_listing();
 
             handles.push((listings, history));
-            chunk_id += 1;
         }
 
         let mut async_packets = Vec::new();
@@ -30,8 +30,7 @@ impl ProcessorData {
         sell_to: String,
         retain_num_days: f32,
     ) -> Self {
-        let num_requests =
-            ((ids.len() + MAX_CHUNK_SIZE - 1) / MAX_CHUNK_SIZE) * purchase_from.len();
+        let num_requests = ids.len().div_ceil(MAX_CHUNK_SIZE) * purchase_from.len();
 
         ProcessorData {
             uuid: Uuid::new_v4().to_string(),
@@ -67,7 +67,7 @@ impl UniversalisJson {
             let id = id.parse::<u32>()?;
             let entry = map.entry(id).or_default();
             entry.append(&mut listings);
-            entry.sort_by(|a, b| a.price.cmp(&b.price));
+            entry.sort_by_key(|listing| listing.price);
         }
 
         Ok(map)
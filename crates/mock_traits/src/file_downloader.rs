@@ -9,25 +9,94 @@ use tokio::time::sleep;
 
 pub trait FileDownloader {
     fn download(url: &str) -> BoxFuture<'_, Result<String>>;
+
+    /// Downloads a file with additional request headers, returning the status
+    /// code & response headers along with the body. By default, the headers
+    /// are ignored and the download is assumed to have succeeded.
+    fn download_with_headers<'a>(
+        url: &'a str,
+        _headers: &'a [(&'a str, &'a str)],
+    ) -> BoxFuture<'a, Result<DownloadResponse>> {
+        async move {
+            Ok(DownloadResponse {
+                status: 200,
+                headers: Vec::new(),
+                body: Self::download(url).await?,
+            })
+        }
+        .boxed()
+    }
+}
+
+/// The response for [FileDownloader::download_with_headers].
+pub struct DownloadResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl DownloadResponse {
+    /// Returns the value of a response header, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
 }
 
 ////////////////////////////////////////////////////////////
 
 pub struct ReqwestDownloader;
 
+impl ReqwestDownloader {
+    fn client() -> Result<reqwest::Client> {
+        let mut headers = HeaderMap::new();
+        headers.insert("User-Agent", HeaderValue::from_static("Tiny Rust Program"));
+        Ok(reqwest::ClientBuilder::new()
+            .default_headers(headers)
+            .build()?)
+    }
+}
+
 impl FileDownloader for ReqwestDownloader {
     fn download(url: &str) -> BoxFuture<'_, Result<String>> {
         async fn inner(url: &str) -> Result<String> {
-            let mut headers = HeaderMap::new();
-            headers.insert("User-Agent", HeaderValue::from_static("Tiny Rust Program"));
-            let client = reqwest::ClientBuilder::new()
-                .default_headers(headers)
-                .build()?;
+            let client = ReqwestDownloader::client()?;
             let request = client.get(url).build()?;
             Ok(client.execute(request).await?.text().await?)
         }
         inner(url).boxed()
     }
+
+    fn download_with_headers<'a>(
+        url: &'a str,
+        headers: &'a [(&'a str, &'a str)],
+    ) -> BoxFuture<'a, Result<DownloadResponse>> {
+        async fn inner(url: &str, headers: &[(&str, &str)]) -> Result<DownloadResponse> {
+            let client = ReqwestDownloader::client()?;
+            let mut request = client.get(url);
+            for (key, value) in headers {
+                request = request.header(*key, *value);
+            }
+            let response = client.execute(request.build()?).await?;
+            let status = response.status().as_u16();
+            let headers = response
+                .headers()
+                .iter()
+                .filter_map(|(key, value)| {
+                    Some((key.to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect();
+            let body = response.text().await?;
+            Ok(DownloadResponse {
+                status,
+                headers,
+                body,
+            })
+        }
+        inner(url, headers).boxed()
+    }
 }
 
 ////////////////////////////////////////////////////////////
@@ -50,6 +119,25 @@ impl<const CHANCE: u32, F: FileDownloader + 'static> FileDownloader
         }
         inner::<F>(url, CHANCE).boxed()
     }
+
+    fn download_with_headers<'a>(
+        url: &'a str,
+        headers: &'a [(&'a str, &'a str)],
+    ) -> BoxFuture<'a, Result<DownloadResponse>> {
+        async move {
+            use rand::Rng;
+            if rand::thread_rng().gen::<u32>() % 100 < CHANCE {
+                Ok(DownloadResponse {
+                    status: 200,
+                    headers: Vec::new(),
+                    body: "".into(),
+                })
+            } else {
+                F::download_with_headers(url, headers).await
+            }
+        }
+        .boxed()
+    }
 }
 
 ////////////////////////////////////////////////////////////
@@ -66,4 +154,15 @@ impl<const DELAY: u32, F: FileDownloader + 'static> FileDownloader for DelayedDo
         }
         inner::<F>(url, DELAY).boxed()
     }
+
+    fn download_with_headers<'a>(
+        url: &'a str,
+        headers: &'a [(&'a str, &'a str)],
+    ) -> BoxFuture<'a, Result<DownloadResponse>> {
+        async move {
+            sleep(Duration::from_millis(DELAY as u64)).await;
+            F::download_with_headers(url, headers).await
+        }
+        .boxed()
+    }
 }
@@ -1,3 +1,5 @@
 mod file_downloader;
 
-pub use file_downloader::{DelayedDownloader, FaultyDownloader, FileDownloader, ReqwestDownloader};
+pub use file_downloader::{
+    DelayedDownloader, DownloadResponse, FaultyDownloader, FileDownloader, ReqwestDownloader,
+};
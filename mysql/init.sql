@@ -0,0 +1,3 @@
+-- New datasets are built in a staging database, then swapped in, so the
+-- server's user needs privileges on it as well as the main database
+GRANT ALL PRIVILEGES ON `ffxiv_items_staging`.* TO 'user'@'%';